@@ -0,0 +1,9 @@
+-- Death hooks, on_death runs its function whenever something dies
+
+on_death(function(death)
+    -- Whoever kills a zombie gets a mouthful of its rot
+    if death.name == "zombie" and death.killer then
+        message("The zombie bursts in a cloud of rot!")
+        status(death.killer, "Poisoned", 3, 1)
+    end
+end)
@@ -0,0 +1,11 @@
+spell {
+    name = "blink",
+    description = "Step to a spot in sight",
+    cost = 4,
+    range = 5,
+    shape = "tile",
+    cast = function(target)
+        teleport(target.caster, target.x, target.y)
+        message("You blink.")
+    end
+}
@@ -0,0 +1,20 @@
+-- Burns everything around where it lands, the caster included
+spell {
+    name = "fireball",
+    description = "Fire in a small burst",
+    cost = 8,
+    range = 6,
+    shape = "area",
+    radius = 1,
+    cast = function(target)
+        message("Flames burst out!")
+
+        for _, victim in ipairs(target.targets) do
+            damage(victim.id, math.random(4, 8), "Fire")
+
+            if math.random() < 0.3 then
+                status(victim.id, "Burning", 3, 1)
+            end
+        end
+    end
+}
@@ -0,0 +1,9 @@
+spell {
+    name = "heal",
+    description = "Close your wounds",
+    cost = 5,
+    shape = "self",
+    cast = function(target)
+        heal(target.caster, math.random(8, 14))
+    end
+}
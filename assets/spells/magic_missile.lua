@@ -0,0 +1,14 @@
+-- Never misses, but only the first thing in the way feels it
+spell {
+    name = "magic missile",
+    description = "A dart of force",
+    cost = 3,
+    range = 8,
+    shape = "bolt",
+    glyph = "*",
+    cast = function(target)
+        for _, victim in ipairs(target.targets) do
+            damage(victim.id, math.random(2, 6), "Physical")
+        end
+    end
+}
@@ -0,0 +1,12 @@
+-- The zombie fights for whoever raised it
+spell {
+    name = "raise dead",
+    description = "Call up a zombie to fight for you",
+    cost = 10,
+    range = 3,
+    shape = "tile",
+    cast = function(target)
+        summon("zombie", target.x, target.y)
+        message("A zombie claws its way up out of the ground.")
+    end
+}
@@ -0,0 +1,74 @@
+// Game data files, read from the assets folder with a copy built into the game to fall back on
+
+use std::path::{Path, PathBuf};
+
+/**
+ * Where the data files live
+ * ROGUE_ASSETS names the assets folder outright, otherwise it's the first one found
+ * next to the executable or above it, then the one in the tree the game was built from
+ */
+pub fn dir() -> Result<PathBuf, String> {
+    if let Some(assets) = std::env::var_os("ROGUE_ASSETS") {
+        let dir = PathBuf::from(assets);
+
+        return match dir.is_dir() {
+            true => Ok(dir),
+            false => Err(format!("ROGUE_ASSETS is not a folder: {}", dir.display()))
+        };
+    }
+
+    let mut candidates: Vec<PathBuf> = std::env::current_exe()
+        .map(|exe| exe.ancestors().skip(1).map(|dir| dir.join("assets")).collect())
+        .unwrap_or_default();
+
+    candidates.push(Path::new(env!("CARGO_MANIFEST_DIR")).join("assets"));
+
+    candidates.iter()
+        .find(|dir| dir.is_dir())
+        .cloned()
+        .ok_or_else(|| {
+            let searched: Vec<String> = candidates.iter().map(|dir| dir.display().to_string()).collect();
+            format!("No assets folder found, looked in {}", searched.join(", "))
+        })
+}
+
+/**
+ * Parse a data file from the assets folder
+ * When it can't be found, read or parsed the built in copy is used instead
+ */
+pub fn load<T, E, F>(name: &str, builtin: &str, parse: F) -> T
+    where E: std::fmt::Display,
+          F: Fn(&str) -> Result<T, E>
+{
+    let loaded = dir()
+        .and_then(|dir| std::fs::read_to_string(dir.join(name)).map_err(|err| format!("can't read {}: {}", name, err)))
+        .and_then(|buffer| parse(&buffer).map_err(|err| format!("invalid {}: {}", name, err)));
+
+    match loaded {
+        Ok(value) => value,
+        Err(err) => {
+            warn!("Using the built in {}, {}", name, err);
+
+            parse(builtin).unwrap_or_else(|err| panic!("Invalid built in {}: {}", name, err))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_read_files_from_the_assets_folder() {
+        let tiles: serde_json::Value = load("tiles.json", "[]", |buffer| serde_json::from_str(buffer));
+
+        assert!(tiles.as_array().map_or(false, |tiles| !tiles.is_empty()));
+    }
+
+    #[test]
+    fn it_should_fall_back_to_the_built_in_copy() {
+        let numbers: Vec<i32> = load("no-such-file.json", "[1, 2]", |buffer| serde_json::from_str(buffer));
+
+        assert_eq!(numbers, vec![1, 2]);
+    }
+}
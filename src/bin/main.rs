@@ -17,64 +17,32 @@ use rogue::{
     EntityManager, 
     Map,
 };
+use rogue::map::MapGenerator;
+use rogue::GameState;
 
 use rogue::systems::*;
-use rogue::map::{simple_map_gen};
 use rogue::components::{self, Position, Input, Render, RenderLayer, Collidable, Walk};
 use rogue::renderer::*;
 
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
 use std::time::{Instant, Duration};
 
-fn create_map_entities(map: &Map, em: &mut EntityManager) {
-    // Create tile entity prototypes
-
-    for y in 0..map.height() {
-        for x in 0..map.width() {
-            let tile = em.create_entity();
-
-            let cell = map.get_cell_ref(x as i32, y as i32);
-
-            let glyph = cell.glyph;
-
-            em.add_component(tile, Render { glyph: glyph, layer: RenderLayer::Map });
-            em.add_component(tile, Position{ x: x as i32, y: y as i32});
-
-            if glyph == '-' || glyph == '|' || glyph == '#' {
-                em.add_component(tile, Collidable);
-            }
-        }
-    }
-}
-
-fn populate_map(map: &Map, em: &mut EntityManager) {
-    // let (goblin, _) = em.get_entity_by_name("Goblin").unwrap();
-
-    // Create a monster at the center of each room
-    for room in &map.rooms {
-        let center = room.center();
-        rogue::monsters::create_zombie(em, center.0, center.1);
-
-        // let g_entity = em.create_entity();
-
-        // em.add_component(g_entity, components::Prototype { prototype: goblin });
-        // em.add_component(g_entity, components::Position { x: center.0 + 1, y: center.1 + 1 });
-
-        // em.find_entity(components::Metaname("Goblin"));
-    }
-}
-
 struct Game {
     // script_manager: ScriptManager,
     renderer: CursesRenderer,
     entity_manager: EntityManager,
+    interface_manager: SystemManager,
     system_manager: SystemManager,
     render_system: RenderSystem,
     input_system: InputSystem,
     game_state: GameState,
     renderer: R,
     headless: bool,
+    debug: bool,
+    // Forces one layout on every level
+    map_generator: Option<MapGenerator>,
     initialized: bool,
     running: bool
 }
@@ -87,10 +55,14 @@ impl<R> Game<R>
             input_system: InputSystem::new(),
             render_system: RenderSystem::new(),
             entity_manager: EntityManager::new(),
+            interface_manager: SystemManager::new(),
             system_manager: SystemManager::new(),
             renderer: CursesRenderer::new(),
             // script_manager: ScriptManager::new(),
+            game_state: GameState::Running,
             headless: false,
+            debug: false,
+            map_generator: None,
             initialized: false,
             running: false
         }
@@ -127,23 +99,65 @@ impl<R> Game<R>
             info!("Headless mode");
             self.headless = true;
         }
+
+        if args.iter().any(|arg| arg == "--debug") {
+            info!("Debug mode");
+            self.debug = true;
+        }
+
+        // --generator=rooms|caves|maze|ruins
+        for arg in args.iter().filter(|arg| arg.starts_with("--generator=")) {
+            match arg["--generator=".len()..].parse() {
+                Ok(generator) => {
+                    info!("Map generator {:?}", generator);
+                    self.map_generator = Some(generator);
+                }
+                Err(err) => error!("{}", err)
+            }
+        }
     }
 
     fn register_game_systems(&mut self) {
+        // One lua host for every system that runs scripts
+        let scripts = Rc::new(rogue::scripts::load());
+
+        let interface_manager = &mut self.interface_manager;
+        interface_manager.register_system(LevelUpSystem);
+        interface_manager.register_system(SpellSystem::new(scripts.clone()));
+        interface_manager.register_system(RangedSystem);
+        interface_manager.register_system(TravelCursorSystem);
+
         let system_manager = &mut self.system_manager;
         system_manager.register_system(Chronos::new());
-        system_manager.register_system(TurnSystem::new());
+        system_manager.register_system(VisibilitySystem);
+        system_manager.register_system(LightingSystem);
         system_manager.register_system(RandomWalkAiSystem);
+        system_manager.register_system(DijkstraAiSystem::new());
+        system_manager.register_system(TravelSystem);
         system_manager.register_system(WalkSystem);
+        system_manager.register_system(AutoExploreSystem::new());
+        system_manager.register_system(TurnSystem::new());
+        system_manager.register_system(StatusSystem);
+        system_manager.register_system(ManaSystem);
+        system_manager.register_system(DungeonSystem);
+        system_manager.register_system(ShootSystem);
+        system_manager.register_system(SpatialIndexSystem);
+        system_manager.register_system(CastSystem::new(scripts.clone()));
         system_manager.register_system(CollisionSystem);
+        system_manager.register_system(DoorSystem);
+        system_manager.register_system(ChestSystem);
+        system_manager.register_system(PickupSystem);
+        system_manager.register_system(SwapSystem);
         system_manager.register_system(AttackSystem);
         system_manager.register_system(DamageSystem);
         system_manager.register_system(MoveSystem);
+        system_manager.register_system(TerrainSystem::new());
         system_manager.register_system(LootSystem);
         system_manager.register_system(EventLogSystem);
-        system_manager.register_system(Reaper);
+        system_manager.register_system(Reaper::new(scripts.clone()));
         system_manager.register_system(Janitor);
 
+        self.interface_manager.mount(&mut self.entity_manager);
         self.system_manager.mount(&mut self.entity_manager);
     }
 
@@ -154,14 +168,12 @@ impl<R> Game<R>
 
         let map_width = 100;
         let map_height = 100;
+        let max_depth = 10;
 
-        let map = simple_map_gen(map_width, map_height);
+        let player_pos = rogue::dungeon::create_dungeon(&mut self.entity_manager, map_width, map_height, max_depth, self.map_generator);
 
-        info!("Map generated");
+        info!("Dungeon generated");
 
-        let player_pos = map.rooms.first().unwrap().center();
-
-        create_map_entities(&map, &mut self.entity_manager);
         let player_components = self.create_player("gromash", player_pos.0, player_pos.1);
 
         let player = self.entity_manager.create_entity();
@@ -171,11 +183,38 @@ impl<R> Game<R>
             self.entity_manager.add_boxed_component(player, component);
         }
 
+        if self.debug {
+            self.give_debug_kit(player);
+        }
+
+        let darts = rogue::items::create_ranged_weapon(&mut self.entity_manager, "darts", '-', Some(components::Ammo { kind: "dart".to_string(), count: 5 }));
+        get_component!(mut, self.entity_manager, player, components::Inventory).unwrap().add_item(darts);
+
         info!("Player created");
+    }
+
+    /// Extra gear for trying things out, only with --debug
+    fn give_debug_kit(&mut self, player: rogue::Entity) {
+        let key = rogue::items::create_key(&mut self.entity_manager, "iron key", "iron");
+        get_component!(mut, self.entity_manager, player, components::Inventory).unwrap().add_item(key);
+
+        let bow = rogue::items::create_ranged_weapon(&mut self.entity_manager, "short bow", ')', None);
+        get_component!(mut, self.entity_manager, player, components::Inventory).unwrap().add_item(bow);
+
+        let arrows = rogue::items::create_ammo(&mut self.entity_manager, "arrows", "arrow", 20);
+        get_component!(mut, self.entity_manager, player, components::Inventory).unwrap().add_item(arrows);
 
-        populate_map(&map, &mut self.entity_manager);
+        self.entity_manager.add_component(player, components::RangedWeapon { name: "short bow".to_string() });
 
-        info!("Map populated");
+        self.entity_manager.add_component(player, components::Spellbook {
+            spells: vec![
+                "magic missile".to_string(),
+                "fireball".to_string(),
+                "heal".to_string(),
+                "blink".to_string(),
+                "raise dead".to_string()
+            ]
+        });
     }
 
     fn create_player(
@@ -192,10 +231,22 @@ impl<R> Game<R>
             Box::new(Position{ x: x, y: y}),
             Box::new(Collidable),
             Box::new(components::Health { health: 100, max_health: 100 }),
+            Box::new(components::Attributes::new(12, 12, 12, 10, 10, 10)),
+            Box::new(components::Weapon { name: "short sword".to_string() }),
+            Box::new(components::Armor { reduction: 1, evasion: 5 }),
+            Box::new(components::RangedWeapon { name: "darts".to_string() }),
             Box::new(Walk::new()),
             Box::new(components::Log::new()),
+            Box::new(components::Inventory::new()),
             Box::new(components::Energy { amount: 0 }),
-            Box::new(components::Speed { amount: 10 })
+            Box::new(components::Speed { amount: 10 }),
+            // Lantern
+            Box::new(components::LightSource { radius: 5, color: [255, 220, 150] }),
+            Box::new(components::Stealth { skill: 20 }),
+            Box::new(components::Faction { name: "player".to_string() }),
+            Box::new(components::Experience::new()),
+            Box::new(components::Mana::new(20, 3)),
+            Box::new(components::Spellbook { spells: vec!["magic missile".to_string()] })
         ]
     }
 
@@ -242,7 +293,24 @@ impl<R> Game<R>
     }
     
     fn update(&mut self, elapsed: Duration) {
-        self.system_manager.process_systems(&mut self.entity_manager);
+        if self.game_state != GameState::Running {
+            return;
+        }
+
+        self.interface_manager.process_systems(&mut self.entity_manager);
+
+        // Opening a menu or moving a cursor doesn't take a turn
+        if !rogue::menu::world_waits(&mut self.entity_manager) {
+            self.system_manager.process_systems(&mut self.entity_manager);
+        }
+
+        let player_died = self.entity_manager.get_entity_by_name("Player")
+            .map_or(false, |player| self.entity_manager.has_component(player, components::Dead::get_component_type()));
+
+        if player_died {
+            info!("Player died, game over");
+            self.game_state = GameState::GameOver;
+        }
     }
 
     fn render_main_menu(&self) {
@@ -262,7 +330,10 @@ impl<R> Game<R>
             GameState::Running => {
                 self.render_system.process(&mut self.entity_manager);
             }
-            _ => {}
+            GameState::GameOver => {
+                // The render system covers the level with the death screen
+                self.render_system.process(&mut self.entity_manager);
+            }
         }
     }
 
@@ -303,6 +374,7 @@ impl<R> Game<R>
 
     fn cleanup(&mut self) {
         if !self.headless {
+            self.interface_manager.unmount(&mut self.entity_manager);
             self.system_manager.unmount(&mut self.entity_manager);
 
             self.render_system.unmount();
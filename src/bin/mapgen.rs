@@ -0,0 +1,107 @@
+// Generate maps without playing, for reviewing generator changes
+//
+// mapgen <rooms|caves|maze|ruins> [--count N] [--seed S] [--size WxH] [--out DIR] [--tileset PNG]
+
+extern crate rand;
+extern crate rogue;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use rogue::map::{MapGenerator, Tileset, export_map};
+
+const USAGE: &str = "mapgen <rooms|caves|maze|ruins> [--count N] [--seed S] [--size WxH] [--out DIR] [--tileset PNG]";
+
+#[derive(Debug)]
+struct Options {
+    generator: MapGenerator,
+    count: u64,
+    seed: u64,
+    width: usize,
+    height: usize,
+    out: String,
+    tileset: Option<String>
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let generator = args.get(0).ok_or("No generator given")?.parse()?;
+
+    let mut options = Options {
+        generator: generator,
+        count: 1,
+        seed: 0,
+        width: 100,
+        height: 100,
+        out: ".".to_string(),
+        tileset: Some("assets/gfx/Aesomatica_16x16.png".to_string())
+    };
+
+    let mut rest = args[1..].iter();
+
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or(format!("No value for {}", flag))?;
+        let invalid = |_| format!("Invalid value {:?} for {}", value, flag);
+
+        match flag.as_str() {
+            "--count" => options.count = value.parse().map_err(invalid)?,
+            "--seed" => options.seed = value.parse().map_err(invalid)?,
+            "--size" => {
+                let mut size = value.split('x').map(|n| n.parse::<usize>());
+
+                match (size.next(), size.next()) {
+                    (Some(Ok(width)), Some(Ok(height))) => {
+                        options.width = width;
+                        options.height = height;
+                    }
+                    _ => return Err(format!("Invalid size {:?}, expected WxH", value))
+                }
+            }
+            "--out" => options.out = value.clone(),
+            "--tileset" => options.tileset = if value == "none" { None } else { Some(value.clone()) },
+            _ => return Err(format!("Unknown option {}", flag))
+        }
+    }
+
+    Ok(options)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            std::process::exit(1);
+        }
+    };
+
+    let tileset = options.tileset.as_ref().map(|filename| {
+        Tileset::load(filename, 16).unwrap_or_else(|err| {
+            eprintln!("Could not load tileset {}: {}", filename, err);
+            std::process::exit(1);
+        })
+    });
+
+    if let Err(err) = std::fs::create_dir_all(&options.out) {
+        eprintln!("Could not create {}: {}", options.out, err);
+        std::process::exit(1);
+    }
+
+    let name = format!("{:?}", options.generator).to_lowercase();
+
+    for seed in options.seed..(options.seed + options.count) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let map = options.generator.generate_with_rng(options.width, options.height, &mut rng);
+
+        let path = format!("{}/{}_{}", options.out, name, seed);
+
+        match export_map(&map, &path, tileset.as_ref()) {
+            Ok(()) => println!("{} ({} regions)", path, map.regions().len()),
+            Err(err) => {
+                eprintln!("Could not write {}: {}", path, err);
+                std::process::exit(1);
+            }
+        }
+    }
+}
@@ -30,6 +30,7 @@ use rogue::map::{simple_map_gen};
 use rogue::components::{self, Position, Input, Render, RenderLayer, Collidable, Walk};
 use rogue::renderer::*;
 
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
 use std::time::{Instant, Duration};
@@ -240,6 +241,8 @@ impl<R> Game<R>
     }
 
     fn load_game_systems(&mut self) {
+        let scripts = Rc::new(rogue::scripts::load());
+
         let system_manager = &mut self.system_manager;
         system_manager.register_system(Chronos::new());
         system_manager.register_system(TurnSystem::new());
@@ -251,7 +254,7 @@ impl<R> Game<R>
         system_manager.register_system(MoveSystem);
         system_manager.register_system(LootSystem);
         system_manager.register_system(EventLogSystem);
-        system_manager.register_system(Reaper);
+        system_manager.register_system(Reaper::new(scripts.clone()));
         system_manager.register_system(Janitor);
 
         self.system_manager.mount(&mut self.entity_manager);
@@ -1,12 +1,15 @@
 use cgmath::Point2;
 
-fn get_line(a: Point2<u32>, b: Point2<u32>) -> Vec<Point2<u32>> {
+/**
+ * All points on the line from a to b, both ends included, ordered from a
+ */
+pub fn get_line(a: Point2<i32>, b: Point2<i32>) -> Vec<Point2<i32>> {
   let mut points = Vec::new();
 
-  let mut x1 = a.x as i32;
-  let mut y1 = a.y as i32;
-  let mut x2 = b.x as i32;
-  let mut y2 = b.y as i32;
+  let mut x1 = a.x;
+  let mut y1 = a.y;
+  let mut x2 = b.x;
+  let mut y2 = b.y;
 
   let is_steep = (y2 - y1).abs() > (x2 - x1).abs();
   if is_steep {
@@ -23,7 +26,7 @@ fn get_line(a: Point2<u32>, b: Point2<u32>) -> Vec<Point2<u32>> {
   }
 
   let dx = x2 - x1;
-  let dy = y2 - y1;
+  let dy = (y2 - y1).abs();
 
   let mut err = dx / 2;
   let mut y = y1;
@@ -32,9 +35,9 @@ fn get_line(a: Point2<u32>, b: Point2<u32>) -> Vec<Point2<u32>> {
 
   for x in x1..(x2 + 1) {
     if is_steep {
-      points.push(Point2::new(y as u32, x as u32 ));
+      points.push(Point2::new(y, x));
     } else {
-      points.push(Point2::new(x as u32, y as u32 ));
+      points.push(Point2::new(x, y));
     }
     err -= dy;
 
@@ -45,11 +48,31 @@ fn get_line(a: Point2<u32>, b: Point2<u32>) -> Vec<Point2<u32>> {
   }
 
   if reversed {
-    for i in 0..(points.len() / 2) {
-      let end = points.len() - 1;
-      points.swap(i, end - i);
-    }
+    points.reverse();
   }
 
   points
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_should_include_both_ends() {
+    let line = get_line(Point2::new(0, 0), Point2::new(4, 2));
+
+    assert_eq!(line.first(), Some(&Point2::new(0, 0)));
+    assert_eq!(line.last(), Some(&Point2::new(4, 2)));
+    assert_eq!(line.len(), 5);
+  }
+
+  #[test]
+  fn it_should_handle_negative_coordinates() {
+    let line = get_line(Point2::new(2, 1), Point2::new(-2, -3));
+
+    assert_eq!(line.first(), Some(&Point2::new(2, 1)));
+    assert_eq!(line.last(), Some(&Point2::new(-2, -3)));
+    assert!(line.contains(&Point2::new(0, -1)));
+  }
+}
@@ -0,0 +1,378 @@
+// Melee combat resolution, tuned by assets/combat.json
+
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+
+use super::{Entity, EntityManager, Component, components, assets};
+use crate::status;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeaponDef {
+    pub name: String,
+    /// Used in hit messages, "you <verb> the goblin"
+    pub verb: String,
+    pub min_damage: i32,
+    pub max_damage: i32,
+    /// Added to the hit chance in percent
+    pub to_hit: i32,
+    #[serde(default)]
+    pub on_hit: Option<OnHit>
+}
+
+/// Status effect a weapon may leave behind on a hit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OnHit {
+    pub status: components::StatusKind,
+    /// Percent
+    pub chance: i32,
+    pub turns: i32,
+    #[serde(default)]
+    pub magnitude: i32
+}
+
+/**
+ * Bows, thrown weapons and wands
+ * Every shot uses up one of the ammo kind from the inventory
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RangedDef {
+    pub name: String,
+    pub verb: String,
+    pub min_damage: i32,
+    pub max_damage: i32,
+    pub to_hit: i32,
+    /// Farthest tile a shot reaches
+    pub range: usize,
+    /// Ammo kind, arrows, darts or charges in a wand
+    pub ammo: String,
+    /// Drawn along the projectile's path
+    pub glyph: char,
+    #[serde(default)]
+    pub kind: components::DamageType
+}
+
+impl RangedDef {
+    /// The shot as a weapon for resolve_attack
+    pub fn as_weapon(&self) -> WeaponDef {
+        WeaponDef {
+            name: self.name.clone(),
+            verb: self.verb.clone(),
+            min_damage: self.min_damage,
+            max_damage: self.max_damage,
+            to_hit: self.to_hit,
+            on_hit: None
+        }
+    }
+}
+
+/// Percentages and multipliers for every attack roll
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CombatRules {
+    pub base_to_hit: i32,
+    /// Hit chance per point of the attacker's dexterity modifier
+    pub to_hit_per_modifier: i32,
+    /// Hit chance taken away per point of the defender's dexterity modifier
+    pub evasion_per_modifier: i32,
+    /// Damage per point of the attacker's strength modifier
+    pub damage_per_modifier: i32,
+    pub min_hit_chance: i32,
+    pub max_hit_chance: i32,
+    pub critical_chance: i32,
+    pub critical_multiplier: i32,
+    pub fumble_chance: i32,
+    pub unarmed: WeaponDef,
+    pub weapons: Vec<WeaponDef>,
+    #[serde(default)]
+    pub ranged: Vec<RangedDef>
+}
+
+impl CombatRules {
+    pub fn from_json(buffer: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(buffer)
+    }
+
+    pub fn weapon(&self, name: &str) -> Option<&WeaponDef> {
+        self.weapons.iter()
+            .chain(std::iter::once(&self.unarmed))
+            .find(|weapon| weapon.name == name)
+    }
+
+    pub fn ranged(&self, name: &str) -> Option<&RangedDef> {
+        self.ranged.iter().find(|ranged| ranged.name == name)
+    }
+
+    /// Verb for hit messages, melee or ranged
+    pub fn verb(&self, name: &str) -> Option<&str> {
+        self.weapon(name).map(|weapon| weapon.verb.as_str())
+            .or_else(|| self.ranged(name).map(|ranged| ranged.verb.as_str()))
+    }
+}
+
+lazy_static! {
+    static ref RULES: CombatRules = assets::load("combat.json", include_str!("../assets/combat.json"), CombatRules::from_json);
+}
+
+pub fn rules() -> &'static CombatRules {
+    &RULES
+}
+
+/// What one side brings to a fight
+#[derive(Debug, Clone, PartialEq)]
+pub struct Combatant {
+    pub attributes: components::Attributes,
+    pub weapon: WeaponDef,
+    pub armor: components::Armor
+}
+
+impl Combatant {
+    /// Entities without attributes fight as an average person, without a weapon they fight unarmed
+    pub fn from_entity(em: &EntityManager, entity: Entity, rules: &CombatRules) -> Self {
+        let attributes = get_component!(em, entity, components::Attributes)
+            .cloned()
+            .unwrap_or_default();
+        let attributes = status::effective_attributes(em, entity, attributes);
+
+        let weapon = get_component!(em, entity, components::Weapon)
+            .and_then(|weapon| rules.weapon(&weapon.name))
+            .cloned()
+            .unwrap_or_else(|| rules.unarmed.clone());
+
+        let armor = get_component!(em, entity, components::Armor)
+            .cloned()
+            .unwrap_or_default();
+
+        Self {
+            attributes: attributes,
+            weapon: weapon,
+            armor: armor
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AttackOutcome {
+    /// Rolled a natural fumble, misses no matter the odds
+    Fumble,
+    Miss,
+    Hit { damage: i32, critical: bool }
+}
+
+/// Chance in percent for the attacker to land a hit
+pub fn hit_chance(rules: &CombatRules, attacker: &Combatant, defender: &Combatant) -> i32 {
+    let chance = rules.base_to_hit
+        + attacker.attributes.modifier(attacker.attributes.dexterity) * rules.to_hit_per_modifier
+        + attacker.weapon.to_hit
+        - defender.attributes.modifier(defender.attributes.dexterity) * rules.evasion_per_modifier
+        - defender.armor.evasion;
+
+    chance.max(rules.min_hit_chance).min(rules.max_hit_chance)
+}
+
+/**
+ * Roll one melee attack
+ * Fumbles and criticals are checked before the hit chance,
+ * armor soaks damage after strength and criticals are applied
+ */
+pub fn resolve_attack<R: Rng>(rules: &CombatRules, attacker: &Combatant, defender: &Combatant, rng: &mut R) -> AttackOutcome {
+    let roll = rng.gen_range(0, 100);
+
+    if roll < rules.fumble_chance {
+        return AttackOutcome::Fumble;
+    }
+
+    let critical = roll >= 100 - rules.critical_chance;
+
+    if !critical && roll >= hit_chance(rules, attacker, defender) {
+        return AttackOutcome::Miss;
+    }
+
+    let weapon = &attacker.weapon;
+    let mut damage = rng.gen_range(weapon.min_damage, weapon.max_damage + 1)
+        + attacker.attributes.modifier(attacker.attributes.strength) * rules.damage_per_modifier;
+
+    if critical {
+        damage *= rules.critical_multiplier;
+    }
+
+    // A hit always does at least a scratch
+    let damage = (damage - defender.armor.reduction).max(1);
+
+    AttackOutcome::Hit { damage: damage, critical: critical }
+}
+
+/// Queue a hit on its target, hits in the same tick stack instead of replacing each other
+pub fn inflict(em: &mut EntityManager, damage: components::Damage) {
+    let target = damage.target;
+
+    match get_component!(mut, em, target, components::DamageQueue) {
+        Some(queue) => queue.instances.push(damage),
+        None => em.add_component(target, components::DamageQueue { instances: vec![damage] })
+    }
+}
+
+/// "you" for the player, "the goblin" for everything else
+pub fn describe(em: &EntityManager, entity: Entity) -> String {
+    if em.has_component(entity, components::Player::get_component_type()) {
+        return "you".to_string();
+    }
+
+    match get_component!(em, entity, components::Name) {
+        Some(name) => format!("the {}", name.name),
+        None => "something".to_string()
+    }
+}
+
+/// Verb agreeing with the subject, "you bite" but "the zombie bites"
+pub fn conjugate(em: &EntityManager, subject: Entity, verb: &str) -> String {
+    if em.has_component(subject, components::Player::get_component_type()) {
+        verb.to_string()
+    } else if ["s", "sh", "ch", "x", "z"].iter().any(|ending| verb.ends_with(ending)) {
+        format!("{}es", verb)
+    } else {
+        format!("{}s", verb)
+    }
+}
+
+/// Capitalize the start of a log message
+pub fn sentence(message: String) -> String {
+    let mut chars = message.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => message
+    }
+}
+
+/**
+ * Log line for damage, "The zombie bites you for 3."
+ * Damage without a source is just reported as taken
+ */
+pub fn damage_message(em: &EntityManager, damage: &components::Damage) -> String {
+    let target = describe(em, damage.target);
+
+    let source = match damage.source {
+        Some(source) => source,
+        None => return sentence(format!("{} {} {} damage.", target, conjugate(em, damage.target, "take"), damage.amount))
+    };
+
+    let verb = damage.weapon.as_ref()
+        .and_then(|weapon| rules().verb(weapon))
+        .unwrap_or("hit");
+
+    let target = if source == damage.target {
+        // Hurting yourself
+        match em.has_component(source, components::Player::get_component_type()) {
+            true => "yourself".to_string(),
+            false => "itself".to_string()
+        }
+    } else {
+        target
+    };
+
+    sentence(format!("{} {} {} for {}.", describe(em, source), conjugate(em, source, verb), target, damage.amount))
+}
+
+/// Log line for damage that was shrugged off, "The zombie is immune to poison."
+pub fn immune_message(em: &EntityManager, damage: &components::Damage) -> String {
+    let verb = match em.has_component(damage.target, components::Player::get_component_type()) {
+        true => "are",
+        false => "is"
+    };
+
+    sentence(format!("{} {} immune to {}.", describe(em, damage.target), verb, damage.kind.name()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn combatant(strength: i32, dexterity: i32, weapon: &str, armor: i32) -> Combatant {
+        Combatant {
+            attributes: components::Attributes::new(strength, dexterity, 10, 10, 10, 10),
+            weapon: rules().weapon(weapon).cloned().unwrap(),
+            armor: components::Armor { reduction: armor, evasion: 0 }
+        }
+    }
+
+    #[test]
+    fn it_should_favor_dexterous_attackers() {
+        let rules = rules();
+
+        let clumsy = combatant(10, 6, "fists", 0);
+        let nimble = combatant(10, 18, "fists", 0);
+
+        assert!(hit_chance(rules, &nimble, &clumsy) > hit_chance(rules, &clumsy, &nimble));
+        assert!(hit_chance(rules, &clumsy, &nimble) >= rules.min_hit_chance);
+    }
+
+    #[test]
+    fn it_should_scale_damage_and_soak_with_armor() {
+        let rules = rules();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let strong = combatant(18, 10, "short sword", 0);
+        let weak = combatant(6, 10, "fists", 0);
+        let armored = combatant(10, 10, "fists", 100);
+
+        let total = |attacker: &Combatant, defender: &Combatant, rng: &mut StdRng| -> i32 {
+            (0..200).map(|_| match resolve_attack(rules, attacker, defender, rng) {
+                AttackOutcome::Hit { damage, .. } => damage,
+                _ => 0
+            }).sum()
+        };
+
+        assert!(total(&strong, &weak, &mut rng) > total(&weak, &strong, &mut rng));
+
+        // Armor can't take a hit below one
+        for _ in 0..50 {
+            if let AttackOutcome::Hit { damage, .. } = resolve_attack(rules, &strong, &armored, &mut rng) {
+                assert_eq!(damage, 1);
+            }
+        }
+    }
+
+    #[test]
+    fn it_should_name_the_attacker_in_damage_messages() {
+        let mut em = EntityManager::new();
+
+        let player = em.create_entity();
+        em.add_component(player, components::Player);
+
+        let zombie = em.create_entity();
+        em.add_component(zombie, components::Name { name: "zombie".to_string() });
+
+        let bite = components::Damage {
+            amount: 3,
+            target: player,
+            kind: components::DamageType::Physical,
+            source: Some(zombie),
+            weapon: Some("teeth".to_string())
+        };
+
+        assert_eq!(damage_message(&em, &bite), "The zombie bites you for 3.");
+
+        let slash = components::Damage { target: zombie, source: Some(player), weapon: Some("short sword".to_string()), ..bite.clone() };
+        assert_eq!(damage_message(&em, &slash), "You slash the zombie for 3.");
+
+        let burn = components::Damage { source: None, weapon: None, kind: components::DamageType::Fire, ..bite };
+        assert_eq!(damage_message(&em, &burn), "You take 3 damage.");
+    }
+
+    #[test]
+    fn it_should_roll_criticals_and_fumbles() {
+        let rules = rules();
+        let mut rng = StdRng::seed_from_u64(2);
+
+        let fighter = combatant(10, 10, "dagger", 0);
+
+        let outcomes: Vec<_> = (0..1000).map(|_| resolve_attack(rules, &fighter, &fighter, &mut rng)).collect();
+
+        assert!(outcomes.iter().any(|outcome| *outcome == AttackOutcome::Fumble));
+        assert!(outcomes.iter().any(|outcome| match outcome {
+            AttackOutcome::Hit { critical, .. } => *critical,
+            _ => false
+        }));
+    }
+}
@@ -1,6 +1,7 @@
 use serde::{Serialize, Deserialize};
 
 use std::any::{Any, TypeId};
+use std::collections::HashMap;
 
 use super::{Entity};
 
@@ -79,6 +80,14 @@ impl Component for Input {
     derive_component!();
 }
 
+/// This tick's key only worked a menu or cursor, the rest of the world waits
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MenuInput;
+
+impl Component for MenuInput {
+    derive_component!();
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Timed;
 
@@ -205,13 +214,202 @@ impl Component for Health {
     derive_component!();
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DamageType {
+    Physical,
+    Fire,
+    Poison,
+    Cold
+}
+
+impl DamageType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DamageType::Physical => "physical",
+            DamageType::Fire => "fire",
+            DamageType::Poison => "poison",
+            DamageType::Cold => "cold"
+        }
+    }
+}
+
+impl Default for DamageType {
+    fn default() -> Self {
+        DamageType::Physical
+    }
+}
+
+/// One hit, queued on the target in a DamageQueue
+#[derive(Debug, Clone, PartialEq)]
 pub struct Damage {
     pub amount: i32,
-    pub target: Entity
+    pub target: Entity,
+    pub kind: DamageType,
+    /// Who dealt it, None for traps and terrain
+    pub source: Option<Entity>,
+    pub weapon: Option<String>
+}
+
+/// Every hit an entity took this tick, resolved together by the DamageSystem
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DamageQueue {
+    pub instances: Vec<Damage>
+}
+
+impl Component for DamageQueue {
+    derive_component!();
+}
+
+/**
+ * Percent taken off damage of each type
+ * 100 or more is immune, below zero takes extra damage
+ */
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Resistances {
+    pub values: HashMap<DamageType, i32>
+}
+
+impl Resistances {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, kind: DamageType, percent: i32) -> Self {
+        self.values.insert(kind, percent);
+        self
+    }
+
+    pub fn get(&self, kind: DamageType) -> i32 {
+        self.values.get(&kind).cloned().unwrap_or(0)
+    }
+
+    pub fn is_immune(&self, kind: DamageType) -> bool {
+        self.get(kind) >= 100
+    }
+
+    /// Damage left after resistance, rounded toward the attacker
+    pub fn apply(&self, kind: DamageType, amount: i32) -> i32 {
+        if self.is_immune(kind) {
+            return 0;
+        }
+
+        if amount <= 0 {
+            return amount;
+        }
+
+        let scaled = amount * (100 - self.get(kind));
+
+        // Any damage that isn't resisted outright does at least one
+        ((scaled + 99) / 100).max(1)
+    }
+}
+
+impl Component for Resistances {
+    derive_component!();
+}
+
+#[test]
+fn test_resistances_apply() {
+    let resistances = Resistances::new()
+        .with(DamageType::Fire, 90)
+        .with(DamageType::Cold, -50);
+
+    assert_eq!(resistances.apply(DamageType::Fire, 3), 1);
+    assert_eq!(resistances.apply(DamageType::Cold, 4), 6);
+
+    // Nothing to floor, a miss stays a miss
+    assert_eq!(resistances.apply(DamageType::Fire, 0), 0);
+    assert_eq!(resistances.apply(DamageType::Physical, 0), 0);
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Experience {
+    pub level: i32,
+    pub xp: i32,
+    /// Level ups waiting on a choice
+    pub unspent: i32
+}
+
+impl Experience {
+    pub fn new() -> Self {
+        Self {
+            level: 1,
+            xp: 0,
+            unspent: 0
+        }
+    }
+}
+
+impl Component for Experience {
+    derive_component!();
+}
+
+/// Experience for whoever lands the killing blow
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct XpReward {
+    pub amount: i32
+}
+
+impl Component for XpReward {
+    derive_component!();
+}
+
+/// Loot table rolled when this dies, see assets/loot.json
+#[derive(Debug, Clone, PartialEq)]
+pub struct Loot {
+    pub table: String
+}
+
+impl Component for Loot {
+    derive_component!();
+}
+
+/// Opened by bumping into it, spilling its inventory
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Chest {
+    pub open: bool
+}
+
+impl Component for Chest {
+    derive_component!();
+}
+
+/// Left where something died
+#[derive(Debug, Clone, PartialEq)]
+pub struct Corpse {
+    /// Name of what died
+    pub of: String
 }
 
-impl Component for Damage {
+impl Component for Corpse {
+    derive_component!();
+}
+
+/// The player is dead but kept around so lookups still find them
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Dead;
+
+impl Component for Dead {
+    derive_component!();
+}
+
+/// Side an entity fights on, see assets/factions.json
+#[derive(Debug, Clone, PartialEq)]
+pub struct Faction {
+    pub name: String
+}
+
+impl Component for Faction {
+    derive_component!();
+}
+
+/// Whoever last hurt this entity, for kill credit and grudges
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LastAttacker {
+    pub entity: Entity
+}
+
+impl Component for LastAttacker {
     derive_component!();
 }
 
@@ -264,6 +462,63 @@ impl Component for RandomWalkAi {
     derive_component!();
 }
 
+/// Move toward the player along the player's dijkstra map
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ChaseAi;
+
+impl Component for ChaseAi {
+    derive_component!();
+}
+
+/// Run away from the player
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FleeAi;
+
+impl Component for FleeAi {
+    derive_component!();
+}
+
+/// Walk toward the nearest unexplored tile until everything has been seen
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AutoExplore;
+
+impl Component for AutoExplore {
+    derive_component!();
+}
+
+/// Walk toward a destination tile one step per turn
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Travel {
+    pub x: i32,
+    pub y: i32
+}
+
+impl Component for Travel {
+    derive_component!();
+}
+
+/// What an aiming cursor is for, each is worked by its own system
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TargetMode {
+    Travel,
+    Shoot,
+    Cast
+}
+
+/// Aiming cursor, while it's there movement keys move the cursor instead
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Targeting {
+    pub x: i32,
+    pub y: i32,
+    pub mode: TargetMode,
+    /// Tab only cycles hostiles this close
+    pub range: usize
+}
+
+impl Component for Targeting {
+    derive_component!();
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Energy {
     pub amount: i32
@@ -282,6 +537,47 @@ impl Component for Speed {
     derive_component!();
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StatusKind {
+    Poisoned,
+    Burning,
+    Slowed,
+    Hasted,
+    Stunned,
+    Regenerating,
+    Confused
+}
+
+/// One effect wearing off over time, see status.rs for what each does
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    /// Turns left
+    pub turns: i32,
+    /// Damage or healing per turn for effects that have any
+    pub magnitude: i32,
+    pub source: Option<Entity>
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StatusEffects {
+    pub effects: Vec<StatusEffect>
+}
+
+impl StatusEffects {
+    pub fn get(&self, kind: StatusKind) -> Option<&StatusEffect> {
+        self.effects.iter().find(|effect| effect.kind == kind)
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.get(kind).is_some()
+    }
+}
+
+impl Component for StatusEffects {
+    derive_component!();
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Pickup {
     target: Entity
@@ -315,17 +611,183 @@ pub struct Inventory {
 }
 
 impl Inventory {
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new()
+        }
+    }
+
     pub fn add_item(&mut self, item: Entity) {
         self.items.push(item);
     }
+
+    pub fn items(&self) -> &[Entity] {
+        &self.items
+    }
+
+    pub fn remove_item(&mut self, item: Entity) {
+        self.items.retain(|other| *other != item);
+    }
 }
 
 impl Component for Inventory {
     derive_component!();
 }
 
+/**
+ * On a bow, darts or wand it names the weapon, stats come from the combat rules
+ * On whoever carries one it's the one at the ready, it has to be in their inventory
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangedWeapon {
+    pub name: String
+}
+
+impl Component for RangedWeapon {
+    derive_component!();
+}
+
+/// A stack of arrows, darts or wand charges carried in an inventory
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ammo {
+    pub kind: String,
+    pub count: i32
+}
+
+impl Component for Ammo {
+    derive_component!();
+}
+
+/// Tile aimed at with the ready ranged weapon, let fly once the turn hands out actions
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Shot {
+    pub x: i32,
+    pub y: i32
+}
+
+impl Component for Shot {
+    derive_component!();
+}
+
+/// Spell points, regen is tenths of a point regained each turn
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Mana {
+    pub mana: i32,
+    pub max_mana: i32,
+    pub regen: i32,
+    /// Tenths regained toward the next point
+    pub progress: i32
+}
+
+impl Mana {
+    pub fn new(max_mana: i32, regen: i32) -> Self {
+        Self {
+            mana: max_mana,
+            max_mana: max_mana,
+            regen: regen,
+            progress: 0
+        }
+    }
+}
+
+impl Component for Mana {
+    derive_component!();
+}
+
+/// Spells known by name, numbered in this order on the spellbook screen
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spellbook {
+    pub spells: Vec<String>
+}
+
+impl Component for Spellbook {
+    derive_component!();
+}
+
+/**
+ * Spellcasting in progress, with the spellbook open while no spell is picked
+ * and a Targeting cursor alongside while a picked spell is being aimed
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Casting {
+    pub spell: Option<String>
+}
+
+impl Component for Casting {
+    derive_component!();
+}
+
+/// Spell and tile it's aimed at, cast once the turn hands out actions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cast {
+    pub spell: String,
+    pub x: i32,
+    pub y: i32
+}
+
+impl Component for Cast {
+    derive_component!();
+}
+
+/// Flight of a shot for the renderer to animate, removed once drawn
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projectile {
+    pub path: Vec<(i32, i32)>,
+    pub glyph: char
+}
+
+impl Component for Projectile {
+    derive_component!();
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Door {
+    pub open: bool,
+    pub locked: bool,
+    /// Id of the key that fits the lock
+    pub key: Option<String>
+}
+
+impl Component for Door {
+    derive_component!();
+}
+
+/// Opens doors whose key matches the id
+#[derive(Debug, Clone, PartialEq)]
+pub struct DoorKey {
+    pub id: String
+}
+
+impl Component for DoorKey {
+    derive_component!();
+}
+
+/// Lights the tiles it can see out to radius
 #[derive(Debug, Copy, Clone, PartialEq)]
-pub struct Turn;
+pub struct LightSource {
+    pub radius: i32,
+    pub color: [u8; 3]
+}
+
+impl Component for LightSource {
+    derive_component!();
+}
+
+/// 0 to 100, how hard the entity is to notice, light makes it easier
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Stealth {
+    pub skill: i32
+}
+
+impl Component for Stealth {
+    derive_component!();
+}
+
+/// Actions an entity gets this turn, handed out from its speed
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Turn {
+    pub actions: i32
+}
 
 impl Component for Turn {
     derive_component!();
@@ -342,18 +804,64 @@ impl Component for Prototype {
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Attributes {
-    strength: i32,
-    dexterity: i32,
-    constitution: i32,
-    intelligence: i32,
-    wisdom: i32,
-    charisma: i32
+    pub strength: i32,
+    pub dexterity: i32,
+    pub constitution: i32,
+    pub intelligence: i32,
+    pub wisdom: i32,
+    pub charisma: i32
+}
+
+impl Attributes {
+    pub fn new(strength: i32, dexterity: i32, constitution: i32, intelligence: i32, wisdom: i32, charisma: i32) -> Self {
+        Self {
+            strength: strength,
+            dexterity: dexterity,
+            constitution: constitution,
+            intelligence: intelligence,
+            wisdom: wisdom,
+            charisma: charisma
+        }
+    }
+
+    /// 10 is average, every two points above or below is worth one
+    pub fn modifier(&self, score: i32) -> i32 {
+        ((score - 10) as f32 / 2.0).floor() as i32
+    }
+}
+
+impl Default for Attributes {
+    fn default() -> Self {
+        Self::new(10, 10, 10, 10, 10, 10)
+    }
 }
 
 impl Component for Attributes {
     derive_component!();
 }
 
+/// Wielded weapon, stats come from the combat rules
+#[derive(Debug, Clone, PartialEq)]
+pub struct Weapon {
+    pub name: String
+}
+
+impl Component for Weapon {
+    derive_component!();
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Armor {
+    /// Damage taken off every hit
+    pub reduction: i32,
+    /// Hit chance taken off every attack in percent
+    pub evasion: i32
+}
+
+impl Component for Armor {
+    derive_component!();
+}
+
 trait Command: Component {
 }
 
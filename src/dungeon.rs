@@ -0,0 +1,442 @@
+use rand::{thread_rng, Rng};
+
+use super::{Entity, EntityManager, Component, components, monsters, items, props};
+use crate::map::{Map, MapGenerator, TileType};
+use crate::loot::loot_tables;
+
+/**
+ * A level of the dungeon
+ * While the player is on a level its map lives on the "Map" entity,
+ * once they leave the map and every entity on it are stored here untouched
+ */
+#[derive(Debug)]
+struct Level {
+    map: Option<Map>,
+    frozen: Vec<(Entity, Vec<Box<dyn Component>>)>,
+    generated: bool
+}
+
+#[derive(Debug)]
+pub struct Dungeon {
+    levels: Vec<Level>,
+    depth: usize,
+    width: usize,
+    height: usize,
+    /// Layout used on every level, otherwise it's picked by depth
+    generator: Option<MapGenerator>
+}
+
+impl Dungeon {
+    pub fn new(width: usize, height: usize, max_depth: usize, generator: Option<MapGenerator>) -> Self {
+        let levels = (0..max_depth)
+            .map(|_| Level { map: None, frozen: Vec::new(), generated: false })
+            .collect();
+
+        Self {
+            levels: levels,
+            depth: 0,
+            width: width,
+            height: height,
+            generator: generator
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn generate_map(&self, depth: usize) -> Map {
+        let generator = self.generator.unwrap_or_else(|| MapGenerator::for_depth(depth));
+
+        debug!("Generating {:?} map for depth {}", generator, depth);
+
+        generator.generate(self.width, self.height)
+    }
+}
+
+impl Component for Dungeon {
+    derive_component!();
+}
+
+/**
+ * Create the dungeon with its first level
+ * Returns where the player starts
+ */
+pub fn create_dungeon(
+    em: &mut EntityManager,
+    width: usize,
+    height: usize,
+    max_depth: usize,
+    generator: Option<MapGenerator>
+) -> (i32, i32) {
+    let mut dungeon = Dungeon::new(width, height, max_depth, generator);
+
+    let map = dungeon.generate_map(0);
+    let start = arrival_position(&map, TileType::StairsUp);
+
+    create_map_entities(&map, em);
+    create_prefab_spawns(&map, em);
+    populate_level(em, &map, 0);
+    dungeon.levels[0].generated = true;
+
+    let map_entity = em.create_entity();
+    em.set_entity_name(map_entity, "Map");
+    em.add_component(map_entity, map);
+
+    let dungeon_entity = em.create_entity();
+    em.set_entity_name(dungeon_entity, "Dungeon");
+    em.add_component(dungeon_entity, dungeon);
+
+    start
+}
+
+/**
+ * Move the traveler one level up or down
+ * Everything else on the current level is frozen until the traveler comes back
+ * Returns the new depth, or None if there is no level in that direction
+ */
+pub fn change_level(em: &mut EntityManager, traveler: Entity, down: bool) -> Option<usize> {
+    let dungeon_entity = em.get_entity_by_name("Dungeon")?;
+    let map_entity = em.get_entity_by_name("Map")?;
+
+    // Take the dungeon out of the entity manager while levels are swapped
+    let mut dungeon_component = em.remove_component(dungeon_entity, Dungeon::get_component_type())?;
+
+    let new_depth = {
+        let dungeon = dungeon_component.as_any_mut().downcast_mut::<Dungeon>().unwrap();
+
+        let current = dungeon.depth;
+        let target = if down { current + 1 } else { current.wrapping_sub(1) };
+
+        if target >= dungeon.levels.len() {
+            None
+        } else {
+            // Freeze everything on the level except the traveler
+            let frozen: Vec<_> = em.get_entities_with_components(components::Position::get_component_type())
+                .into_iter()
+                .filter(|entity| *entity != traveler)
+                .map(|entity| (entity, em.take_components(entity)))
+                .collect();
+
+            debug!("Froze {} entities on depth {}", frozen.len(), current);
+
+            dungeon.levels[current].frozen = frozen;
+
+            let new_map = match dungeon.levels[target].map.take() {
+                Some(map) => map,
+                None => dungeon.generate_map(target)
+            };
+
+            let old_map = {
+                let map = get_component!(mut, em, map_entity, Map).unwrap();
+                std::mem::replace(map, new_map)
+            };
+
+            dungeon.levels[current].map = Some(old_map);
+
+            let level = &mut dungeon.levels[target];
+
+            if level.generated {
+                for (entity, components) in level.frozen.drain(..) {
+                    for component in components {
+                        em.add_boxed_component(entity, component);
+                    }
+                }
+            } else {
+                debug!("Generating depth {}", target);
+
+                spawn_level(em, map_entity, target);
+                level.generated = true;
+            }
+
+            dungeon.depth = target;
+
+            Some(target)
+        }
+    };
+
+    em.add_boxed_component(dungeon_entity, dungeon_component);
+
+    if new_depth.is_some() {
+        // Arrive on the stairs leading back
+        let arrival_tile = if down { TileType::StairsUp } else { TileType::StairsDown };
+        let arrival = arrival_position(get_component!(em, map_entity, Map).unwrap(), arrival_tile);
+
+        if let Some(position) = get_component!(mut, em, traveler, components::Position) {
+            position.x = arrival.0;
+            position.y = arrival.1;
+        }
+    }
+
+    new_depth
+}
+
+fn spawn_level(em: &mut EntityManager, map_entity: Entity, depth: usize) {
+    // Temporarily take the map so entities can be created while reading it
+    let mut map_component = em.remove_component(map_entity, Map::get_component_type()).unwrap();
+
+    {
+        let map = map_component.as_any_mut().downcast_mut::<Map>().unwrap();
+
+        create_map_entities(map, em);
+        create_prefab_spawns(map, em);
+        populate_level(em, map, depth);
+    }
+
+    em.add_boxed_component(map_entity, map_component);
+}
+
+// On the stairs, or on the open tile closest to the first room without any
+fn arrival_position(map: &Map, tile: TileType) -> (i32, i32) {
+    let center = map.rooms.first()
+        .map(|room| room.center())
+        .unwrap_or((map.width() as i32 / 2, map.height() as i32 / 2));
+
+    map.find_tiles(tile)
+        .first()
+        .cloned()
+        .or_else(|| map.nearest(center, |x, y| !map.is_blocked(x, y)))
+        .unwrap_or(center)
+}
+
+/**
+ * Terrain is drawn and collided straight from the map,
+ * only tiles that can be interacted with become entities
+ */
+pub fn create_map_entities(map: &Map, em: &mut EntityManager) {
+    let mut rng = thread_rng();
+
+    let mut locked = false;
+
+    for (x, y) in map.find_tiles(TileType::DoorClosed) {
+        // Some doors need the iron key
+        let key = if rng.gen_range(0, 5) == 0 { Some("iron") } else { None };
+        props::create_door(em, x, y, key);
+        locked |= key.is_some();
+    }
+
+    // Whoever locked the doors left the key lying in the first room
+    if let Some(room) = map.rooms.first().filter(|_| locked) {
+        let (x, y) = room.center();
+        let key = items::create_key(em, "iron key", "iron");
+        em.add_component(key, components::Position { x: x, y: y });
+    }
+
+    // Torches in the corner of every other room
+    for room in map.rooms.iter() {
+        let corner = (room.x1 + 1, room.y1 + 1);
+
+        if rng.gen::<bool>() && map.get_tile(corner.0, corner.1) == TileType::Floor {
+            props::create_torch(em, corner.0, corner.1);
+        }
+    }
+}
+
+/// Create whatever the prefabs on the map asked for
+pub fn create_prefab_spawns(map: &Map, em: &mut EntityManager) {
+    for spawn in map.spawns() {
+        match spawn.marker {
+            'g' => { monsters::create_goblin(em, spawn.x, spawn.y); }
+            'z' => { monsters::create_zombie(em, spawn.x, spawn.y); }
+            '!' => items::spawn_potion_of_healing(em, spawn.x, spawn.y),
+            marker => warn!("Unknown spawn marker {:?}", marker)
+        }
+    }
+}
+
+/**
+ * Spawn monsters in every room but the first, where the player arrives
+ * Deeper levels have more, tougher monsters and more goblins among them
+ */
+pub fn populate_level(em: &mut EntityManager, map: &Map, depth: usize) {
+    let mut rng = thread_rng();
+
+    if map.rooms.is_empty() {
+        populate_open_level(em, map, depth);
+        return;
+    }
+
+    let chest_table = loot_tables().for_depth(depth);
+
+    for room in map.rooms.iter().skip(1) {
+        // One room in five hides a chest
+        if let Some(table) = chest_table {
+            let (x, y) = (room.x2 - 1, room.y2 - 1);
+
+            if rng.gen_range(0, 5) == 0 && map.get_tile(x, y) == TileType::Floor {
+                props::create_chest(em, x, y, table, &mut rng);
+            }
+        }
+
+        let monster_count = rng.gen_range(0, 2 + depth / 2);
+
+        for _ in 0..monster_count {
+            let x = rng.gen_range(room.x1 + 1, room.x2);
+            let y = rng.gen_range(room.y1 + 1, room.y2);
+
+            spawn_monster(em, map, x, y, depth);
+        }
+    }
+}
+
+/// Caves and mazes have no rooms, scatter chests and monsters away from the up stairs instead
+fn populate_open_level(em: &mut EntityManager, map: &Map, depth: usize) {
+    const SAFE_DISTANCE: i32 = 10;
+
+    let mut rng = thread_rng();
+
+    let arrival = arrival_position(map, TileType::StairsUp);
+    let floor = map.find_tiles(TileType::Floor);
+
+    if floor.is_empty() {
+        return;
+    }
+
+    let far_enough = |(x, y): (i32, i32)| (x - arrival.0).abs().max((y - arrival.1).abs()) >= SAFE_DISTANCE;
+
+    // About as many chests as a level of rooms would have
+    if let Some(table) = loot_tables().for_depth(depth) {
+        for _ in 0..floor.len() / 500 {
+            let (x, y) = floor[rng.gen_range(0, floor.len())];
+
+            if far_enough((x, y)) {
+                props::create_chest(em, x, y, table, &mut rng);
+            }
+        }
+    }
+
+    let monster_count = floor.len() / 150 + depth;
+
+    for _ in 0..monster_count {
+        let (x, y) = floor[rng.gen_range(0, floor.len())];
+
+        if !far_enough((x, y)) {
+            continue;
+        }
+
+        spawn_monster(em, map, x, y, depth);
+    }
+}
+
+fn spawn_monster(em: &mut EntityManager, map: &Map, x: i32, y: i32, depth: usize) {
+    let mut rng = thread_rng();
+
+    if map.get_tile(x, y) != TileType::Floor {
+        return;
+    }
+
+    let monster = if rng.gen_range(0, 10) < depth.min(7) {
+        monsters::create_goblin(em, x, y)
+    } else {
+        monsters::create_zombie(em, x, y)
+    };
+
+    if let Some(health) = get_component!(mut, em, monster, components::Health) {
+        health.max_health += depth as i32 * 2;
+        health.health = health.max_health;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+
+    fn create_player(em: &mut EntityManager, position: (i32, i32)) -> Entity {
+        let player = em.create_entity();
+        em.add_component(player, components::Player);
+        em.add_component(player, components::Position { x: position.0, y: position.1 });
+        player
+    }
+
+    #[test]
+    fn it_should_start_on_up_stairs() {
+        let mut em = EntityManager::new();
+
+        let start = create_dungeon(&mut em, 100, 100, 3, None);
+
+        let map_entity = em.get_entity_by_name("Map").unwrap();
+        let map = get_component!(em, map_entity, Map).unwrap();
+
+        assert_eq!(map.get_tile(start.0, start.1), TileType::StairsUp);
+        assert_eq!(map.find_tiles(TileType::StairsDown).len(), 1);
+    }
+
+    #[test]
+    fn it_should_arrive_on_open_ground_in_a_one_room_level() {
+        let map = MapBuilder::new(10, 10)
+            .create_room(&Rect::new(2, 2, 4, 4))
+            .place_stairs()
+            .build();
+
+        let up = map.find_tiles(TileType::StairsUp);
+        let down = map.find_tiles(TileType::StairsDown);
+
+        assert_eq!(up, vec![(4, 4)]);
+        assert_eq!(down.len(), 1);
+        assert!((down[0].0 - 4).abs() <= 1 && (down[0].1 - 4).abs() <= 1);
+
+        // No stairs to arrive on, the closest open tile to the room instead
+        let mut stairless = MapBuilder::new(10, 10).create_room(&Rect::new(2, 2, 4, 4)).build();
+        stairless.set_tile(4, 4, TileType::Wall);
+
+        let arrival = arrival_position(&stairless, TileType::StairsUp);
+        assert!(!stairless.is_blocked(arrival.0, arrival.1));
+    }
+
+    #[test]
+    fn it_should_hide_chests_in_open_levels() {
+        let mut em = EntityManager::new();
+
+        let mut map = MapBuilder::new(60, 60).create_room(&Rect::new(0, 0, 59, 59)).build();
+        map.set_rooms(Vec::new());
+        map.set_tile(1, 1, TileType::StairsUp);
+
+        populate_level(&mut em, &map, 0);
+
+        let chests = em.get_entities_with_components(components::Chest::get_component_type());
+        assert!(!chests.is_empty());
+
+        for chest in chests {
+            let position = get_component!(em, chest, components::Position).unwrap();
+            assert_eq!(map.get_tile(position.x, position.y), TileType::Floor);
+            assert!(position.x.max(position.y) > 10);
+        }
+    }
+
+    #[test]
+    fn it_should_freeze_and_restore_levels() {
+        let mut em = EntityManager::new();
+
+        let start = create_dungeon(&mut em, 60, 60, 2, Some(MapGenerator::Rooms));
+        let player = create_player(&mut em, start);
+
+        let zombie = monsters::create_zombie(&mut em, 1, 1);
+
+        assert_eq!(change_level(&mut em, player, true), Some(1));
+        assert!(!em.has_component(zombie, components::Position::get_component_type()));
+
+        let map_entity = em.get_entity_by_name("Map").unwrap();
+        let position = *get_component!(em, player, components::Position).unwrap();
+        let map = get_component!(em, map_entity, Map).unwrap();
+        assert_eq!(map.get_tile(position.x, position.y), TileType::StairsUp);
+
+        // No level below the bottom
+        assert_eq!(change_level(&mut em, player, true), None);
+
+        assert_eq!(change_level(&mut em, player, false), Some(0));
+        assert_eq!(get_component!(em, zombie, components::Position), Some(&components::Position { x: 1, y: 1 }));
+
+        let position = *get_component!(em, player, components::Position).unwrap();
+        let map = get_component!(em, map_entity, Map).unwrap();
+        assert_eq!(map.get_tile(position.x, position.y), TileType::StairsDown);
+
+        // No level above the top
+        assert_eq!(change_level(&mut em, player, false), None);
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::components::{self, Component, ComponentType};
 
@@ -31,6 +31,8 @@ pub struct EntityManager {
     entities: Vec<Entity>,
     entity_names: HashMap<Entity, String>,
     component_data_tables: HashMap<ComponentType, HashMap<Entity, Box<dyn Component>>>,
+    // Entities whose watched components were added, removed or borrowed mutably
+    changed: HashMap<ComponentType, HashSet<Entity>>,
     listeners: Vec<std::sync::mpsc::Sender<String>>
 }
 
@@ -46,6 +48,7 @@ impl EntityManager {
             entities: Vec::new(),
             entity_names: HashMap::new(),
             component_data_tables: HashMap::new(),
+            changed: HashMap::new(),
             listeners: Vec::new(),
         }
     }
@@ -73,9 +76,33 @@ impl EntityManager {
         self.add_component(child, components::Prototype { prototype: prototype });
     }
 
+    /**
+     * Start keeping track of which entities touch a component type
+     * Lets caches like the spatial index update only what changed
+     */
+    pub fn watch(&mut self, component_type: ComponentType) {
+        self.changed.entry(component_type).or_insert_with(HashSet::new);
+    }
+
+    /// Entities whose component changed since the last call, clears them
+    pub fn take_changed(&mut self, component_type: ComponentType) -> Vec<Entity> {
+        match self.changed.get_mut(&component_type) {
+            Some(changed) => changed.drain().collect(),
+            None => Vec::new()
+        }
+    }
+
+    fn mark_changed(&mut self, entity: Entity, component_type: ComponentType) {
+        if let Some(changed) = self.changed.get_mut(&component_type) {
+            changed.insert(entity);
+        }
+    }
+
     pub fn add_boxed_component(&mut self, entity: Entity, component: Box<dyn Component>) {
         let component_type = component.get_type();
 
+        self.mark_changed(entity, component_type);
+
         let table = self.get_mut_component_table(component_type);
 
         let _ = table.insert(entity, component);
@@ -112,7 +139,9 @@ impl EntityManager {
     }
 
     fn get_prototype(&self, entity: Entity) -> Option<Entity> {
-        self.get_component(entity, components::Prototype::get_component_type())
+        // Straight from the table, get_component asks for the prototype whenever a component is missing
+        self.get_component_table(components::Prototype::get_component_type())
+            .and_then(|table| table.get(&entity))
             .map(|component| component.as_any().downcast_ref::<components::Prototype>())
             .flatten()
             .map(|prototype| prototype.prototype)
@@ -124,8 +153,8 @@ impl EntityManager {
         component_type: ComponentType
     ) -> Option<&Box<dyn Component>> {
         // Get the table
-        if let Some(table) = self.component_data_tables.get(&component_type) {
-            return table.get(&entity);
+        if let Some(component) = self.component_data_tables.get(&component_type).and_then(|table| table.get(&entity)) {
+            Some(component)
         } else if let Some(prototype) = self.get_prototype(entity) {
             // Check the prototype
             self.get_component(prototype, component_type)
@@ -142,6 +171,8 @@ impl EntityManager {
     // }
 
     pub fn get_component_mut(&mut self, entity: Entity, component_type: ComponentType) -> Option<&mut Box<dyn Component>> {
+        self.mark_changed(entity, component_type);
+
         let mut component = None;
         if let Some(table) = self.component_data_tables.get_mut(&component_type) {
             component = table.get_mut(&entity);
@@ -159,12 +190,29 @@ impl EntityManager {
     }
 
     pub fn remove_component(&mut self, entity: Entity, component_type: ComponentType) -> Option<Box<dyn Component>> {
+        self.mark_changed(entity, component_type);
+
         self.component_data_tables
             .get_mut(&component_type)
             .map(|component_table| component_table.remove(&entity))
             .flatten()
     }
 
+    /**
+     * Remove every component of an entity, keeping the entity alive
+     * Used to freeze entities that should be restored later
+     */
+    pub fn take_components(&mut self, entity: Entity) -> Vec<Box<dyn Component>> {
+        for changed in self.changed.values_mut() {
+            changed.insert(entity);
+        }
+
+        self.component_data_tables
+            .values_mut()
+            .filter_map(|table| table.remove(&entity))
+            .collect()
+    }
+
     pub fn get_entities_with_components(&self, component_type: ComponentType) -> Vec<Entity> {
         use std::iter::FromIterator;
 
@@ -200,6 +248,10 @@ impl EntityManager {
     }
 
     pub fn kill_entity(&mut self, entity: Entity) {
+        for changed in self.changed.values_mut() {
+            changed.insert(entity);
+        }
+
         for (_, table) in self.component_data_tables.iter_mut() {
             let _ = table.remove(&entity);
         }
@@ -221,6 +273,11 @@ impl EntityManager {
     pub fn subscribe(&mut self, listener: std::sync::mpsc::Sender<String>) {
         self.listeners.push(listener);
     }
+
+    /// Send an event to every subscriber, dropping the ones that hung up
+    pub fn publish(&mut self, event: &str) {
+        self.listeners.retain(|listener| listener.send(event.to_string()).is_ok());
+    }
 }
 
 impl std::fmt::Debug for EntityManager {
@@ -306,5 +363,58 @@ mod entity_manager_tests {
         assert_eq!(entity, expected_entity);
     }
 
+    #[test]
+    fn it_should_get_components_without_any_prototypes() {
+        let mut em = EntityManager::new();
+
+        let entity = em.create_entity();
+
+        // No prototype table at all, looking for one must not recurse
+        assert!(em.get_component(entity, TestComponent::get_component_type()).is_none());
+        assert!(em.get_component(entity, components::Prototype::get_component_type()).is_none());
+    }
+
+    #[test]
+    fn it_should_fall_back_to_the_prototype() {
+        let mut em = EntityManager::new();
+
+        let goblin = em.create_entity();
+        em.add_component(goblin, TestComponent);
+
+        // The table exists, the child just isn't in it
+        let other = em.create_entity();
+        em.add_component(other, TestComponent);
+
+        let child = em.create_entity();
+        em.extend(goblin, child);
+
+        assert!(em.get_component(child, TestComponent::get_component_type()).is_some());
+    }
+
+    #[test]
+    fn it_should_track_changes_to_watched_components() {
+        let mut em = EntityManager::new();
+
+        let entity = em.create_entity();
+        em.add_component(entity, TestComponent);
+
+        // Not watched yet
+        assert!(em.take_changed(TestComponent::get_component_type()).is_empty());
+
+        em.watch(TestComponent::get_component_type());
+
+        let other = em.create_entity();
+        em.add_component(other, TestComponent);
+        em.get_component_mut(entity, TestComponent::get_component_type());
+
+        let mut changed = em.take_changed(TestComponent::get_component_type());
+        changed.sort_by_key(|entity| entity.id);
+        assert_eq!(changed, vec![entity, other]);
+        assert!(em.take_changed(TestComponent::get_component_type()).is_empty());
+
+        em.kill_entity(other);
+        assert_eq!(em.take_changed(TestComponent::get_component_type()), vec![other]);
+    }
+
     // IT should fail to set name if the name is already set
 }
\ No newline at end of file
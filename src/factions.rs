@@ -0,0 +1,119 @@
+// Who fights whom, tuned by assets/factions.json
+
+use std::collections::HashMap;
+
+use serde::{Serialize, Deserialize};
+
+use super::{Entity, EntityManager, Component, components, assets};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Relation {
+    /// Attacked on sight and on bump
+    Hostile,
+    /// Left alone, bumping just pushes against them
+    Neutral,
+    /// Bumping swaps places
+    Allied
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RelationDef {
+    a: String,
+    b: String,
+    relation: Relation
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FactionsDef {
+    default: Relation,
+    relations: Vec<RelationDef>
+}
+
+/**
+ * Relations between pairs of factions, the same either way round
+ * Members of one faction are allied unless the table says otherwise
+ */
+#[derive(Debug, Clone)]
+pub struct FactionTable {
+    default: Relation,
+    relations: HashMap<(String, String), Relation>
+}
+
+impl FactionTable {
+    pub fn from_json(buffer: &str) -> serde_json::Result<Self> {
+        let def: FactionsDef = serde_json::from_str(buffer)?;
+
+        let mut relations = HashMap::new();
+
+        for relation in def.relations {
+            relations.insert((relation.b.clone(), relation.a.clone()), relation.relation);
+            relations.insert((relation.a, relation.b), relation.relation);
+        }
+
+        Ok(Self {
+            default: def.default,
+            relations: relations
+        })
+    }
+
+    pub fn relation(&self, a: &str, b: &str) -> Relation {
+        match self.relations.get(&(a.to_string(), b.to_string())) {
+            Some(relation) => *relation,
+            None if a == b => Relation::Allied,
+            None => self.default
+        }
+    }
+}
+
+lazy_static! {
+    static ref FACTIONS: FactionTable = assets::load("factions.json", include_str!("../assets/factions.json"), FactionTable::from_json);
+}
+
+pub fn factions() -> &'static FactionTable {
+    &FACTIONS
+}
+
+/// How a feels about b, anything without a faction is fair game
+pub fn relation(em: &EntityManager, a: Entity, b: Entity) -> Relation {
+    let faction = |entity| get_component!(em, entity, components::Faction).map(|faction| faction.name.clone());
+
+    match (faction(a), faction(b)) {
+        (Some(a), Some(b)) => factions().relation(&a, &b),
+        _ => Relation::Hostile
+    }
+}
+
+pub fn is_hostile(em: &EntityManager, a: Entity, b: Entity) -> bool {
+    relation(em, a, b) == Relation::Hostile
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_look_up_relations_both_ways() {
+        let table = factions();
+
+        assert_eq!(table.relation("player", "goblins"), Relation::Hostile);
+        assert_eq!(table.relation("goblins", "player"), Relation::Hostile);
+        assert_eq!(table.relation("undead", "undead"), Relation::Allied);
+        assert_eq!(table.relation("goblins", "undead"), Relation::Neutral);
+    }
+
+    #[test]
+    fn it_should_treat_factionless_entities_as_hostile() {
+        let mut em = EntityManager::new();
+
+        let zombie = em.create_entity();
+        em.add_component(zombie, components::Faction { name: "undead".to_string() });
+
+        let other_zombie = em.create_entity();
+        em.add_component(other_zombie, components::Faction { name: "undead".to_string() });
+
+        let dummy = em.create_entity();
+
+        assert!(!is_hostile(&em, zombie, other_zombie));
+        assert!(is_hostile(&em, zombie, dummy));
+    }
+}
@@ -0,0 +1,76 @@
+// Long walks the player leaves running over many turns, and what cuts them short
+
+use super::{Entity, EntityManager, Component, components};
+use crate::message_log;
+use crate::targeting;
+
+/// How far the player notices hostiles, same as they see
+pub const SIGHT_RADIUS: usize = 8;
+
+/// Whether the entity is exploring or traveling on its own, input shouldn't wait for a key then
+pub fn busy(em: &EntityManager, entity: Entity) -> bool {
+    em.has_component(entity, components::AutoExplore::get_component_type())
+        || em.has_component(entity, components::Travel::get_component_type())
+}
+
+/// Stop walking on its own, the reason goes to the log when there was anything to stop
+pub fn stop(em: &mut EntityManager, entity: Entity, reason: &str) {
+    if !busy(em, entity) {
+        return;
+    }
+
+    em.remove_component(entity, components::AutoExplore::get_component_type());
+    em.remove_component(entity, components::Travel::get_component_type());
+
+    message_log::push(em, entity, reason.to_string());
+}
+
+/// Whether a hostile is in sight
+pub fn hostile_in_view(em: &EntityManager, entity: Entity) -> bool {
+    !targeting::hostile_targets(em, entity, SIGHT_RADIUS).is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+
+    #[test]
+    fn it_should_stop_on_hostiles_in_view() {
+        let mut em = EntityManager::new();
+
+        let map_entity = em.create_entity();
+        em.set_entity_name(map_entity, "Map");
+        em.add_component(map_entity, MapBuilder::new(30, 10).create_room(&Rect::new(0, 0, 29, 9)).build());
+
+        let player = em.create_entity();
+        em.add_component(player, components::Log::new());
+        em.add_component(player, components::Faction { name: "player".to_string() });
+        em.add_component(player, components::Position { x: 2, y: 2 });
+        em.add_component(player, components::AutoExplore);
+
+        let goblin = em.create_entity();
+        em.add_component(goblin, components::Faction { name: "goblins".to_string() });
+        em.add_component(goblin, components::Health { health: 8, max_health: 8 });
+        em.add_component(goblin, components::Position { x: 20, y: 2 });
+
+        // Company doesn't count
+        let ally = em.create_entity();
+        em.add_component(ally, components::Faction { name: "player".to_string() });
+        em.add_component(ally, components::Health { health: 8, max_health: 8 });
+        em.add_component(ally, components::Position { x: 4, y: 2 });
+
+        assert!(busy(&em, player));
+        assert!(!hostile_in_view(&em, player));
+
+        em.add_component(goblin, components::Position { x: 8, y: 2 });
+        assert!(hostile_in_view(&em, player));
+
+        stop(&mut em, player, "You stop.");
+        stop(&mut em, player, "You stop again.");
+
+        assert!(!busy(&em, player));
+        assert_eq!(get_component!(em, player, components::Log).unwrap().history, vec!["You stop.".to_string()]);
+    }
+}
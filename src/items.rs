@@ -1,14 +1,104 @@
-use super::{EntityManager, components};
+use super::{Entity, EntityManager, components};
 
 pub fn spawn_potion_of_healing(
     em: &mut EntityManager,
     x: i32,
     y: i32
 ) {
-    let health_potion = em.create_entity();
+    let health_potion = create_potion_of_healing(em);
 
     em.add_component(health_potion, components::Position { x: x, y: y });
+}
+
+pub fn create_potion_of_healing(em: &mut EntityManager) -> Entity {
+    let health_potion = em.create_entity();
+
+    em.add_component(health_potion, components::Name { name: "potion of healing".to_string() });
     em.add_component(health_potion, components::Render { glyph: '!', layer: components::RenderLayer::Item });
+    em.add_component(health_potion, components::Item);
     em.add_component(health_potion, components::Consumable);
     // em.add_component(health_potion, components::Script)
-}
\ No newline at end of file
+
+    health_potion
+}
+
+/// Keys have no position until they're put down somewhere
+pub fn create_key(
+    em: &mut EntityManager,
+    name: &str,
+    key_id: &str
+) -> Entity {
+    let key = em.create_entity();
+
+    em.add_component(key, components::Name { name: name.to_string() });
+    em.add_component(key, components::Render { glyph: '-', layer: components::RenderLayer::Item });
+    em.add_component(key, components::Item);
+    em.add_component(key, components::DoorKey { id: key_id.to_string() });
+
+    key
+}
+
+/// Arrows are counted stacks that start out in an inventory
+pub fn create_ammo(
+    em: &mut EntityManager,
+    name: &str,
+    kind: &str,
+    count: i32
+) -> Entity {
+    let ammo = em.create_entity();
+
+    em.add_component(ammo, components::Name { name: name.to_string() });
+    em.add_component(ammo, components::Render { glyph: '/', layer: components::RenderLayer::Item });
+    em.add_component(ammo, components::Item);
+    em.add_component(ammo, components::Ammo { kind: kind.to_string(), count: count });
+
+    ammo
+}
+
+/**
+ * Bows, darts and wands, readied from the inventory with 'r'
+ * Darts are thrown from their own stack and a wand holds its charges,
+ * so those carry ammo as well
+ */
+pub fn create_ranged_weapon(
+    em: &mut EntityManager,
+    name: &str,
+    glyph: char,
+    ammo: Option<components::Ammo>
+) -> Entity {
+    let weapon = em.create_entity();
+
+    em.add_component(weapon, components::Name { name: name.to_string() });
+    em.add_component(weapon, components::Render { glyph: glyph, layer: components::RenderLayer::Item });
+    em.add_component(weapon, components::Item);
+    em.add_component(weapon, components::RangedWeapon { name: name.to_string() });
+
+    if let Some(ammo) = ammo {
+        em.add_component(weapon, ammo);
+    }
+
+    weapon
+}
+
+/**
+ * Items by the names used in loot tables, without a position
+ * Ammo and darts come as one stack of count, a wand with count charges,
+ * anything else as count separate items
+ */
+pub fn create_item(
+    em: &mut EntityManager,
+    name: &str,
+    count: i32
+) -> Vec<Entity> {
+    match name {
+        "arrows" => vec![create_ammo(em, name, "arrow", count)],
+        "short bow" => (0..count).map(|_| create_ranged_weapon(em, name, ')', None)).collect(),
+        "darts" => vec![create_ranged_weapon(em, name, '-', Some(components::Ammo { kind: "dart".to_string(), count: count }))],
+        "wand of fire" => vec![create_ranged_weapon(em, name, '/', Some(components::Ammo { kind: "fire charge".to_string(), count: count }))],
+        "potion of healing" => (0..count).map(|_| create_potion_of_healing(em)).collect(),
+        _ => {
+            warn!("Unknown item {}", name);
+            Vec::new()
+        }
+    }
+}
@@ -32,6 +32,8 @@ extern crate time;
 
 extern crate ncurses;
 
+extern crate png;
+
 extern crate uuid;
 
 #[macro_use]
@@ -41,16 +43,31 @@ pub use components::{Component, ComponentType};
 mod entities;
 pub use entities::{Entity, EntityManager};
 
+#[macro_use]
+pub mod systems;
+
+pub mod assets;
 pub mod map;
 mod types;
-mod bresenham;
+pub mod bresenham;
 pub mod monsters;
 pub mod items;
+pub mod props;
+pub mod dungeon;
+pub mod spatial;
+pub mod message_log;
+pub mod menu;
+pub mod targeting;
+pub mod interrupt;
+pub mod combat;
+pub mod factions;
+pub mod status;
+pub mod progression;
+pub mod loot;
+pub mod scripts;
+pub mod magic;
 pub mod renderer;
 
-#[macro_use]
-pub mod systems;
-
 pub use types::*;
 pub use map::{Map, MapBuilder};
 pub mod file_logger;
\ No newline at end of file
@@ -0,0 +1,240 @@
+// Weighted loot tables, see assets/loot.json
+
+use std::collections::HashMap;
+
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use serde::de::Error;
+
+use crate::assets;
+
+/// Tables referencing tables deeper than this are assumed to loop
+const MAX_NESTING: usize = 8;
+
+fn one() -> u32 {
+    1
+}
+
+fn single() -> (i32, i32) {
+    (1, 1)
+}
+
+/**
+ * One line of a loot table
+ * Names either an item or another table to roll on, an entry with neither drops nothing
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LootEntry {
+    #[serde(default)]
+    pub item: Option<String>,
+    #[serde(default)]
+    pub table: Option<String>,
+    #[serde(default = "one")]
+    pub weight: u32,
+    /// Inclusive quantity range for items
+    #[serde(default = "single")]
+    pub count: (i32, i32)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LootTable {
+    /// Inclusive range of picks from the weighted entries
+    #[serde(default = "single")]
+    pub rolls: (i32, i32),
+    /// Always dropped, on top of the rolls
+    #[serde(default)]
+    pub guaranteed: Vec<LootEntry>,
+    #[serde(default)]
+    pub entries: Vec<LootEntry>
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct DepthTable {
+    min_depth: usize,
+    table: String
+}
+
+/// Something that dropped, how many of an item
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drop {
+    pub item: String,
+    pub count: i32
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LootTables {
+    tables: HashMap<String, LootTable>,
+    #[serde(default)]
+    depths: Vec<DepthTable>
+}
+
+impl LootTables {
+    /// Ranges are checked here, an inverted one would only blow up once rolled
+    pub fn from_json(buffer: &str) -> serde_json::Result<Self> {
+        let tables: Self = serde_json::from_str(buffer)?;
+
+        for (name, table) in tables.tables.iter() {
+            let (min, max) = table.rolls;
+
+            if min < 0 || min > max {
+                return Err(serde_json::Error::custom(format!("loot table {} has bad rolls [{}, {}]", name, min, max)));
+            }
+
+            for entry in table.guaranteed.iter().chain(table.entries.iter()) {
+                let (min, max) = entry.count;
+
+                // Below zero can mean sometimes nothing, but never anything is a mistake
+                if entry.item.is_some() && (min > max || max < 1) {
+                    return Err(serde_json::Error::custom(format!("loot table {} has bad count [{}, {}]", name, min, max)));
+                }
+            }
+        }
+
+        Ok(tables)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LootTable> {
+        self.tables.get(name)
+    }
+
+    /// Table for chests and monster drops at a depth, the deepest one that applies
+    pub fn for_depth(&self, depth: usize) -> Option<&str> {
+        self.depths.iter()
+            .filter(|entry| entry.min_depth <= depth)
+            .max_by_key(|entry| entry.min_depth)
+            .map(|entry| entry.table.as_str())
+    }
+
+    /// Everything a roll on the named table drops
+    pub fn roll<R: Rng>(&self, name: &str, rng: &mut R) -> Vec<Drop> {
+        let mut drops = Vec::new();
+
+        self.roll_into(name, rng, 0, &mut drops);
+
+        drops
+    }
+
+    fn roll_into<R: Rng>(&self, name: &str, rng: &mut R, nesting: usize, drops: &mut Vec<Drop>) {
+        if nesting > MAX_NESTING {
+            warn!("Loot table {} nested too deep, skipping", name);
+            return;
+        }
+
+        let table = match self.tables.get(name) {
+            Some(table) => table,
+            None => {
+                warn!("No loot table named {}", name);
+                return;
+            }
+        };
+
+        for entry in table.guaranteed.iter() {
+            self.take(entry, rng, nesting, drops);
+        }
+
+        let total: u32 = table.entries.iter().map(|entry| entry.weight).sum();
+
+        if total == 0 {
+            return;
+        }
+
+        for _ in 0..rng.gen_range(table.rolls.0, table.rolls.1 + 1) {
+            let mut pick = rng.gen_range(0, total);
+
+            for entry in table.entries.iter() {
+                if pick < entry.weight {
+                    self.take(entry, rng, nesting, drops);
+                    break;
+                }
+
+                pick -= entry.weight;
+            }
+        }
+    }
+
+    fn take<R: Rng>(&self, entry: &LootEntry, rng: &mut R, nesting: usize, drops: &mut Vec<Drop>) {
+        if let Some(item) = entry.item.as_ref() {
+            let count = rng.gen_range(entry.count.0, entry.count.1 + 1);
+
+            if count > 0 {
+                drops.push(Drop { item: item.clone(), count: count });
+            }
+        }
+
+        if let Some(table) = entry.table.as_ref() {
+            self.roll_into(table, rng, nesting + 1, drops);
+        }
+    }
+}
+
+lazy_static! {
+    static ref LOOT: LootTables = assets::load("loot.json", include_str!("../assets/loot.json"), LootTables::from_json);
+}
+
+pub fn loot_tables() -> &'static LootTables {
+    &LOOT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    const TABLES: &str = r#"{
+        "tables": {
+            "gems": { "entries": [ { "item": "ruby", "count": [2, 4] } ] },
+            "chest": {
+                "rolls": [3, 3],
+                "guaranteed": [ { "item": "gold", "count": [10, 10] } ],
+                "entries": [ { "weight": 1 }, { "table": "gems", "weight": 1 } ]
+            },
+            "loop": { "entries": [ { "table": "loop" } ] }
+        },
+        "depths": [ { "min_depth": 0, "table": "chest" }, { "min_depth": 5, "table": "gems" } ]
+    }"#;
+
+    #[test]
+    fn it_should_roll_guaranteed_weighted_and_nested_entries() {
+        let tables = LootTables::from_json(TABLES).unwrap();
+        let mut rng = StdRng::seed_from_u64(3);
+
+        for _ in 0..20 {
+            let drops = tables.roll("chest", &mut rng);
+
+            assert_eq!(drops[0], Drop { item: "gold".to_string(), count: 10 });
+            assert!(drops.len() <= 4);
+            assert!(drops[1..].iter().all(|drop| drop.item == "ruby" && drop.count >= 2 && drop.count <= 4));
+        }
+
+        // Loops give up instead of overflowing
+        assert!(tables.roll("loop", &mut rng).is_empty());
+        assert!(tables.roll("missing", &mut rng).is_empty());
+    }
+
+    #[test]
+    fn it_should_reject_bad_ranges() {
+        let table = |rolls: &str, count: &str| format!(
+            r#"{{ "tables": {{ "chest": {{ "rolls": {}, "entries": [ {{ "item": "gold", "count": {} }} ] }} }} }}"#,
+            rolls, count
+        );
+
+        assert!(LootTables::from_json(&table("[0, 2]", "[-2, 1]")).is_ok());
+        assert!(LootTables::from_json(&table("[3, 1]", "[1, 1]")).is_err());
+        assert!(LootTables::from_json(&table("[-1, 1]", "[1, 1]")).is_err());
+        assert!(LootTables::from_json(&table("[1, 1]", "[4, 2]")).is_err());
+        assert!(LootTables::from_json(&table("[1, 1]", "[0, 0]")).is_err());
+    }
+
+    #[test]
+    fn it_should_pick_tables_by_depth() {
+        let tables = LootTables::from_json(TABLES).unwrap();
+
+        assert_eq!(tables.for_depth(0), Some("chest"));
+        assert_eq!(tables.for_depth(7), Some("gems"));
+
+        // The shipped tables all parse and point at real tables
+        let shipped = loot_tables();
+        assert!(shipped.depths.iter().all(|depth| shipped.get(&depth.table).is_some()));
+    }
+}
@@ -0,0 +1,272 @@
+// Spells: lua scripts in assets/spells define them and decide what they do,
+// the game works out who a spell reaches and the script host carries out what they ask for
+
+use rlua::Table;
+
+use super::{Entity, EntityManager, Component, components};
+use crate::map::Map;
+use crate::map::los::{ProjectileHit, collidable_occupants};
+use crate::targeting::distance;
+
+/// Which tiles and entities a spell reaches from where it's aimed
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SpellShape {
+    /// Flies at the target and stops on the first thing in the way
+    Bolt,
+    /// Everything in sight of the target within the radius
+    Area,
+    /// Only the caster, no aiming
+    Caster,
+    /// An empty tile in sight, for blinks and summons
+    Tile
+}
+
+impl SpellShape {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bolt" => Some(SpellShape::Bolt),
+            "area" => Some(SpellShape::Area),
+            "self" => Some(SpellShape::Caster),
+            "tile" => Some(SpellShape::Tile),
+            _ => None
+        }
+    }
+
+    pub fn needs_target(&self) -> bool {
+        *self != SpellShape::Caster
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpellDef {
+    pub name: String,
+    pub description: String,
+    /// Mana spent on a successful cast
+    pub cost: i32,
+    pub range: usize,
+    pub shape: SpellShape,
+    pub radius: i32,
+    /// Drawn along the flight of bolts
+    pub glyph: char
+}
+
+/// Who and where a spell reaches
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reach {
+    pub target: (i32, i32),
+    pub entities: Vec<(Entity, (i32, i32))>,
+    /// Flight of a bolt for the renderer
+    pub path: Vec<(i32, i32)>
+}
+
+/// Every spell the scripts defined, kept on the "Grimoire" entity for the UI
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Grimoire {
+    pub spells: Vec<SpellDef>
+}
+
+impl Grimoire {
+    pub fn get(&self, name: &str) -> Option<&SpellDef> {
+        self.spells.iter().find(|spell| spell.name == name)
+    }
+}
+
+impl Component for Grimoire {
+    derive_component!();
+}
+
+/// Read the table a script passed to spell { ... }
+pub fn spell_def(table: &Table) -> rlua::Result<SpellDef> {
+    let name: String = table.get("name")?;
+    let shape: String = table.get("shape")?;
+
+    let shape = SpellShape::from_name(&shape)
+        .ok_or_else(|| rlua::Error::RuntimeError(format!("{} has unknown shape {}", name, shape)))?;
+
+    let glyph: Option<String> = table.get("glyph")?;
+
+    Ok(SpellDef {
+        name: name,
+        description: table.get::<_, Option<String>>("description")?.unwrap_or_default(),
+        cost: table.get("cost")?,
+        range: table.get::<_, Option<usize>>("range")?.unwrap_or(0),
+        shape: shape,
+        radius: table.get::<_, Option<i32>>("radius")?.unwrap_or(0),
+        glyph: glyph.and_then(|glyph| glyph.chars().next()).unwrap_or('*')
+    })
+}
+
+fn position(em: &EntityManager, entity: Entity) -> Option<(i32, i32)> {
+    get_component!(em, entity, components::Position).map(|position| (position.x, position.y))
+}
+
+/// Who and what a spell aimed at target reaches, or why it can't be cast there
+pub fn reach(em: &EntityManager, caster: Entity, spell: &SpellDef, target: (i32, i32)) -> Result<Reach, String> {
+    let origin = position(em, caster).ok_or("You are nowhere.")?;
+
+    if spell.shape == SpellShape::Caster {
+        return Ok(Reach { target: origin, entities: vec![(caster, origin)], path: Vec::new() });
+    }
+
+    let map = em.get_entity_by_name("Map")
+        .and_then(|map_entity| get_component!(em, map_entity, Map))
+        .ok_or("There is nowhere to cast.")?;
+
+    if spell.shape == SpellShape::Bolt {
+        if target == origin {
+            return Err("You need something to aim at.".to_string());
+        }
+
+        let mut occupants = collidable_occupants(em);
+        occupants.remove(&origin);
+
+        let trace = map.trace_projectile(origin, target, spell.range, &occupants);
+        let end = trace.end().unwrap_or(origin);
+
+        let entities = match trace.hit {
+            ProjectileHit::Entity(hit) if em.has_component(hit, components::Health::get_component_type()) => vec![(hit, end)],
+            _ => Vec::new()
+        };
+
+        return Ok(Reach { target: end, entities: entities, path: trace.path });
+    }
+
+    if distance(origin, target) > spell.range as i32 {
+        return Err("That's out of range.".to_string());
+    }
+
+    if !map.has_line_of_sight(origin, target) {
+        return Err("You can't see there.".to_string());
+    }
+
+    match spell.shape {
+        SpellShape::Tile => {
+            if map.is_blocked(target.0, target.1) || collidable_occupants(em).contains_key(&target) {
+                return Err("Something is in the way.".to_string());
+            }
+
+            Ok(Reach { target: target, entities: Vec::new(), path: Vec::new() })
+        }
+        _ => {
+            let entities = em.get_entities_with_components(components::Health::get_component_type())
+                .into_iter()
+                .filter_map(|entity| position(em, entity).map(|at| (entity, at)))
+                .filter(|(_, at)| distance(target, *at) <= spell.radius && map.has_line_of_sight(target, *at))
+                .collect();
+
+            Ok(Reach { target: target, entities: entities, path: Vec::new() })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+    use crate::scripts::{self, Scripts, ScriptEffect};
+    use components::{DamageType, StatusKind};
+
+    fn room() -> EntityManager {
+        let mut em = EntityManager::new();
+
+        let map_entity = em.create_entity();
+        em.set_entity_name(map_entity, "Map");
+        em.add_component(map_entity, MapBuilder::new(20, 20).create_room(&Rect::new(0, 0, 19, 19)).build());
+
+        em
+    }
+
+    fn creature(em: &mut EntityManager, x: i32, y: i32) -> Entity {
+        let entity = em.create_entity();
+        em.add_component(entity, components::Health { health: 10, max_health: 10 });
+        em.add_component(entity, components::Collidable);
+        em.add_component(entity, components::Position { x: x, y: y });
+        entity
+    }
+
+    fn def(shape: SpellShape, range: usize, radius: i32) -> SpellDef {
+        SpellDef {
+            name: "test".to_string(),
+            description: String::new(),
+            cost: 1,
+            range: range,
+            shape: shape,
+            radius: radius,
+            glyph: '*'
+        }
+    }
+
+    #[test]
+    fn it_should_register_and_cast_scripted_spells() {
+        let spells = Scripts::new();
+
+        spells.load(r#"
+            spell {
+                name = "frost touch",
+                cost = 2,
+                range = 1,
+                shape = "bolt",
+                cast = function(target)
+                    for _, victim in ipairs(target.targets) do
+                        damage(victim.id, 3, "Cold")
+                        status(victim.id, "Slowed", 2)
+                    end
+                    message("Brr.")
+                end
+            }
+        "#).unwrap();
+
+        let def = spells.spell("frost touch").unwrap();
+        assert_eq!(def.shape, SpellShape::Bolt);
+        assert_eq!(def.cost, 2);
+
+        let victim = Entity { id: 4 };
+        let reach = Reach { target: (1, 0), entities: vec![(victim, (1, 0))], path: Vec::new() };
+
+        assert_eq!(spells.cast("frost touch", Entity { id: 0 }, &reach).unwrap(), vec![
+            ScriptEffect::Damage { target: victim, amount: 3, kind: DamageType::Cold },
+            ScriptEffect::Status { target: victim, kind: StatusKind::Slowed, turns: 2, magnitude: 0 },
+            ScriptEffect::Message("Brr.".to_string())
+        ]);
+
+        assert!(spells.load(r#"spell { name = "bad", cost = 1, shape = "cone", cast = function() end }"#).is_err());
+        assert!(spells.cast("missing", Entity { id: 0 }, &reach).is_err());
+    }
+
+    #[test]
+    fn it_should_load_the_spell_scripts() {
+        let spells = Scripts::new();
+        spells.load_dir(scripts::folder("spells").unwrap()).unwrap();
+
+        for name in &["magic missile", "fireball", "heal", "blink", "raise dead"] {
+            assert!(spells.spell(name).is_some(), "missing {}", name);
+        }
+    }
+
+    #[test]
+    fn it_should_reach_by_shape() {
+        let mut em = room();
+
+        let caster = creature(&mut em, 2, 2);
+        let near = creature(&mut em, 5, 2);
+        let behind = creature(&mut em, 7, 2);
+        creature(&mut em, 10, 10);
+
+        let bolt = reach(&em, caster, &def(SpellShape::Bolt, 8, 0), (7, 2)).unwrap();
+        assert_eq!(bolt.entities, vec![(near, (5, 2))]);
+        assert_eq!(bolt.target, (5, 2));
+
+        let mut area = reach(&em, caster, &def(SpellShape::Area, 8, 2), (6, 2)).unwrap().entities;
+        area.sort_by_key(|(entity, _)| entity.id);
+        assert_eq!(area, vec![(near, (5, 2)), (behind, (7, 2))]);
+
+        assert!(reach(&em, caster, &def(SpellShape::Area, 8, 2), (10, 10)).is_ok());
+        assert!(reach(&em, caster, &def(SpellShape::Area, 4, 2), (10, 10)).is_err());
+
+        assert!(reach(&em, caster, &def(SpellShape::Tile, 8, 0), (5, 2)).is_err());
+        assert!(reach(&em, caster, &def(SpellShape::Tile, 8, 0), (4, 4)).is_ok());
+
+        assert_eq!(reach(&em, caster, &def(SpellShape::Caster, 0, 0), (9, 9)).unwrap().entities, vec![(caster, (2, 2))]);
+    }
+}
@@ -0,0 +1,241 @@
+// Flood fill connectivity checks and repair
+
+use std::collections::{HashSet, VecDeque};
+
+use super::{Map, MapBuilder, TileType};
+
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+impl Map {
+    /// Walkable tiles plus closed doors, which can be opened
+    pub fn is_passable(&self, x: i32, y: i32) -> bool {
+        !self.is_blocked(x, y) || self.get_tile(x, y) == TileType::DoorClosed
+    }
+
+    /// Every passable tile reachable from start by cardinal steps
+    pub fn flood_fill(&self, start: (i32, i32)) -> HashSet<(i32, i32)> {
+        let mut reached = HashSet::new();
+
+        if !self.is_passable(start.0, start.1) {
+            return reached;
+        }
+
+        let mut open = VecDeque::new();
+        open.push_back(start);
+        reached.insert(start);
+
+        while let Some((x, y)) = open.pop_front() {
+            for (dx, dy) in DIRECTIONS.iter() {
+                let next = (x + dx, y + dy);
+
+                if self.is_passable(next.0, next.1) && reached.insert(next) {
+                    open.push_back(next);
+                }
+            }
+        }
+
+        reached
+    }
+
+    /**
+     * Groups of passable tiles that can reach each other
+     * Largest region first
+     */
+    pub fn regions(&self) -> Vec<Vec<(i32, i32)>> {
+        let mut seen = HashSet::new();
+        let mut regions = Vec::new();
+
+        for y in 0..self.height() as i32 {
+            for x in 0..self.width() as i32 {
+                if seen.contains(&(x, y)) || !self.is_passable(x, y) {
+                    continue;
+                }
+
+                let mut region: Vec<_> = self.flood_fill((x, y)).into_iter().collect();
+                region.sort();
+
+                seen.extend(region.iter().cloned());
+                regions.push(region);
+            }
+        }
+
+        regions.sort_by(|a, b| b.len().cmp(&a.len()));
+
+        regions
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.regions().len() <= 1
+    }
+}
+
+impl MapBuilder {
+    /**
+     * Tunnel every smaller region into the largest one
+     * Each tunnel runs from the region to the closest tile already connected
+     */
+    pub fn connect_regions(mut self) -> Self {
+        let regions = self.map.regions();
+
+        if regions.len() <= 1 {
+            return self;
+        }
+
+        let mut connected = self.map.flood_fill(regions[0][0]);
+
+        for region in regions.iter().skip(1) {
+            // Already joined by an earlier tunnel passing through
+            if connected.contains(&region[0]) {
+                continue;
+            }
+
+            let (from, to) = match self.nearest_connected(region, &connected) {
+                Some(pair) => pair,
+                None => continue
+            };
+
+            debug!("Connecting region of {} tiles at {:?} to {:?}", region.len(), to, from);
+
+            // Dig across first, then down, the corner is shared
+            self = self
+                .create_h_tunnel(from.0, to.0, from.1)
+                .create_v_tunnel(from.1, to.1, to.0);
+
+            // The tunnel, the region and anything it cut through are connected now
+            grow(&self.map, &mut connected, to);
+
+            // Regions touching the map edge can't always be tunneled to
+            if !connected.contains(&region[0]) {
+                warn!("Could not connect map region at {:?}", region[0]);
+            }
+        }
+
+        self
+    }
+
+    /**
+     * Search outward from the region through rock for the closest connected tile
+     * Returns the connected tile and the region tile the search reached it from
+     */
+    fn nearest_connected(&self, region: &[(i32, i32)], connected: &HashSet<(i32, i32)>) -> Option<((i32, i32), (i32, i32))> {
+        let width = self.width;
+        let index = |(x, y): (i32, i32)| y as usize * width + x as usize;
+
+        let mut origins = vec![None; self.width * self.height];
+        let mut open = VecDeque::new();
+
+        for tile in region.iter() {
+            origins[index(*tile)] = Some(*tile);
+            open.push_back(*tile);
+        }
+
+        while let Some(current) = open.pop_front() {
+            let origin = origins[index(current)];
+
+            for (dx, dy) in DIRECTIONS.iter() {
+                let next = (current.0 + dx, current.1 + dy);
+
+                if !self.map.in_bounds(next.0, next.1) || origins[index(next)].is_some() {
+                    continue;
+                }
+
+                if connected.contains(&next) {
+                    return origin.map(|origin| (next, origin));
+                }
+
+                origins[index(next)] = origin;
+                open.push_back(next);
+            }
+        }
+
+        None
+    }
+}
+
+// Add everything reachable from start that isn't connected yet
+fn grow(map: &Map, connected: &mut HashSet<(i32, i32)>, start: (i32, i32)) {
+    if !map.is_passable(start.0, start.1) || !connected.insert(start) {
+        return;
+    }
+
+    let mut open = vec![start];
+
+    while let Some((x, y)) = open.pop() {
+        for (dx, dy) in DIRECTIONS.iter() {
+            let next = (x + dx, y + dy);
+
+            if map.is_passable(next.0, next.1) && connected.insert(next) {
+                open.push(next);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::map::MapGenerator;
+    use crate::types::Rect;
+
+    #[test]
+    fn it_should_find_separate_regions() {
+        let map = MapBuilder::new(30, 12)
+            .create_room(&Rect::new(0, 0, 10, 10))
+            .create_room(&Rect::new(15, 0, 5, 5))
+            .build();
+
+        let regions = map.regions();
+
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].len(), 81);
+        assert_eq!(regions[1].len(), 16);
+        assert!(!map.is_connected());
+    }
+
+    #[test]
+    fn it_should_connect_regions() {
+        let map = MapBuilder::new(40, 30)
+            .create_room(&Rect::new(0, 0, 10, 10))
+            .create_room(&Rect::new(15, 0, 5, 5))
+            .create_room(&Rect::new(25, 18, 8, 8))
+            .connect_regions()
+            .build();
+
+        assert!(map.is_connected());
+    }
+
+    #[test]
+    fn it_should_connect_many_pockets_in_one_pass() {
+        // Single tile pockets in a grid, hundreds of regions
+        let mut builder = MapBuilder::new(61, 61);
+
+        for y in (1..60).step_by(3) {
+            for x in (1..60).step_by(3) {
+                builder.dig_cell(x, y);
+            }
+        }
+
+        let map = builder.connect_regions().build();
+
+        assert!(map.is_connected());
+        assert_eq!(map.regions().len(), 1);
+    }
+
+    #[test]
+    fn it_should_generate_connected_maps() {
+        for generator in MapGenerator::ALL.iter() {
+            for seed in 0..25 {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let map = generator.generate_with_rng(100, 100, &mut rng);
+
+                let start = map.find_tiles(TileType::StairsUp)[0];
+                let reachable = map.flood_fill(start);
+
+                let unreachable = map.regions().iter().flatten().filter(|tile| !reachable.contains(tile)).count();
+                assert_eq!(unreachable, 0, "{:?} seed {} has {} unreachable tiles", generator, seed, unreachable);
+            }
+        }
+    }
+}
@@ -0,0 +1,326 @@
+// Dijkstra maps
+// http://www.roguebasin.com/index.php?title=The_Incredible_Power_of_Dijkstra_Maps
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use super::Map;
+use super::pathfinding::can_step;
+use crate::entities::EntityManager;
+use crate::components::{Component, self};
+
+/// Value of tiles that can't reach any goal
+pub const UNREACHABLE: f32 = std::f32::MAX;
+
+/// Flee maps scale the inverted distance by this so fleeing monsters
+/// prefer running past the player into open space over cornering themselves
+const FLEE_COEFFICIENT: f32 = -1.2;
+
+const DIRECTIONS: [(i32, i32); 8] = [
+    (0, -1), (1, 0), (0, 1), (-1, 0),
+    (1, -1), (1, 1), (-1, 1), (-1, -1)
+];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Node {
+    cost: f32,
+    position: (i32, i32)
+}
+
+impl Eq for Node {}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so the binary heap pops the cheapest node first
+        other.cost.partial_cmp(&self.cost).unwrap_or(Ordering::Equal)
+            .then_with(|| self.position.cmp(&other.position))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/**
+ * Distance from every tile to the nearest goal
+ * Walking downhill leads toward a goal, the flee map leads away from them
+ */
+#[derive(Debug, Clone)]
+pub struct DijkstraMap {
+    width: usize,
+    height: usize,
+    values: Vec<f32>,
+    goals: Vec<(i32, i32)>,
+    // Map and revision the values were computed against
+    revision: Option<(u64, u64)>
+}
+
+impl DijkstraMap {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width: width,
+            height: height,
+            values: vec![UNREACHABLE; width * height],
+            goals: Vec::new(),
+            revision: None
+        }
+    }
+
+    pub fn value(&self, x: i32, y: i32) -> Option<f32> {
+        if self.in_bounds(x, y) {
+            Some(self.values[self.index(x, y)]).filter(|value| *value != UNREACHABLE)
+        } else {
+            None
+        }
+    }
+
+    pub fn goals(&self) -> &[(i32, i32)] {
+        &self.goals
+    }
+
+    /// Recompute the distances from scratch
+    pub fn compute(&mut self, map: &Map, goals: &[(i32, i32)]) {
+        let mut goals = goals.to_vec();
+        goals.sort();
+        goals.dedup();
+
+        self.width = map.width();
+        self.height = map.height();
+        self.values = vec![UNREACHABLE; self.width * self.height];
+
+        for (x, y) in goals.iter().cloned() {
+            if self.in_bounds(x, y) {
+                let index = self.index(x, y);
+                self.values[index] = 0.0;
+            }
+        }
+
+        self.goals = goals;
+        self.revision = Some((map.id(), map.revision()));
+
+        self.scan(map);
+    }
+
+    /**
+     * Recompute only if the terrain or the goals have changed since the last run
+     * Returns whether the map was rebuilt
+     */
+    pub fn update(&mut self, map: &Map, goals: &[(i32, i32)]) -> bool {
+        let mut sorted = goals.to_vec();
+        sorted.sort();
+        sorted.dedup();
+
+        if self.revision == Some((map.id(), map.revision())) && sorted == self.goals {
+            return false;
+        }
+
+        self.compute(map, &sorted);
+
+        true
+    }
+
+    /// Derive a map that leads away from the goals
+    pub fn flee(&self, map: &Map) -> DijkstraMap {
+        let mut flee = self.clone();
+
+        for value in flee.values.iter_mut() {
+            if *value != UNREACHABLE {
+                *value *= FLEE_COEFFICIENT;
+            }
+        }
+
+        flee.scan(map);
+
+        flee
+    }
+
+    /// Lowest valued walkable neighbor of a position as a direction
+    pub fn downhill(&self, map: &Map, x: i32, y: i32) -> Option<(i32, i32)> {
+        let current = self.value(x, y)?;
+
+        DIRECTIONS.iter()
+            .filter(|direction| self.in_bounds(x + direction.0, y + direction.1) && can_step(map, (x, y), **direction))
+            .filter_map(|(dx, dy)| self.value(x + dx, y + dy).map(|value| ((*dx, *dy), value)))
+            .filter(|(_, value)| *value < current)
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .map(|(direction, _)| direction)
+    }
+
+    // Relax every tile from its current value, goals and flee seeds included
+    fn scan(&mut self, map: &Map) {
+        let mut open = BinaryHeap::new();
+
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                let value = self.values[self.index(x, y)];
+
+                if value != UNREACHABLE && !self.is_wall(map, x, y) {
+                    open.push(Node { cost: value, position: (x, y) });
+                }
+            }
+        }
+
+        while let Some(Node { cost, position: (x, y) }) = open.pop() {
+            if cost > self.values[self.index(x, y)] {
+                continue;
+            }
+
+            for (dx, dy) in DIRECTIONS.iter() {
+                let (nx, ny) = (x + dx, y + dy);
+
+                if !self.in_bounds(nx, ny) || !can_step(map, (x, y), (*dx, *dy)) {
+                    continue;
+                }
+
+                let index = self.index(nx, ny);
+                let next_cost = cost + map.movement_cost(nx, ny) as f32;
+
+                if next_cost < self.values[index] {
+                    self.values[index] = next_cost;
+                    open.push(Node { cost: next_cost, position: (nx, ny) });
+                }
+            }
+        }
+    }
+
+    fn is_wall(&self, map: &Map, x: i32, y: i32) -> bool {
+        !self.in_bounds(x, y) || map.is_blocked(x, y)
+    }
+
+    fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        y as usize * self.width + x as usize
+    }
+}
+
+/**
+ * Common goal sets
+ */
+pub fn player_goals(em: &EntityManager) -> Vec<(i32, i32)> {
+    positions_with(em, components::Player::get_component_type())
+}
+
+pub fn item_goals(em: &EntityManager) -> Vec<(i32, i32)> {
+    positions_with(em, components::Item::get_component_type())
+}
+
+/// Walkable tiles the player hasn't seen yet
+pub fn unexplored_goals(map: &Map) -> Vec<(i32, i32)> {
+    let mut goals = Vec::new();
+
+    for y in 0..map.height() as i32 {
+        for x in 0..map.width() as i32 {
+            if !map.is_revealed(x, y) && !map.is_blocked(x, y) {
+                goals.push((x, y));
+            }
+        }
+    }
+
+    goals
+}
+
+fn positions_with(em: &EntityManager, component_type: components::ComponentType) -> Vec<(i32, i32)> {
+    em.get_entities_with_components(component_type)
+        .iter()
+        .filter_map(|entity| get_component!(em, *entity, components::Position))
+        .map(|position| (position.x, position.y))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+
+    fn corridor() -> Map {
+        // Room from (1, 1) to (9, 8)
+        MapBuilder::new(12, 10)
+            .create_room(&Rect::new(0, 0, 10, 9))
+            .build()
+    }
+
+    #[test]
+    fn it_should_measure_distance_to_nearest_goal() {
+        let map = corridor();
+
+        let mut dijkstra = DijkstraMap::new(map.width(), map.height());
+        dijkstra.compute(&map, &[(1, 1), (9, 1)]);
+
+        assert_eq!(dijkstra.value(1, 1), Some(0.0));
+        assert_eq!(dijkstra.value(4, 1), Some(3.0));
+        assert_eq!(dijkstra.value(8, 1), Some(1.0));
+        assert_eq!(dijkstra.value(0, 0), None);
+    }
+
+    #[test]
+    fn it_should_walk_downhill_to_goal() {
+        let map = corridor();
+
+        let mut dijkstra = DijkstraMap::new(map.width(), map.height());
+        dijkstra.compute(&map, &[(1, 1)]);
+
+        assert_eq!(dijkstra.downhill(&map, 5, 5), Some((-1, -1)));
+        assert_eq!(dijkstra.downhill(&map, 1, 1), None);
+    }
+
+    #[test]
+    fn it_should_flee_away_from_goal() {
+        let map = corridor();
+
+        let mut dijkstra = DijkstraMap::new(map.width(), map.height());
+        dijkstra.compute(&map, &[(1, 1)]);
+
+        let flee = dijkstra.flee(&map);
+
+        let (dx, dy) = flee.downhill(&map, 3, 3).unwrap();
+        assert!(dx > 0 || dy > 0);
+    }
+
+    #[test]
+    fn it_should_only_rebuild_on_change() {
+        let mut map = corridor();
+
+        let mut dijkstra = DijkstraMap::new(map.width(), map.height());
+
+        assert!(dijkstra.update(&map, &[(1, 1)]));
+        assert!(!dijkstra.update(&map, &[(1, 1)]));
+        assert!(dijkstra.update(&map, &[(2, 2)]));
+
+        map.set_cell(3, 3, crate::map::Wall);
+        assert!(dijkstra.update(&map, &[(2, 2)]));
+        assert_eq!(dijkstra.value(3, 3), None);
+    }
+
+    #[test]
+    fn it_should_rebuild_for_another_map_at_the_same_revision() {
+        let map = corridor();
+        let other = corridor();
+
+        assert_eq!(map.revision(), other.revision());
+
+        let mut dijkstra = DijkstraMap::new(map.width(), map.height());
+
+        assert!(dijkstra.update(&map, &[(1, 1)]));
+        assert!(dijkstra.update(&other, &[(1, 1)]));
+    }
+
+    #[test]
+    fn it_should_not_cut_corners() {
+        let mut map = corridor();
+        map.set_cell(2, 1, crate::map::Wall);
+
+        let mut dijkstra = DijkstraMap::new(map.width(), map.height());
+        dijkstra.compute(&map, &[(1, 1)]);
+
+        // Around the wall rather than diagonally past it
+        assert_eq!(dijkstra.value(2, 2), Some(2.0));
+        assert_eq!(dijkstra.value(3, 1), Some(4.0));
+        assert_eq!(dijkstra.downhill(&map, 3, 1), Some((0, 1)));
+    }
+}
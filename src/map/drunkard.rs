@@ -0,0 +1,117 @@
+// Drunkard's walk cave generation
+// http://www.roguebasin.com/index.php?title=Random_Walk_Cave_Generation
+
+use rand::{thread_rng, Rng};
+
+use super::{Map, MapBuilder, TileType, Placement};
+
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Rounds of walkers before giving up on reaching the floor target
+const MAX_ROUNDS: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct DrunkardOptions {
+    /// Share of the map, edges excluded, dug out before stopping
+    pub floor_percent: f32,
+    /// Walkers sent out each round, each starts somewhere already dug
+    pub walkers: usize,
+    /// Steps a walker takes before it sobers up
+    pub steps_per_walker: usize
+}
+
+impl Default for DrunkardOptions {
+    fn default() -> Self {
+        Self {
+            floor_percent: 0.4,
+            walkers: 4,
+            steps_per_walker: 200
+        }
+    }
+}
+
+pub fn drunkard_map_gen(width: usize, height: usize) -> Map {
+    drunkard_map_gen_with_rng(width, height, &DrunkardOptions::default(), &mut thread_rng())
+}
+
+/**
+ * Carve caves by letting walkers stumble around digging out every tile they step on
+ * Walkers always start on dug floor so the cave comes out in one piece
+ */
+pub fn drunkard_map_gen_with_rng<R: Rng>(width: usize, height: usize, options: &DrunkardOptions, rng: &mut R) -> Map {
+    let mut builder = MapBuilder::new(width, height);
+
+    if width < 3 || height < 3 {
+        return builder.build();
+    }
+
+    let interior = (width - 2) * (height - 2);
+    let target = ((interior as f32 * options.floor_percent) as usize).max(1).min(interior);
+
+    let start = (width as i32 / 2, height as i32 / 2);
+    builder.dig_cell(start.0, start.1);
+
+    let mut floors = vec![start];
+
+    for _ in 0..MAX_ROUNDS {
+        for _ in 0..options.walkers {
+            let (mut x, mut y) = floors[rng.gen_range(0, floors.len())];
+
+            for _ in 0..options.steps_per_walker {
+                if floors.len() >= target {
+                    break;
+                }
+
+                let (dx, dy) = DIRECTIONS[rng.gen_range(0, DIRECTIONS.len())];
+
+                // Stumble into the edge and stay put
+                if x + dx <= 0 || y + dy <= 0 || x + dx >= width as i32 - 1 || y + dy >= height as i32 - 1 {
+                    continue;
+                }
+
+                x += dx;
+                y += dy;
+
+                if builder.map.get_tile(x, y) == TileType::Wall {
+                    builder.dig_cell(x, y);
+                    floors.push((x, y));
+                }
+            }
+        }
+
+        if floors.len() >= target {
+            break;
+        }
+    }
+
+    debug!("Drunkard's walk dug {} of {} tiles", floors.len(), target);
+
+    builder
+        .place_prefabs(Placement::Rock, rng)
+        .connect_regions()
+        .place_stairs()
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn it_should_dig_requested_floor_percent() {
+        let options = DrunkardOptions { floor_percent: 0.3, ..DrunkardOptions::default() };
+        let mut rng = StdRng::seed_from_u64(3);
+
+        let map = drunkard_map_gen_with_rng(40, 30, &options, &mut rng);
+
+        let floor = map.get_cells().iter().filter(|cell| !cell.blocked).count();
+        let interior = 38 * 28;
+
+        // Stairs replace two floor tiles but are walkable too
+        assert!(floor as f32 >= interior as f32 * 0.3 - 1.0);
+        assert!(floor < interior / 2);
+        assert!(map.is_connected());
+    }
+}
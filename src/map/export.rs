@@ -0,0 +1,194 @@
+// Dump maps to the text map format and to png for looking over generator output
+
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+
+use super::Map;
+
+/// Tileset background color, drawn as black
+const MAGENTA: [u8; 3] = [255, 0, 255];
+
+/**
+ * A grid of 16 by 16 glyphs laid out in code page 437 order,
+ * like assets/gfx/Aesomatica_16x16.png
+ */
+#[derive(Debug, Clone)]
+pub struct Tileset {
+    pixels: Vec<u8>,
+    width: usize,
+    tile_size: usize
+}
+
+impl Tileset {
+    pub fn load(filename: &str, tile_size: usize) -> Result<Self, Box<dyn Error>> {
+        let mut decoder = png::Decoder::new(File::open(filename)?);
+        // Palette and grayscale images come out as rgb or rgba
+        decoder.set_transformations(png::Transformations::EXPAND);
+
+        let (info, mut reader) = decoder.read_info()?;
+
+        let mut buffer = vec![0; info.buffer_size()];
+        reader.next_frame(&mut buffer)?;
+
+        let channels = match info.color_type {
+            png::ColorType::RGB => 3,
+            png::ColorType::RGBA => 4,
+            png::ColorType::GrayscaleAlpha => 2,
+            _ => 1
+        };
+
+        // Normalize to rgb
+        let pixels = buffer.chunks(channels)
+            .flat_map(|pixel| match channels {
+                3 | 4 => vec![pixel[0], pixel[1], pixel[2]],
+                _ => vec![pixel[0], pixel[0], pixel[0]]
+            })
+            .collect();
+
+        Ok(Self {
+            pixels: pixels,
+            width: info.width as usize,
+            tile_size: tile_size
+        })
+    }
+
+    pub fn tile_size(&self) -> usize {
+        self.tile_size
+    }
+
+    /// Pixel of a glyph, glyphs outside code page 437 draw as '?'
+    fn pixel(&self, glyph: char, x: usize, y: usize) -> [u8; 3] {
+        let code = if (glyph as u32) < 256 { glyph as usize } else { '?' as usize };
+
+        let tiles_per_row = self.width / self.tile_size;
+        let px = (code % tiles_per_row) * self.tile_size + x;
+        let py = (code / tiles_per_row) * self.tile_size + y;
+
+        let index = (py * self.width + px) * 3;
+
+        match self.pixels.get(index..(index + 3)) {
+            Some(rgb) => [rgb[0], rgb[1], rgb[2]],
+            None => [0, 0, 0]
+        }
+    }
+}
+
+impl Map {
+    /// The map in the MAP/ENDMAP text format, readable by Map::from_buffer
+    pub fn to_buffer(&self) -> String {
+        let mut buffer = String::from("MAP\n");
+
+        for y in 0..self.height() as i32 {
+            for x in 0..self.width() as i32 {
+                buffer.push(self.get_cell_ref(x, y).glyph);
+            }
+
+            buffer.push('\n');
+        }
+
+        buffer.push_str("ENDMAP\n");
+
+        buffer
+    }
+
+    pub fn save_text(&self, filename: &str) -> std::io::Result<()> {
+        std::fs::write(filename, self.to_buffer())
+    }
+
+    /**
+     * Rgb pixels of the map drawn with the tileset
+     * Each glyph is tinted with its tile color
+     */
+    pub fn render_pixels(&self, tileset: &Tileset) -> Vec<u8> {
+        let size = tileset.tile_size();
+        let image_width = self.width() * size;
+
+        let mut pixels = vec![0; image_width * self.height() * size * 3];
+
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let cell = self.get_cell_ref(x as i32, y as i32);
+                let color = cell.tile.def().color;
+
+                for ty in 0..size {
+                    for tx in 0..size {
+                        let glyph_pixel = tileset.pixel(cell.glyph, tx, ty);
+
+                        if glyph_pixel == MAGENTA {
+                            continue;
+                        }
+
+                        let index = ((y * size + ty) * image_width + x * size + tx) * 3;
+
+                        for channel in 0..3 {
+                            pixels[index + channel] = (glyph_pixel[channel] as u16 * color[channel] as u16 / 255) as u8;
+                        }
+                    }
+                }
+            }
+        }
+
+        pixels
+    }
+
+    pub fn save_png(&self, filename: &str, tileset: &Tileset) -> Result<(), Box<dyn Error>> {
+        let size = tileset.tile_size();
+        let writer = BufWriter::new(File::create(filename)?);
+
+        let mut encoder = png::Encoder::new(writer, (self.width() * size) as u32, (self.height() * size) as u32);
+        encoder.set_color(png::ColorType::RGB);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.render_pixels(tileset))?;
+
+        Ok(())
+    }
+}
+
+/// Write both exports next to each other, as <path>.map and <path>.png
+pub fn export_map(map: &Map, path: &str, tileset: Option<&Tileset>) -> Result<(), Box<dyn Error>> {
+    map.save_text(&format!("{}.map", path))?;
+
+    if let Some(tileset) = tileset {
+        map.save_png(&format!("{}.png", path), tileset)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::TileType;
+
+    #[test]
+    fn it_should_round_trip_text_format() {
+        let map = Map::from_buffer(include_str!("../../assets/test.map"));
+        let copy = Map::from_buffer(&map.to_buffer());
+
+        assert_eq!((copy.width(), copy.height()), (map.width(), map.height()));
+        assert_eq!(copy.get_tile(18, 4), TileType::DoorClosed);
+
+        let tiles = |map: &Map| map.get_cells().iter().map(|cell| cell.tile).collect::<Vec<_>>();
+        assert_eq!(tiles(&copy), tiles(&map));
+    }
+
+    #[test]
+    fn it_should_draw_with_tileset() {
+        let tileset = Tileset::load("assets/gfx/Aesomatica_16x16.png", 16).unwrap();
+        let map = Map::from_buffer("#.\n..");
+
+        let pixels = map.render_pixels(&tileset);
+
+        assert_eq!(pixels.len(), 32 * 32 * 3);
+
+        // Something of the wall glyph got drawn in the top left tile
+        let lit = (0..16)
+            .flat_map(|y| (0..16).map(move |x| (y * 32 + x) * 3))
+            .any(|index| pixels[index..(index + 3)].iter().any(|channel| *channel > 0));
+
+        assert!(lit);
+    }
+}
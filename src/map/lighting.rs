@@ -0,0 +1,118 @@
+// Light levels computed over the field of view of each light source
+
+use super::Map;
+
+/// Sight range monsters can notice things in
+pub const NOTICE_RANGE: i32 = 10;
+
+const WHITE: [u8; 3] = [255, 255, 255];
+
+impl Map {
+    /// Reset every tile to the ambient level before casting lights
+    pub fn clear_light(&mut self, ambient: f32) {
+        for level in self.light.iter_mut() {
+            *level = ambient;
+        }
+
+        for color in self.light_color.iter_mut() {
+            *color = WHITE;
+        }
+    }
+
+    /**
+     * Light the tiles a source can see, fading out toward its radius
+     * Overlapping lights add up and their colors blend by brightness
+     */
+    pub fn cast_light(&mut self, origin: (i32, i32), radius: i32, color: [u8; 3]) {
+        for (x, y) in self.field_of_view(origin, radius) {
+            let distance = (((x - origin.0).pow(2) + (y - origin.1).pow(2)) as f32).sqrt();
+            let added = 1.0 - distance / (radius + 1) as f32;
+
+            if added <= 0.0 {
+                continue;
+            }
+
+            let index = self.index(x, y);
+            let current = self.light[index];
+
+            let blend = |old: u8, new: u8| ((old as f32 * current + new as f32 * added) / (current + added)) as u8;
+            let old_color = self.light_color[index];
+
+            self.light_color[index] = [
+                blend(old_color[0], color[0]),
+                blend(old_color[1], color[1]),
+                blend(old_color[2], color[2])
+            ];
+            self.light[index] = (current + added).min(1.0);
+        }
+    }
+
+    /// 0 is pitch black, 1 fully lit, off the map is dark
+    pub fn light_level(&self, x: i32, y: i32) -> f32 {
+        if self.in_bounds(x, y) {
+            self.light[self.index(x, y)]
+        } else {
+            0.0
+        }
+    }
+
+    pub fn light_color(&self, x: i32, y: i32) -> [u8; 3] {
+        if self.in_bounds(x, y) {
+            self.light_color[self.index(x, y)]
+        } else {
+            WHITE
+        }
+    }
+}
+
+/**
+ * Chance an observer notices something standing in the given light
+ * Anything adjacent is always noticed, past the notice range nothing is
+ */
+pub fn notice_chance(light: f32, distance: i32, stealth: i32) -> f32 {
+    if distance <= 1 {
+        return 1.0;
+    }
+
+    if distance > NOTICE_RANGE {
+        return 0.0;
+    }
+
+    let concealment = 1.0 - stealth.max(0).min(100) as f32 / 100.0;
+    let falloff = 1.0 - distance as f32 / (NOTICE_RANGE + 1) as f32;
+
+    (light * concealment * falloff).max(0.0).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+
+    #[test]
+    fn it_should_fade_light_with_distance() {
+        let mut map = MapBuilder::new(20, 12)
+            .create_room(&Rect::new(0, 0, 10, 10))
+            .build();
+
+        map.clear_light(0.0);
+        map.cast_light((5, 5), 4, [255, 0, 0]);
+
+        assert_eq!(map.light_level(5, 5), 1.0);
+        assert!(map.light_level(7, 5) < map.light_level(6, 5));
+        assert!((map.light_level(5, 1) - 0.2).abs() < 0.001);
+        assert_eq!(map.light_color(5, 5), [255, 0, 0]);
+
+        // Walls keep light out of the next room
+        assert_eq!(map.light_level(12, 5), 0.0);
+    }
+
+    #[test]
+    fn it_should_notice_more_in_light() {
+        assert_eq!(notice_chance(0.0, 1, 100), 1.0);
+        assert_eq!(notice_chance(1.0, NOTICE_RANGE + 1, 0), 0.0);
+        assert!(notice_chance(1.0, 5, 20) > notice_chance(0.2, 5, 20));
+        assert!(notice_chance(1.0, 5, 20) > notice_chance(1.0, 5, 80));
+    }
+}
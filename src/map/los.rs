@@ -0,0 +1,179 @@
+// Line of sight and projectile tracing built on bresenham lines
+
+use std::collections::HashMap;
+
+use cgmath::Point2;
+
+use super::Map;
+use crate::bresenham::get_line;
+use crate::entities::{Entity, EntityManager};
+use crate::components::{Component, Collidable, Position};
+
+/// What stopped a projectile
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ProjectileHit {
+    Entity(Entity),
+    Wall,
+    /// Reached the target or ran out of range without hitting anything
+    Nothing
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectileTrace {
+    /// Tiles the projectile passed through, excluding the origin
+    pub path: Vec<(i32, i32)>,
+    pub hit: ProjectileHit
+}
+
+impl ProjectileTrace {
+    /// Where the projectile came to rest
+    pub fn end(&self) -> Option<(i32, i32)> {
+        self.path.last().cloned()
+    }
+}
+
+fn line(a: (i32, i32), b: (i32, i32)) -> Vec<(i32, i32)> {
+    get_line(Point2::new(a.0, a.1), Point2::new(b.0, b.1))
+        .into_iter()
+        .map(|point| (point.x, point.y))
+        .collect()
+}
+
+impl Map {
+    fn blocks_sight(&self, x: i32, y: i32) -> bool {
+        self.get(x, y).map_or(true, |cell| cell.block_sight)
+    }
+
+    /**
+     * Whether b can be seen from a
+     * Only the tiles between the two ends are checked so walls themselves can be seen
+     */
+    pub fn has_line_of_sight(&self, a: (i32, i32), b: (i32, i32)) -> bool {
+        if !self.in_bounds(a.0, a.1) || !self.in_bounds(b.0, b.1) {
+            return false;
+        }
+
+        let points = line(a, b);
+
+        points.iter()
+            .skip(1)
+            .take(points.len().saturating_sub(2))
+            .all(|(x, y)| !self.blocks_sight(*x, *y))
+    }
+
+    /// Every tile within radius visible from the origin
+    pub fn field_of_view(&self, origin: (i32, i32), radius: i32) -> Vec<(i32, i32)> {
+        let mut visible = Vec::new();
+
+        for y in (origin.1 - radius)..(origin.1 + radius + 1) {
+            for x in (origin.0 - radius)..(origin.0 + radius + 1) {
+                let dx = x - origin.0;
+                let dy = y - origin.1;
+
+                if dx * dx + dy * dy <= radius * radius && self.has_line_of_sight(origin, (x, y)) {
+                    visible.push((x, y));
+                }
+            }
+        }
+
+        visible
+    }
+
+    /**
+     * Follow a projectile from origin toward target for at most range tiles
+     * Stops on the first blocking tile or collidable entity in the way
+     */
+    pub fn trace_projectile(
+        &self,
+        origin: (i32, i32),
+        target: (i32, i32),
+        range: usize,
+        occupants: &HashMap<(i32, i32), Entity>
+    ) -> ProjectileTrace {
+        let mut path = Vec::new();
+
+        for (x, y) in line(origin, target).into_iter().skip(1).take(range) {
+            if self.is_blocked(x, y) {
+                return ProjectileTrace { path: path, hit: ProjectileHit::Wall };
+            }
+
+            path.push((x, y));
+
+            if let Some(entity) = occupants.get(&(x, y)) {
+                return ProjectileTrace { path: path, hit: ProjectileHit::Entity(*entity) };
+            }
+        }
+
+        ProjectileTrace { path: path, hit: ProjectileHit::Nothing }
+    }
+}
+
+/// Collidable entities by tile, for projectile traces
+pub fn collidable_occupants(em: &EntityManager) -> HashMap<(i32, i32), Entity> {
+    em.get_entities_with_components(Collidable::get_component_type())
+        .iter()
+        .filter_map(|entity| get_component!(em, *entity, Position).map(|position| ((position.x, position.y), *entity)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+
+    fn room_with_pillar() -> Map {
+        let mut map = MapBuilder::new(12, 12)
+            .create_room(&Rect::new(0, 0, 10, 10))
+            .build();
+
+        map.set_cell(5, 5, crate::map::Wall);
+
+        map
+    }
+
+    #[test]
+    fn it_should_be_blocked_by_walls() {
+        let map = room_with_pillar();
+
+        assert!(map.has_line_of_sight((2, 2), (8, 2)));
+        assert!(!map.has_line_of_sight((3, 5), (7, 5)));
+        // The pillar itself is visible
+        assert!(map.has_line_of_sight((3, 5), (5, 5)));
+    }
+
+    #[test]
+    fn it_should_not_see_out_of_bounds() {
+        let map = room_with_pillar();
+
+        assert!(!map.has_line_of_sight((2, 2), (-3, 2)));
+    }
+
+    #[test]
+    fn it_should_stop_projectile_at_first_entity() {
+        let map = room_with_pillar();
+
+        let mut occupants = HashMap::new();
+        occupants.insert((4, 2), Entity { id: 7 });
+        occupants.insert((6, 2), Entity { id: 8 });
+
+        let trace = map.trace_projectile((2, 2), (8, 2), 10, &occupants);
+
+        assert_eq!(trace.hit, ProjectileHit::Entity(Entity { id: 7 }));
+        assert_eq!(trace.path, vec![(3, 2), (4, 2)]);
+    }
+
+    #[test]
+    fn it_should_stop_projectile_at_wall_and_range() {
+        let map = room_with_pillar();
+        let occupants = HashMap::new();
+
+        let trace = map.trace_projectile((3, 5), (8, 5), 10, &occupants);
+        assert_eq!(trace.hit, ProjectileHit::Wall);
+        assert_eq!(trace.end(), Some((4, 5)));
+
+        let trace = map.trace_projectile((2, 2), (8, 2), 3, &occupants);
+        assert_eq!(trace.hit, ProjectileHit::Nothing);
+        assert_eq!(trace.end(), Some((5, 2)));
+    }
+}
@@ -0,0 +1,93 @@
+// Recursive backtracker maze generation
+// http://weblog.jamisbuck.org/2010/12/27/maze-generation-recursive-backtracking
+
+use rand::{thread_rng, Rng};
+
+use super::{Map, MapBuilder, Placement};
+
+const DIRECTIONS: [(i32, i32); 4] = [(0, -2), (2, 0), (0, 2), (-2, 0)];
+
+pub fn maze_map_gen(width: usize, height: usize) -> Map {
+    maze_map_gen_with_rng(width, height, &mut thread_rng())
+}
+
+/**
+ * Perfect maze where passages run along odd coordinates
+ * Walls sit between them, so there is exactly one route between any two tiles
+ */
+pub fn maze_map_gen_with_rng<R: Rng>(width: usize, height: usize, rng: &mut R) -> Map {
+    let mut builder = MapBuilder::new(width, height);
+
+    // Passage tiles that still have a wall on the far side of the map edge
+    let cells_x = (width as i32 - 1) / 2;
+    let cells_y = (height as i32 - 1) / 2;
+
+    if cells_x < 1 || cells_y < 1 {
+        return builder.build();
+    }
+
+    let mut visited = vec![false; (cells_x * cells_y) as usize];
+    let visit_index = |(x, y): (i32, i32)| ((y / 2) * cells_x + x / 2) as usize;
+
+    let start = (1, 1);
+    builder.dig_cell(start.0, start.1);
+    visited[visit_index(start)] = true;
+
+    // Iterative so large mazes don't overflow the stack
+    let mut stack = vec![start];
+
+    while let Some(&(x, y)) = stack.last() {
+        let unvisited: Vec<(i32, i32)> = DIRECTIONS.iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .filter(|(nx, ny)| *nx > 0 && *ny > 0 && *nx < cells_x * 2 && *ny < cells_y * 2)
+            .filter(|next| !visited[visit_index(*next)])
+            .collect();
+
+        if unvisited.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        let next = unvisited[rng.gen_range(0, unvisited.len())];
+
+        // Knock down the wall between the two passages
+        builder.dig_cell((x + next.0) / 2, (y + next.1) / 2);
+        builder.dig_cell(next.0, next.1);
+
+        visited[visit_index(next)] = true;
+        stack.push(next);
+    }
+
+    // Only fits where the maze leaves rock at the edges, joined to the maze if it does
+    builder
+        .place_prefabs(Placement::Rock, rng)
+        .connect_regions()
+        .place_stairs()
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn it_should_carve_a_perfect_maze() {
+        let mut rng = StdRng::seed_from_u64(11);
+
+        let map = maze_map_gen_with_rng(21, 15, &mut rng);
+
+        // Every passage tile is open
+        for y in (1..15).step_by(2) {
+            for x in (1..21).step_by(2) {
+                assert!(!map.is_blocked(x, y));
+            }
+        }
+
+        // A spanning tree over 10x7 passages has one fewer opening than passages
+        let open = map.get_cells().iter().filter(|cell| !cell.blocked).count();
+        assert_eq!(open, 70 + 69);
+        assert!(map.is_connected());
+    }
+}
@@ -0,0 +1,883 @@
+use std::str::FromStr;
+
+use rand::{thread_rng, Rng};
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::types::{Rect, Dimension};
+use crate::components::Component;
+
+pub mod pathfinding;
+pub use self::pathfinding::{Path, PathOptions, Movement};
+
+pub mod dijkstra;
+pub use self::dijkstra::DijkstraMap;
+
+pub mod los;
+pub use self::los::{ProjectileTrace, ProjectileHit};
+
+pub mod tiles;
+pub use self::tiles::{TileType, TileDef, TileTable};
+
+pub mod connectivity;
+
+pub mod prefab;
+pub use self::prefab::{Prefab, Spawn, Placement};
+
+pub mod drunkard;
+pub use self::drunkard::{DrunkardOptions, drunkard_map_gen, drunkard_map_gen_with_rng};
+
+pub mod maze;
+pub use self::maze::{maze_map_gen, maze_map_gen_with_rng};
+
+pub mod wfc;
+pub use self::wfc::{WfcModel, wfc_map_gen, wfc_map_gen_with_rng};
+
+pub mod lighting;
+
+pub mod export;
+pub use self::export::{Tileset, export_map};
+
+static NEXT_MAP_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Copy, Clone)]
+pub struct Cell {
+    pub tile: TileType,
+    pub glyph: char,
+    pub blocked: bool,
+    pub block_sight: bool
+}
+
+impl Cell {
+    pub fn new(tile: TileType) -> Self {
+        let def = tile.def();
+
+        Self {
+            tile: tile,
+            blocked: !def.walkable,
+            block_sight: def.opaque,
+            glyph: def.glyph,
+            // prop: None,
+            // item: None,
+            // entity: None
+        }
+    }
+}
+
+// A map is a 2d grid of tiles
+#[derive(Debug)]
+pub struct Map {
+    cells: Vec<Cell>,
+    pub rooms: Vec<Rect>,
+    width: usize,
+    height: usize,
+    // Cells the player has seen
+    revealed: Vec<bool>,
+    // Tells maps apart, cached path data is keyed on it along with the revision
+    id: u64,
+    // Bumped on every terrain change so cached path data knows when to rebuild
+    revision: u64,
+    // Entities prefabs asked for, created when the level is populated
+    spawns: Vec<Spawn>,
+    // Light reaching each cell and the color it has been tinted
+    light: Vec<f32>,
+    light_color: Vec<[u8; 3]>,
+}
+
+impl Map {
+    /// Solid rock, generators carve the floor out of it
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width: width,
+            height: height,
+            cells: vec![Wall; width * height],
+            rooms: Vec::new(),
+            revealed: vec![false; width * height],
+            id: NEXT_MAP_ID.fetch_add(1, Ordering::Relaxed),
+            revision: 0,
+            spawns: Vec::new(),
+            // Fully lit until a lighting pass says otherwise
+            light: vec![1.0; width * height],
+            light_color: vec![[255, 255, 255]; width * height]
+        }
+    }
+
+    pub fn fill(&mut self, cell: Cell) {
+        self.cells = vec![cell; self.width * self.height];
+        self.revision += 1;
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /**
+     * Fill room with walls
+     */
+    pub fn init_cells(width: usize, height: usize) -> Vec<Cell> {
+        let mut cells = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                cells.push(Cell::new(TileType::Wall));
+            }
+        }
+
+
+        cells
+    }
+
+    pub fn cell_index(&self, x: i32, y: i32) -> usize {
+        y as usize * self.width + x as usize
+    }
+
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<&Cell> {
+        if self.in_bounds(x, y) {
+            self.cells.get(self.index(x, y))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: i32, y: i32) -> Option<&mut Cell> {
+        if self.in_bounds(x, y) {
+            self.revision += 1;
+
+            let index = self.index(x, y);
+            self.cells.get_mut(index)
+        } else {
+            None
+        }
+    }
+
+    /// Anything off the edge of the map is blocked
+    pub fn is_blocked(&self, x: i32, y: i32) -> bool {
+        self.get(x, y).map_or(true, |cell| cell.blocked)
+    }
+
+    pub fn get_cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// Off the map reads as wall
+    pub fn get_tile(&self, x: i32, y: i32) -> TileType {
+        self.get(x, y).map_or(TileType::Wall, |cell| cell.tile)
+    }
+
+    pub fn set_tile(&mut self, x: i32, y: i32, tile: TileType) {
+        self.set_cell(x, y, Cell::new(tile));
+    }
+
+    pub fn movement_cost(&self, x: i32, y: i32) -> i32 {
+        self.get_tile(x, y).def().movement_cost
+    }
+
+    pub fn get_dimensions(&self) -> Dimension {
+        Dimension { width: self.width as i32, height: self.height as i32 }
+    }
+
+    // Returns (width, height) of string grid buffer
+    fn get_buffer_dimensions(buffer: &str) -> (usize, usize) {
+        // Split each line by new line
+        let lines = buffer.lines();
+
+        // Count number of lines to get max_height
+        let height = lines.count();
+
+
+        // Potential optimization 
+        // The first lines iterator is consumed by count
+        // so have to create another one
+        let lines = buffer.lines();
+
+        let width = lines.map(|line| line.chars().count()).max().unwrap_or(0);
+
+        debug!("Load map buffer with dimensions (w, h): ({}, {})", width, height);
+
+        (width, height)
+    }
+
+    /**
+     * Load a map from the text map format, see assets/test.map
+     */
+    pub fn from_buffer(buffer: &str) -> Self {
+        let body = buffer.lines()
+            .filter(|line| !line.trim().starts_with("MAP") && line.trim() != "ENDMAP")
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let (width, height) = Map::get_buffer_dimensions(&body);
+
+        let mut map = Map::new(width, height);
+        map.cells = create_cells_from_buffer(&body, width, height);
+
+        map
+    }
+
+    /// Panics when out of bounds, use `get` for positions that may be off the map
+    pub fn get_cell_ref(&self, x: i32, y: i32) -> &Cell {
+        self.get(x, y).unwrap_or_else(|| panic!("Cell ({}, {}) is outside the map", x, y))
+    }
+
+    /// Panics when out of bounds, use `get_mut` for positions that may be off the map
+    pub fn get_mut_cell_ref(&mut self, x: i32, y: i32) -> &mut Cell {
+        let (width, height) = (self.width, self.height);
+
+        self.get_mut(x, y)
+            .unwrap_or_else(|| panic!("Cell ({}, {}) is outside the {}x{} map", x, y, width, height))
+    }
+
+    /// Writes outside the map are ignored
+    pub fn set_cell(&mut self, x: i32, y: i32, cell: Cell) {
+        if let Some(target) = self.get_mut(x, y) {
+            *target = cell;
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn reveal(&mut self, x: i32, y: i32) {
+        if self.in_bounds(x, y) {
+            let index = self.index(x, y);
+            self.revealed[index] = true;
+        }
+    }
+
+    pub fn is_revealed(&self, x: i32, y: i32) -> bool {
+        self.in_bounds(x, y) && self.revealed[self.index(x, y)]
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        y as usize * self.width + x as usize
+    }
+
+    /// Positions of every tile of a type
+    pub fn find_tiles(&self, tile: TileType) -> Vec<(i32, i32)> {
+        self.cells.iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.tile == tile)
+            .map(|(index, _)| ((index % self.width) as i32, (index / self.width) as i32))
+            .collect()
+    }
+
+    /// Closest tile to a point that passes the test, the point itself first
+    pub fn nearest<F>(&self, from: (i32, i32), test: F) -> Option<(i32, i32)>
+        where F: Fn(i32, i32) -> bool
+    {
+        let in_bounds = |x: i32, y: i32| x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32;
+
+        for radius in 0..self.width.max(self.height) as i32 {
+            for dy in -radius..(radius + 1) {
+                for dx in -radius..(radius + 1) {
+                    // Only the ring at this distance, the inside was already searched
+                    if dx.abs() != radius && dy.abs() != radius {
+                        continue;
+                    }
+
+                    let (x, y) = (from.0 + dx, from.1 + dy);
+
+                    if in_bounds(x, y) && test(x, y) {
+                        return Some((x, y));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn get_rooms(&self) -> &[Rect] {
+        &self.rooms
+    }
+
+    pub fn set_rooms(&mut self, rooms: Vec<Rect>) {
+        self.rooms = rooms;
+    }
+
+    pub fn spawns(&self) -> &[Spawn] {
+        &self.spawns
+    }
+}
+
+// Stored on the "Map" entity so systems can query terrain
+impl Component for Map {
+    derive_component!();
+}
+
+pub struct MapBuilder {
+    width: usize,
+    height: usize,
+    map: Map,
+}
+
+impl MapBuilder {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width: width,
+            height: height,
+            map: Map::new(width, height)
+        }
+    }
+
+    /// Keep building on an existing map
+    pub fn from_map(map: Map) -> Self {
+        Self {
+            width: map.width(),
+            height: map.height(),
+            map: map
+        }
+    }
+
+    /// Rooms hanging off the map are clamped so they keep a wall on every side
+    pub fn create_room(mut self, room: &Rect) -> Self {
+        let room = self.clamp_rect(room);
+
+        for x in (room.x1 + 1)..room.x2 {
+            for y in (room.y1 + 1)..room.y2 {
+                self.dig_cell(x, y);
+            }
+        }
+
+        self.map.rooms.push(room);
+
+        self
+    }
+
+    pub fn create_h_tunnel(mut self, x1: i32, x2: i32, y: i32) -> Self {
+        for x in x1.min(x2)..(x1.max(x2) + 1) {
+            self.dig_cell(x, y);
+        }
+
+        self
+    }
+
+    pub fn create_v_tunnel(mut self, y1: i32, y2: i32, x: i32) -> Self {
+        for y in y1.min(y2)..(y1.max(y2) + 1) {
+            self.dig_cell(x, y);
+        }
+
+        self
+    }
+
+    fn clamp_rect(&self, rect: &Rect) -> Rect {
+        let max_x = self.width as i32 - 1;
+        let max_y = self.height as i32 - 1;
+
+        Rect {
+            x1: rect.x1.max(0).min(max_x),
+            y1: rect.y1.max(0).min(max_y),
+            x2: rect.x2.max(0).min(max_x),
+            y2: rect.y2.max(0).min(max_y)
+        }
+    }
+
+    /// Turn a tile into floor, the outer edge is never dug so the map stays closed
+    pub fn dig_cell(&mut self, x: i32, y: i32) {
+        if x <= 0 || y <= 0 || x >= self.width as i32 - 1 || y >= self.height as i32 - 1 {
+            return;
+        }
+
+        self.map.set_tile(x, y, TileType::Floor);
+    }
+
+    /**
+     * Put closed doors where corridors break through room walls
+     */
+    pub fn place_doors(mut self) -> Self {
+        let map = &mut self.map;
+
+        let mut doors = Vec::new();
+
+        for room in map.rooms.iter() {
+            for x in room.x1..(room.x2 + 1) {
+                for y in room.y1..(room.y2 + 1) {
+                    let on_vertical_side = x == room.x1 || x == room.x2;
+                    let on_horizontal_side = y == room.y1 || y == room.y2;
+
+                    if !(on_vertical_side || on_horizontal_side) || map.is_blocked(x, y) {
+                        continue;
+                    }
+
+                    let walled_vertically = map.is_blocked(x, y - 1) && map.is_blocked(x, y + 1);
+                    let walled_horizontally = map.is_blocked(x - 1, y) && map.is_blocked(x + 1, y);
+
+                    if (on_vertical_side && walled_vertically) || (on_horizontal_side && walled_horizontally) {
+                        doors.push((x, y));
+                    }
+                }
+            }
+        }
+
+        for (x, y) in doors {
+            map.set_tile(x, y, TileType::DoorClosed);
+        }
+
+        self
+    }
+
+    /**
+     * Up stairs in the center of the first room, down stairs in the last
+     * Maps without rooms get them as far apart as walking allows
+     */
+    pub fn place_stairs(mut self) -> Self {
+        let (first, last) = if self.map.rooms.is_empty() {
+            self.farthest_apart()
+        } else {
+            (self.map.rooms.first().map(|room| room.center()), self.map.rooms.last().map(|room| room.center()))
+        };
+
+        if let (Some(up), Some(down)) = (first, last) {
+            // Single room maps get the down stairs on the closest other floor tile
+            let down = match up == down {
+                true => self.map.nearest(up, |x, y| (x, y) != up && self.map.get_tile(x, y) == TileType::Floor),
+                false => Some(down)
+            };
+
+            self.map.set_tile(up.0, up.1, TileType::StairsUp);
+
+            match down {
+                Some(down) => self.map.set_tile(down.0, down.1, TileType::StairsDown),
+                None => warn!("No room for down stairs next to the up stairs at {:?}", up)
+            }
+        }
+
+        self
+    }
+
+    // First open tile of the largest region and the tile farthest from it
+    fn farthest_apart(&self) -> (Option<(i32, i32)>, Option<(i32, i32)>) {
+        let start = match self.map.regions().first().and_then(|region| region.first().cloned()) {
+            Some(start) => start,
+            None => return (None, None)
+        };
+
+        let mut distances = DijkstraMap::new(self.width, self.height);
+        distances.compute(&self.map, &[start]);
+
+        let mut farthest = start;
+        let mut farthest_distance = 0.0;
+
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                if let Some(distance) = distances.value(x, y) {
+                    if distance > farthest_distance {
+                        farthest = (x, y);
+                        farthest_distance = distance;
+                    }
+                }
+            }
+        }
+
+        (Some(start), Some(farthest))
+    }
+
+    pub fn build(self) -> Map {
+        self.map
+    }
+}
+
+/// Level layouts the dungeon can pick from
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MapGenerator {
+    /// Rectangular rooms joined by corridors, with the odd vault
+    Rooms,
+    /// Drunkard's walk caves
+    Caves,
+    Maze,
+    /// Wave function collapse over assets/ruins.map
+    Ruins
+}
+
+impl MapGenerator {
+    pub const ALL: [MapGenerator; 4] = [MapGenerator::Rooms, MapGenerator::Caves, MapGenerator::Maze, MapGenerator::Ruins];
+
+    /// Mostly rooms, caves on every other level and a maze or ruins now and then
+    pub fn for_depth(depth: usize) -> Self {
+        if depth % 5 == 4 {
+            MapGenerator::Maze
+        } else if depth % 5 == 2 {
+            MapGenerator::Ruins
+        } else if depth % 2 == 1 {
+            MapGenerator::Caves
+        } else {
+            MapGenerator::Rooms
+        }
+    }
+
+    pub fn generate(&self, width: usize, height: usize) -> Map {
+        self.generate_with_rng(width, height, &mut thread_rng())
+    }
+
+    pub fn generate_with_rng<R: Rng>(&self, width: usize, height: usize, rng: &mut R) -> Map {
+        match self {
+            MapGenerator::Rooms => simple_map_gen_with_rng(width, height, rng),
+            MapGenerator::Caves => drunkard_map_gen_with_rng(width, height, &DrunkardOptions::default(), rng),
+            MapGenerator::Maze => maze_map_gen_with_rng(width, height, rng),
+            MapGenerator::Ruins => wfc_map_gen_with_rng(wfc::ruins_sample(), width, height, rng)
+        }
+    }
+}
+
+impl FromStr for MapGenerator {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "rooms" => Ok(MapGenerator::Rooms),
+            "caves" => Ok(MapGenerator::Caves),
+            "maze" => Ok(MapGenerator::Maze),
+            "ruins" => Ok(MapGenerator::Ruins),
+            _ => Err(format!("Unknown map generator {:?}, expected rooms, caves, maze or ruins", name))
+        }
+    }
+}
+
+fn create_cells_from_buffer(buffer: &str, width: usize, height: usize) -> Vec<Cell> {
+    let lines = buffer.lines();
+
+    let mut cells = Vec::with_capacity(width * height);
+
+    for line in lines {
+        let mut chars = line.chars();
+
+        for _ in 0..width {
+            let glyph = chars.next().unwrap_or(' ');
+
+            cells.push(Cell::new(TileType::from_glyph(glyph)));
+        }
+    }
+
+    cells
+}
+
+pub const Wall: Cell = Cell { tile: TileType::Wall, glyph: '#', blocked: true, block_sight: true };
+
+struct Arena<T: std::fmt::Debug> {
+    pub nodes: Vec<Node<T>>
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct NodeId {
+    index: usize 
+}
+
+#[derive(Debug)]
+pub struct Node<T: std::fmt::Debug> {
+    parent: Option<NodeId>,
+    previous_sibling: Option<NodeId>,
+    next_sibling: Option<NodeId>,
+    first_child: Option<NodeId>,
+    last_child:Option<NodeId>,
+    pub data: T
+}
+
+impl<T: std::fmt::Debug> Arena<T> {
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new()
+        }
+    }
+
+    pub fn get(&self, node_id: NodeId) -> Option<&Node<T>> {
+        self.nodes.get(node_id.index)
+    }
+
+    pub fn new_node(&mut self, data: T) -> NodeId {
+        let next_index = self.nodes.len();
+
+        self.nodes.push(Node {
+            parent: None,
+            first_child: None,
+            last_child: None,
+            previous_sibling: None,
+            next_sibling: None,
+            data: data
+        });
+
+        NodeId { index: next_index }
+    }
+
+    pub fn get_all_leaf_nodes(&self, node_id: NodeId) -> Vec<NodeId> {
+        let mut leaf_node_ids = vec![];
+
+        let node = &self.nodes[node_id.index];
+
+        if node.first_child.is_none() {
+            leaf_node_ids.push(node_id);
+        } else {
+            if let Some(left_node_id) = node.first_child {
+                leaf_node_ids.append(&mut self.get_all_leaf_nodes(left_node_id))
+            } else if let Some(right_node_id) = node.last_child {
+                leaf_node_ids.append(&mut self.get_all_leaf_nodes(right_node_id))
+            }
+        }
+
+        return leaf_node_ids
+    }
+}
+
+pub fn simple_map_gen(width: usize, height: usize) -> Map {
+    simple_map_gen_with_rng(width, height, &mut thread_rng())
+}
+
+/// Rooms and corridors from a given rng, the same seed gives the same map
+pub fn simple_map_gen_with_rng<R: Rng>(width: usize, height: usize, rng: &mut R) -> Map {
+    let mut map = MapBuilder::new(width, height);
+
+    let mut rooms = vec![];
+
+    let min_room_size = 5;
+    let max_room_size = 30;
+    let max_room_count = 30;
+
+    for _ in 0..max_room_count {
+        let room_width = rng.gen_range(min_room_size, max_room_size);
+        let room_height = rng.gen_range(min_room_size, max_room_size);
+
+        // Too big for the map
+        if room_width + 1 >= width || room_height + 1 >= height {
+            continue;
+        }
+
+        let x = rng.gen_range(0, width - room_width - 1);
+        let y = rng.gen_range(0, height - room_height - 1);
+
+        let new_room = Rect::new(x as i32, y as i32, room_width as i32, room_height as i32);
+
+        let failed = rooms.iter().any(|room| new_room.intersect(room));
+
+        if !failed {
+            // No intersections, valid room
+            // Get previous room center
+            if rooms.len() > 0 {
+                let prev_room = rooms.last().expect("No rooms found");
+                let new_center = new_room.center();
+                let center = prev_room.center();
+
+                // coinflip horizontal or vertical
+                if rng.gen::<bool>() {
+                    map = map.create_h_tunnel(center.0, new_center.0, center.1);
+                    map = map.create_v_tunnel(center.1, new_center.1, center.0);
+                } else {
+                    map = map.create_v_tunnel(center.1, new_center.1, center.0);
+                    map = map.create_h_tunnel(center.0, new_center.0, new_center.1);
+                } 
+            }
+
+            rooms.push(new_room);
+        }
+    }
+
+    for room in rooms {
+        map = map.create_room(&room);
+    }
+
+    // A vault or two in the rock between rooms
+    map = map.place_prefabs(Placement::Rock, rng);
+
+    // Tunnels between rooms can be cut off by the edge clamp, join what's left
+    map = map.connect_regions();
+
+    map = map.place_doors();
+
+    map = map.place_stairs();
+
+    map.build()
+}
+
+fn split_dungeon(node_id: NodeId, arena: &mut Arena<Rect>) {
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+
+    let max_room_count = 2;
+
+    if node_id.index > max_room_count {
+        return;
+    }
+
+    let horizontal = false;
+
+    let node = &mut arena.nodes[node_id.index];
+
+    let (left, right) = if horizontal {
+        let y = rng.gen_range(node.data.y1, node.data.y2);
+
+        let top_room = Rect::new(node.data.x1, node.data.y1, node.data.width(), y - node.data.y1);
+        let bottom_room = Rect::new(node.data.x1, y, node.data.width(), node.data.y2 - y);
+
+        (top_room, bottom_room)
+    } else {
+        let x = rng.gen_range(node.data.x1, node.data.x2);
+
+        let left_room = Rect::new(node.data.x1, node.data.y1, x - node.data.x1, node.data.height());
+        let right_room = Rect::new(x, node.data.y1, node.data.x2 - x, node.data.height());
+
+        (left_room, right_room)
+    };
+
+    let left_node_id = arena.new_node(left);
+    let right_node_id = arena.new_node(right);
+
+    if let Some(mut parent_node) = arena.nodes.get_mut(node_id.index) {
+        parent_node.first_child = Some(left_node_id);
+        parent_node.last_child = Some(right_node_id);
+    }
+
+    if let Some(mut left_node) = arena.nodes.get_mut(left_node_id.index) {
+        left_node.parent = Some(node_id);
+    }
+
+    if let Some(mut right_node) = arena.nodes.get_mut(right_node_id.index) {
+        right_node.parent = Some(node_id);
+    }
+
+    split_dungeon(left_node_id, arena);
+    split_dungeon(right_node_id, arena);
+}
+
+pub fn bsp_map_generator(width: usize, height: usize) -> Map {
+    let map = Map::new(width, height);
+
+    let root_rect = Rect::new(0, 0, width as i32, height as i32);
+
+    let mut bsp = Arena::new();
+    let root_node_id = bsp.new_node(root_rect);
+
+    split_dungeon(root_node_id, &mut bsp);
+    // Recursively split room
+    // Choose a random position
+    // Split the dungeon into two sub dungeons
+
+    debug!("{:?}", bsp.nodes);
+    let leaves = bsp.get_all_leaf_nodes(NodeId { index: 0 });
+    debug!("Leaves {:?}", leaves);
+
+    // Shrink Resize each room into random sizes
+    // Build corriders through all the leafs of the tree
+    // Connecting each leaf to its sister
+
+    map
+}
+
+pub fn ca_map_gen(width: usize, height: usize) -> Map {
+    let mut map = Map::new(width, height);
+
+    let mut rng = rand::thread_rng();
+
+    map.fill(Cell::new(TileType::Floor));
+
+    // Fil 45% of the map
+    let original_fill_amount = 45;
+
+    for _ in 0..original_fill_amount {
+        let x = rng.gen_range(0, map.width() as i32);
+        let y = rng.gen_range(0, map.height() as i32);
+
+        map.set_cell(x, y, Wall);
+    }
+
+    for _ in 0..5 {
+        for y in 0..map.height() {
+            for x in 0..map.width() {
+                let mut wall_neighbors = 0;
+
+                if x > 0 {
+
+                }
+            }
+        }
+    }
+
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_start_as_solid_rock() {
+        let map = Map::new(4, 3);
+
+        assert!(map.get_cells().iter().all(|cell| cell.tile == TileType::Wall && cell.blocked && cell.block_sight));
+    }
+
+    #[test]
+    fn it_should_load_doors_from_map_file() {
+        let map = Map::from_buffer(include_str!("../../assets/test.map"));
+
+        assert_eq!(map.width(), 45);
+        assert_eq!(map.get_tile(18, 4), TileType::DoorClosed);
+        assert!(map.is_blocked(18, 4));
+        assert!(map.get_cell_ref(18, 4).block_sight);
+        // Gap between the rooms is solid
+        assert!(map.is_blocked(22, 1));
+    }
+
+    #[test]
+    fn it_should_treat_out_of_bounds_as_blocked() {
+        let mut map = MapBuilder::new(10, 10)
+            .create_room(&Rect::new(0, 0, 9, 9))
+            .build();
+
+        assert!(!map.is_blocked(1, 1));
+        assert!(map.is_blocked(-1, 1));
+        assert!(map.is_blocked(1, 10));
+        assert!(map.get(10, 0).is_none());
+        assert_eq!(map.get_tile(-5, -5), TileType::Wall);
+
+        // Negative x must not wrap onto the previous row
+        assert!(map.is_blocked(-1, 2));
+
+        map.set_tile(20, 20, TileType::Floor);
+        map.reveal(-1, -1);
+        assert!(!map.is_revealed(-1, -1));
+    }
+
+    #[test]
+    fn it_should_clamp_rooms_and_tunnels_to_map() {
+        let map = MapBuilder::new(10, 10)
+            .create_room(&Rect::new(5, 5, 20, 20))
+            .create_h_tunnel(-5, 30, 3)
+            .create_v_tunnel(-5, 30, 3)
+            .build();
+
+        assert_eq!(map.rooms[0].x2, 9);
+        assert!(!map.is_blocked(8, 8));
+        assert!(!map.is_blocked(1, 3));
+
+        // Outer edge stays wall
+        for i in 0..10 {
+            assert!(map.is_blocked(i, 0) && map.is_blocked(i, 9));
+            assert!(map.is_blocked(0, i) && map.is_blocked(9, i));
+        }
+    }
+
+    #[test]
+    fn it_should_place_doors_in_room_walls() {
+        let map = MapBuilder::new(30, 12)
+            .create_h_tunnel(5, 20, 5)
+            .create_room(&Rect::new(0, 0, 10, 10))
+            .create_room(&Rect::new(15, 0, 10, 10))
+            .place_doors()
+            .build();
+
+        assert_eq!(map.get_tile(10, 5), TileType::DoorClosed);
+        assert_eq!(map.get_tile(15, 5), TileType::DoorClosed);
+        assert_eq!(map.get_tile(12, 5), TileType::Floor);
+    }
+}
@@ -0,0 +1,311 @@
+// A* pathfinding
+// http://www.redblobgames.com/pathfinding/a-star/introduction.html
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use super::Map;
+use crate::entities::EntityManager;
+use crate::components::{Component, Collidable, Position};
+
+/// Extra cost of stepping onto a tile occupied by a collidable entity.
+/// Occupied tiles are not blocked outright so a path can still squeeze
+/// past a crowd when there is no other way around.
+const OCCUPIED_COST: i32 = 10;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Movement {
+    /// Up, down, left and right
+    Cardinal,
+    /// Cardinal directions plus diagonals
+    Diagonal
+}
+
+impl Movement {
+    fn directions(&self) -> &'static [(i32, i32)] {
+        const CARDINAL: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+        const DIAGONAL: [(i32, i32); 8] = [
+            (0, -1), (1, 0), (0, 1), (-1, 0),
+            (1, -1), (1, 1), (-1, 1), (-1, -1)
+        ];
+
+        match self {
+            Movement::Cardinal => &CARDINAL,
+            Movement::Diagonal => &DIAGONAL
+        }
+    }
+
+    fn heuristic(&self, a: (i32, i32), b: (i32, i32)) -> i32 {
+        let dx = (a.0 - b.0).abs();
+        let dy = (a.1 - b.1).abs();
+
+        match self {
+            Movement::Cardinal => dx + dy,
+            Movement::Diagonal => dx.max(dy)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PathOptions {
+    pub movement: Movement,
+    /// Maximum number of tiles expanded before giving up
+    pub max_search: usize,
+    /// Route around tiles occupied by collidable entities
+    pub avoid_entities: bool
+}
+
+impl Default for PathOptions {
+    fn default() -> Self {
+        Self {
+            movement: Movement::Diagonal,
+            max_search: 2000,
+            avoid_entities: true
+        }
+    }
+}
+
+/// Steps from the start (exclusive) to the goal (inclusive)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    steps: Vec<(i32, i32)>
+}
+
+impl Path {
+    pub fn steps(&self) -> &[(i32, i32)] {
+        &self.steps
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn next_step(&self) -> Option<(i32, i32)> {
+        self.steps.first().cloned()
+    }
+
+    /// Walk direction to take from `from` to reach the next step
+    pub fn next_direction(&self, from: (i32, i32)) -> Option<(i32, i32)> {
+        self.next_step().map(|(x, y)| (x - from.0, y - from.1))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Node {
+    f: i32,
+    g: i32,
+    position: (i32, i32)
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Prefer nodes closer to the goal when the estimates tie
+        self.f.cmp(&other.f)
+            .then_with(|| other.g.cmp(&self.g))
+            .then_with(|| self.position.cmp(&other.position))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn is_walkable(map: &Map, position: (i32, i32)) -> bool {
+    !map.is_blocked(position.0, position.1)
+}
+
+/// Whether a step lands on an open tile, diagonals can't cut corners around walls
+pub fn can_step(map: &Map, (x, y): (i32, i32), (dx, dy): (i32, i32)) -> bool {
+    is_walkable(map, (x + dx, y + dy))
+        && (dx == 0 || dy == 0 || (is_walkable(map, (x + dx, y)) && is_walkable(map, (x, y + dy))))
+}
+
+/**
+ * Find the cheapest path between start and goal.
+ * Tiles in `occupied` cost extra to enter when the options ask to avoid entities,
+ * the goal itself is never penalized since it is usually the entity being chased.
+ */
+pub fn find_path(
+    map: &Map,
+    start: (i32, i32),
+    goal: (i32, i32),
+    options: &PathOptions,
+    occupied: &HashSet<(i32, i32)>
+) -> Option<Path> {
+    if !map.in_bounds(start.0, start.1) || !is_walkable(map, goal) {
+        return None;
+    }
+
+    if start == goal {
+        return Some(Path { steps: Vec::new() });
+    }
+
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<(i32, i32), (i32, i32)> = HashMap::new();
+    let mut cost_so_far: HashMap<(i32, i32), i32> = HashMap::new();
+
+    open.push(Reverse(Node { f: options.movement.heuristic(start, goal), g: 0, position: start }));
+    cost_so_far.insert(start, 0);
+
+    let mut searched = 0;
+
+    while let Some(Reverse(current)) = open.pop() {
+        if current.position == goal {
+            return Some(reconstruct_path(&came_from, start, goal));
+        }
+
+        // Skip stale entries left behind by a cheaper route
+        if current.g > cost_so_far[&current.position] {
+            continue;
+        }
+
+        searched += 1;
+
+        if searched > options.max_search {
+            debug!("A* gave up after searching {} tiles", searched);
+            return None;
+        }
+
+        let (x, y) = current.position;
+
+        for (dx, dy) in options.movement.directions() {
+            let next = (x + dx, y + dy);
+
+            if !can_step(map, (x, y), (*dx, *dy)) {
+                continue;
+            }
+
+            let mut step_cost = map.movement_cost(next.0, next.1);
+
+            if options.avoid_entities && next != goal && occupied.contains(&next) {
+                step_cost += OCCUPIED_COST;
+            }
+
+            let new_cost = current.g + step_cost;
+
+            if cost_so_far.get(&next).map_or(true, |cost| new_cost < *cost) {
+                cost_so_far.insert(next, new_cost);
+                came_from.insert(next, current.position);
+
+                let f = new_cost + options.movement.heuristic(next, goal);
+                open.push(Reverse(Node { f: f, g: new_cost, position: next }));
+            }
+        }
+    }
+
+    None
+}
+
+fn reconstruct_path(
+    came_from: &HashMap<(i32, i32), (i32, i32)>,
+    start: (i32, i32),
+    goal: (i32, i32)
+) -> Path {
+    let mut steps = vec![goal];
+    let mut current = goal;
+
+    while let Some(previous) = came_from.get(&current) {
+        if *previous == start {
+            break;
+        }
+
+        steps.push(*previous);
+        current = *previous;
+    }
+
+    steps.reverse();
+
+    Path { steps: steps }
+}
+
+/// Positions of every collidable entity, used to make paths entity aware
+pub fn collidable_positions(em: &EntityManager) -> HashSet<(i32, i32)> {
+    em.get_entities_with_components(Collidable::get_component_type())
+        .iter()
+        .filter_map(|entity| get_component!(em, *entity, Position))
+        .map(|position| (position.x, position.y))
+        .collect()
+}
+
+impl Map {
+    /// Find a path ignoring entities
+    pub fn find_path(&self, start: (i32, i32), goal: (i32, i32), options: &PathOptions) -> Option<Path> {
+        find_path(self, start, goal, options, &HashSet::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+
+    fn two_rooms() -> Map {
+        // Two rooms joined by a single corridor along y = 5
+        MapBuilder::new(30, 12)
+            .create_room(&Rect::new(0, 0, 10, 10))
+            .create_room(&Rect::new(15, 0, 10, 10))
+            .create_h_tunnel(5, 20, 5)
+            .build()
+    }
+
+    #[test]
+    fn it_should_find_path_around_walls() {
+        let map = two_rooms();
+
+        let path = map.find_path((2, 2), (18, 2), &PathOptions::default())
+            .expect("No path found");
+
+        assert_eq!(path.steps().last(), Some(&(18, 2)));
+        assert!(path.steps().iter().all(|(x, y)| !map.is_blocked(*x, *y)));
+        assert!(path.steps().iter().any(|(_, y)| *y == 5));
+    }
+
+    #[test]
+    fn it_should_only_take_cardinal_steps() {
+        let map = two_rooms();
+
+        let options = PathOptions { movement: Movement::Cardinal, ..PathOptions::default() };
+        let path = map.find_path((2, 2), (7, 7), &options).unwrap();
+
+        assert_eq!(path.len(), 10);
+
+        let mut previous = (2, 2);
+        for step in path.steps() {
+            assert_eq!((step.0 - previous.0).abs() + (step.1 - previous.1).abs(), 1);
+            previous = *step;
+        }
+    }
+
+    #[test]
+    fn it_should_respect_max_search() {
+        let map = two_rooms();
+
+        let options = PathOptions { max_search: 5, ..PathOptions::default() };
+
+        assert!(map.find_path((2, 2), (18, 2), &options).is_none());
+    }
+
+    #[test]
+    fn it_should_route_around_occupied_tiles() {
+        let map = MapBuilder::new(12, 12)
+            .create_room(&Rect::new(0, 0, 10, 10))
+            .build();
+
+        let options = PathOptions { movement: Movement::Cardinal, ..PathOptions::default() };
+
+        let mut occupied = HashSet::new();
+        occupied.insert((3, 2));
+
+        let path = find_path(&map, (2, 2), (4, 2), &options, &occupied).unwrap();
+
+        assert!(!path.steps().contains(&(3, 2)));
+        assert_eq!(path.steps().last(), Some(&(4, 2)));
+    }
+}
@@ -0,0 +1,310 @@
+// Hand made rooms stamped into generated maps
+
+use rand::Rng;
+
+use super::{Cell, MapBuilder, TileType};
+
+/// Glyphs in a prefab that mark something to spawn, the tile under them is floor
+pub const SPAWN_MARKERS: [char; 3] = ['g', 'z', '!'];
+
+/// Something a prefab asks to spawn once the level is populated
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Spawn {
+    pub x: i32,
+    pub y: i32,
+    pub marker: char
+}
+
+/// Where a prefab may go
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Placement {
+    /// Only into untouched rock, leaving what was dug alone
+    Rock,
+    /// Over whatever is there, for maps that leave no rock to spare
+    Anywhere
+}
+
+/**
+ * A small hand made room in the map file format
+ * Blocks start with "MAP <name>" and end with "ENDMAP"
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prefab {
+    pub name: String,
+    width: i32,
+    height: i32,
+    tiles: Vec<TileType>,
+    spawns: Vec<Spawn>
+}
+
+impl Prefab {
+    fn from_lines(name: &str, lines: &[&str]) -> Self {
+        let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as i32;
+        let height = lines.len() as i32;
+
+        let mut tiles = Vec::with_capacity((width * height) as usize);
+        let mut spawns = Vec::new();
+
+        for (y, line) in lines.iter().enumerate() {
+            let mut chars = line.chars();
+
+            for x in 0..width {
+                let glyph = chars.next().unwrap_or(' ');
+
+                if SPAWN_MARKERS.contains(&glyph) {
+                    spawns.push(Spawn { x: x, y: y as i32, marker: glyph });
+                    tiles.push(TileType::Floor);
+                } else {
+                    tiles.push(TileType::from_glyph(glyph));
+                }
+            }
+        }
+
+        Self {
+            name: name.to_string(),
+            width: width,
+            height: height,
+            tiles: tiles,
+            spawns: spawns
+        }
+    }
+
+    /// Every prefab in a buffer
+    pub fn parse_all(buffer: &str) -> Vec<Prefab> {
+        let mut prefabs = Vec::new();
+        let mut current: Option<(String, Vec<&str>)> = None;
+
+        for line in buffer.lines() {
+            let trimmed = line.trim();
+
+            if trimmed.starts_with("ENDMAP") {
+                if let Some((name, lines)) = current.take() {
+                    prefabs.push(Prefab::from_lines(&name, &lines));
+                }
+            } else if trimmed.starts_with("MAP") {
+                let name = trimmed["MAP".len()..].trim();
+                current = Some((name.to_string(), Vec::new()));
+            } else if let Some((_, lines)) = current.as_mut() {
+                lines.push(line);
+            }
+        }
+
+        prefabs
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn spawns(&self) -> &[Spawn] {
+        &self.spawns
+    }
+
+    pub fn get_tile(&self, x: i32, y: i32) -> TileType {
+        self.tiles[(y * self.width + x) as usize]
+    }
+
+    /// Rotated clockwise by quarter turns
+    pub fn rotated(&self, quarter_turns: u8) -> Prefab {
+        let mut prefab = self.clone();
+
+        for _ in 0..(quarter_turns % 4) {
+            // (x, y) -> (height - 1 - y, x)
+            prefab = prefab.remap(prefab.height, prefab.width, |p, x, y| (p.height - 1 - y, x));
+        }
+
+        prefab
+    }
+
+    /// Flipped left to right
+    pub fn mirrored(&self) -> Prefab {
+        self.remap(self.width, self.height, |p, x, y| (p.width - 1 - x, y))
+    }
+
+    // Build a prefab of the given size where source (x, y) lands on transform(x, y)
+    fn remap<F>(&self, width: i32, height: i32, transform: F) -> Prefab
+        where F: Fn(&Prefab, i32, i32) -> (i32, i32)
+    {
+        let mut tiles = vec![TileType::Wall; (width * height) as usize];
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (nx, ny) = transform(self, x, y);
+                tiles[(ny * width + nx) as usize] = self.get_tile(x, y);
+            }
+        }
+
+        let spawns = self.spawns.iter()
+            .map(|spawn| {
+                let (x, y) = transform(self, spawn.x, spawn.y);
+                Spawn { x: x, y: y, marker: spawn.marker }
+            })
+            .collect();
+
+        Prefab {
+            name: self.name.clone(),
+            width: width,
+            height: height,
+            tiles: tiles,
+            spawns: spawns
+        }
+    }
+}
+
+lazy_static! {
+    static ref PREFABS: Vec<Prefab> = Prefab::parse_all(include_str!("../../assets/prefabs.map"));
+}
+
+/// Prefabs shipped in assets/prefabs.map
+pub fn prefabs() -> &'static [Prefab] {
+    &PREFABS
+}
+
+impl MapBuilder {
+    /**
+     * Stamp a prefab into the map, randomly rotated and mirrored
+     * Leaves the map untouched if there's no room after a few tries,
+     * the vault is joined to the rest of the map by connect_regions
+     */
+    pub fn place_prefab<R: Rng>(mut self, prefab: &Prefab, placement: Placement, rng: &mut R) -> Self {
+        const ATTEMPTS: usize = 50;
+
+        let mut prefab = prefab.rotated(rng.gen_range(0, 4));
+
+        if rng.gen::<bool>() {
+            prefab = prefab.mirrored();
+        }
+
+        // Keep a wall between the vault and the map edge
+        let max_x = self.width as i32 - prefab.width - 1;
+        let max_y = self.height as i32 - prefab.height - 1;
+
+        if max_x <= 1 || max_y <= 1 {
+            return self;
+        }
+
+        for _ in 0..ATTEMPTS {
+            let x = rng.gen_range(1, max_x);
+            let y = rng.gen_range(1, max_y);
+
+            if placement == Placement::Anywhere || self.is_solid(x - 1, y - 1, prefab.width + 2, prefab.height + 2) {
+                debug!("Placing prefab {} at ({}, {})", prefab.name, x, y);
+
+                self.stamp(&prefab, x, y);
+                return self;
+            }
+        }
+
+        debug!("No space for prefab {}", prefab.name);
+
+        self
+    }
+
+    /**
+     * A vault or two from the shipped prefabs, wherever the rock leaves room
+     * Every generator runs this before connect_regions
+     */
+    pub fn place_prefabs<R: Rng>(mut self, placement: Placement, rng: &mut R) -> Self {
+        let library = prefabs();
+
+        for _ in 0..rng.gen_range(0, 3) {
+            let vault = &library[rng.gen_range(0, library.len())];
+            self = self.place_prefab(vault, placement, rng);
+        }
+
+        self
+    }
+
+    fn is_solid(&self, x: i32, y: i32, width: i32, height: i32) -> bool {
+        (y..(y + height)).all(|y| (x..(x + width)).all(|x| self.map.get_tile(x, y) == TileType::Wall))
+    }
+
+    fn stamp(&mut self, prefab: &Prefab, x: i32, y: i32) {
+        for py in 0..prefab.height {
+            for px in 0..prefab.width {
+                self.map.set_cell(x + px, y + py, Cell::new(prefab.get_tile(px, py)));
+            }
+        }
+
+        for spawn in prefab.spawns() {
+            self.map.spawns.push(Spawn { x: x + spawn.x, y: y + spawn.y, marker: spawn.marker });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::map::MapGenerator;
+
+    const NEST: &str = "MAP nest\n#####\n#g.z#\n#...#\n##.##\nENDMAP\n";
+
+    #[test]
+    fn it_should_parse_prefabs_with_spawns() {
+        let parsed = Prefab::parse_all(NEST);
+
+        assert_eq!(parsed.len(), 1);
+
+        let nest = &parsed[0];
+        assert_eq!(nest.name, "nest");
+        assert_eq!((nest.width(), nest.height()), (5, 4));
+        assert_eq!(nest.get_tile(1, 1), TileType::Floor);
+        assert_eq!(nest.spawns(), &[Spawn { x: 1, y: 1, marker: 'g' }, Spawn { x: 3, y: 1, marker: 'z' }]);
+
+        assert_eq!(prefabs().len(), 4);
+    }
+
+    #[test]
+    fn it_should_rotate_and_mirror() {
+        let nest = &Prefab::parse_all(NEST)[0];
+
+        let rotated = nest.rotated(1);
+        assert_eq!((rotated.width(), rotated.height()), (4, 5));
+        // The opening at the bottom now faces left
+        assert_eq!(rotated.get_tile(0, 2), TileType::Floor);
+        assert_eq!(rotated.spawns()[0], Spawn { x: 2, y: 1, marker: 'g' });
+
+        assert_eq!(&nest.rotated(4), nest);
+
+        let mirrored = nest.mirrored();
+        assert_eq!(mirrored.spawns()[0], Spawn { x: 3, y: 1, marker: 'g' });
+        assert_eq!(&mirrored.mirrored(), nest);
+    }
+
+    #[test]
+    fn it_should_stamp_into_solid_rock() {
+        let nest = &Prefab::parse_all(NEST)[0];
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let map = MapBuilder::new(20, 20)
+            .place_prefab(nest, Placement::Rock, &mut rng)
+            .build();
+
+        assert_eq!(map.spawns().len(), 2);
+
+        for spawn in map.spawns() {
+            assert_eq!(map.get_tile(spawn.x, spawn.y), TileType::Floor);
+        }
+    }
+
+    #[test]
+    fn it_should_place_vaults_in_generated_maps() {
+        // Mazes leave no solid rock to put a vault in
+        for generator in &[MapGenerator::Rooms, MapGenerator::Caves, MapGenerator::Ruins] {
+            let with_vaults = (0..20)
+                .filter(|seed| {
+                    let mut rng = StdRng::seed_from_u64(*seed);
+                    !generator.generate_with_rng(80, 50, &mut rng).spawns().is_empty()
+                })
+                .count();
+
+            assert!(with_vaults > 0, "{:?} never placed a vault", generator);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use serde::{Serialize, Deserialize};
+
+use std::collections::HashMap;
+
+use crate::assets;
+use crate::components::DamageType;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TileType {
+    Floor,
+    Wall,
+    DoorOpen,
+    DoorClosed,
+    Water,
+    Lava,
+    StairsDown,
+    StairsUp,
+    Rubble
+}
+
+/// Terrain properties of a tile type, see assets/tiles.json
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TileDef {
+    pub tile: TileType,
+    pub name: String,
+    pub glyph: char,
+    /// Other glyphs that load as this tile in map files
+    #[serde(default)]
+    pub symbols: Vec<char>,
+    pub walkable: bool,
+    pub opaque: bool,
+    pub movement_cost: i32,
+    pub damage_on_enter: i32,
+    #[serde(default)]
+    pub damage_type: DamageType,
+    pub color: [u8; 3]
+}
+
+#[derive(Debug)]
+pub struct TileTable {
+    defs: HashMap<TileType, TileDef>,
+    glyphs: HashMap<char, TileType>
+}
+
+impl TileTable {
+    pub fn from_json(buffer: &str) -> serde_json::Result<Self> {
+        let defs: Vec<TileDef> = serde_json::from_str(buffer)?;
+
+        let mut glyphs = HashMap::new();
+
+        for def in &defs {
+            for glyph in std::iter::once(&def.glyph).chain(def.symbols.iter()) {
+                glyphs.entry(*glyph).or_insert(def.tile);
+            }
+        }
+
+        Ok(Self {
+            defs: defs.into_iter().map(|def| (def.tile, def)).collect(),
+            glyphs: glyphs
+        })
+    }
+
+    pub fn get(&self, tile: TileType) -> &TileDef {
+        self.defs.get(&tile)
+            .unwrap_or_else(|| panic!("No tile definition for {:?}", tile))
+    }
+
+    pub fn from_glyph(&self, glyph: char) -> Option<TileType> {
+        self.glyphs.get(&glyph).cloned()
+    }
+}
+
+lazy_static! {
+    static ref TILES: TileTable = assets::load("tiles.json", include_str!("../../assets/tiles.json"), TileTable::from_json);
+}
+
+impl TileType {
+    pub fn def(&self) -> &'static TileDef {
+        TILES.get(*self)
+    }
+
+    /// Tile a map file glyph stands for, unknown glyphs are floor
+    pub fn from_glyph(glyph: char) -> TileType {
+        TILES.from_glyph(glyph).unwrap_or(TileType::Floor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_define_every_tile() {
+        let tiles = [
+            TileType::Floor, TileType::Wall, TileType::DoorOpen, TileType::DoorClosed,
+            TileType::Water, TileType::Lava, TileType::StairsDown, TileType::StairsUp,
+            TileType::Rubble
+        ];
+
+        for tile in tiles.iter() {
+            assert_eq!(tile.def().tile, *tile);
+        }
+    }
+
+    #[test]
+    fn it_should_look_up_tiles_by_glyph() {
+        assert_eq!(TileType::from_glyph('#'), TileType::Wall);
+        assert_eq!(TileType::from_glyph('|'), TileType::Wall);
+        assert_eq!(TileType::from_glyph('>'), TileType::StairsDown);
+        assert_eq!(TileType::from_glyph('?'), TileType::Floor);
+    }
+}
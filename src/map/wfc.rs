@@ -0,0 +1,358 @@
+// Wave function collapse, simple tiled model
+// https://github.com/mxgmn/WaveFunctionCollapse
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use rand::{thread_rng, Rng};
+
+use super::{Cell, Map, MapBuilder, TileType, Placement, simple_map_gen_with_rng};
+
+/// Up, right, down, left, opposite of direction d is (d + 2) % 4
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Runs that end in a contradiction before giving up on the sample
+const MAX_ATTEMPTS: usize = 10;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Entry {
+    options: usize,
+    // Random tie breaker so collapse doesn't sweep the map in order
+    noise: f32,
+    index: usize
+}
+
+impl Eq for Entry {}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so the heap pops the fewest options first
+        other.options.cmp(&self.options)
+            .then_with(|| other.noise.partial_cmp(&self.noise).unwrap_or(Ordering::Equal))
+            .then_with(|| self.index.cmp(&other.index))
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/**
+ * Which tiles may sit next to each other, learned from a sample map
+ * Tiles are picked in proportion to how often they appear in the sample
+ */
+#[derive(Debug, Clone)]
+pub struct WfcModel {
+    tiles: Vec<TileType>,
+    weights: Vec<f32>,
+    // allowed[direction][a][b], tile b may sit in that direction from tile a
+    allowed: Vec<Vec<Vec<bool>>>
+}
+
+impl WfcModel {
+    pub fn from_sample(sample: &Map) -> Self {
+        let mut tiles: Vec<TileType> = Vec::new();
+        let mut weights: Vec<f32> = Vec::new();
+
+        for cell in sample.get_cells() {
+            match tiles.iter().position(|tile| *tile == cell.tile) {
+                Some(index) => weights[index] += 1.0,
+                None => {
+                    tiles.push(cell.tile);
+                    weights.push(1.0);
+                }
+            }
+        }
+
+        let mut allowed = vec![vec![vec![false; tiles.len()]; tiles.len()]; DIRECTIONS.len()];
+        let tile_index = |tile: TileType| tiles.iter().position(|other| *other == tile).unwrap();
+
+        for y in 0..sample.height() as i32 {
+            for x in 0..sample.width() as i32 {
+                let a = tile_index(sample.get_tile(x, y));
+
+                for (direction, (dx, dy)) in DIRECTIONS.iter().enumerate() {
+                    if let Some(neighbor) = sample.get(x + dx, y + dy) {
+                        let b = tile_index(neighbor.tile);
+
+                        allowed[direction][a][b] = true;
+                        allowed[(direction + 2) % 4][b][a] = true;
+                    }
+                }
+            }
+        }
+
+        Self {
+            tiles: tiles,
+            weights: weights,
+            allowed: allowed
+        }
+    }
+
+    pub fn tiles(&self) -> &[TileType] {
+        &self.tiles
+    }
+
+    /**
+     * Try to fill a map of the given size
+     * Returns None when every attempt ran into a contradiction
+     */
+    pub fn generate<R: Rng>(&self, width: usize, height: usize, rng: &mut R) -> Option<Map> {
+        for attempt in 0..MAX_ATTEMPTS {
+            if let Some(map) = self.run(width, height, rng) {
+                return Some(map);
+            }
+
+            debug!("Wave function collapse contradiction on attempt {}", attempt + 1);
+        }
+
+        None
+    }
+
+    fn run<R: Rng>(&self, width: usize, height: usize, rng: &mut R) -> Option<Map> {
+        let mut wave = Wave::new(self, width, height);
+
+        // Keep the map closed when the sample has walls to close it with
+        if let Some(wall) = self.tiles.iter().position(|tile| *tile == TileType::Wall) {
+            for y in 0..height {
+                for x in 0..width {
+                    if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+                        wave.restrict(y * width + x, |tile| tile == wall);
+                    }
+                }
+            }
+        }
+
+        if !wave.propagate() {
+            return None;
+        }
+
+        let mut heap: BinaryHeap<Entry> = (0..width * height)
+            .map(|index| Entry { options: wave.count(index), noise: rng.gen(), index: index })
+            .collect();
+
+        wave.changed.clear();
+
+        while let Some(entry) = heap.pop() {
+            let options = wave.count(entry.index);
+
+            // Stale entry, a newer one was pushed when the cell last changed
+            if options != entry.options || options <= 1 {
+                continue;
+            }
+
+            let choice = self.pick(&wave.options[entry.index], rng);
+            wave.restrict(entry.index, |tile| tile == choice);
+
+            if !wave.propagate() {
+                return None;
+            }
+
+            let changed: Vec<usize> = wave.changed.drain(..).collect();
+
+            for index in changed {
+                let options = wave.count(index);
+
+                if options > 1 {
+                    heap.push(Entry { options: options, noise: rng.gen(), index: index });
+                }
+            }
+        }
+
+        let mut map = Map::new(width, height);
+
+        for (index, options) in wave.options.iter().enumerate() {
+            let tile = options.iter().position(|possible| *possible)?;
+            map.set_cell((index % width) as i32, (index / width) as i32, Cell::new(self.tiles[tile]));
+        }
+
+        Some(map)
+    }
+
+    fn pick<R: Rng>(&self, options: &[bool], rng: &mut R) -> usize {
+        let total: f32 = (0..self.tiles.len()).filter(|tile| options[*tile]).map(|tile| self.weights[tile]).sum();
+        let mut roll = rng.gen::<f32>() * total;
+
+        for tile in (0..self.tiles.len()).filter(|tile| options[*tile]) {
+            roll -= self.weights[tile];
+
+            if roll <= 0.0 {
+                return tile;
+            }
+        }
+
+        // Rounding left a sliver, take the last option
+        (0..self.tiles.len()).rev().find(|tile| options[*tile]).unwrap()
+    }
+}
+
+// Tiles each cell could still become
+struct Wave<'a> {
+    model: &'a WfcModel,
+    width: usize,
+    height: usize,
+    options: Vec<Vec<bool>>,
+    // Cells waiting to push their restrictions onto their neighbors
+    pending: Vec<usize>,
+    // Cells whose options shrank since the last collapse
+    changed: Vec<usize>
+}
+
+impl<'a> Wave<'a> {
+    fn new(model: &'a WfcModel, width: usize, height: usize) -> Self {
+        Self {
+            model: model,
+            width: width,
+            height: height,
+            options: vec![vec![true; model.tiles.len()]; width * height],
+            pending: Vec::new(),
+            changed: Vec::new()
+        }
+    }
+
+    fn count(&self, index: usize) -> usize {
+        self.options[index].iter().filter(|possible| **possible).count()
+    }
+
+    fn restrict<F: Fn(usize) -> bool>(&mut self, index: usize, keep: F) {
+        let mut shrank = false;
+
+        for (tile, possible) in self.options[index].iter_mut().enumerate() {
+            if *possible && !keep(tile) {
+                *possible = false;
+                shrank = true;
+            }
+        }
+
+        if shrank {
+            self.pending.push(index);
+            self.changed.push(index);
+        }
+    }
+
+    // Returns false on a contradiction
+    fn propagate(&mut self) -> bool {
+        let tile_count = self.model.tiles.len();
+
+        while let Some(index) = self.pending.pop() {
+            let x = (index % self.width) as i32;
+            let y = (index / self.width) as i32;
+
+            for (direction, (dx, dy)) in DIRECTIONS.iter().enumerate() {
+                let (nx, ny) = (x + dx, y + dy);
+
+                if nx < 0 || ny < 0 || nx >= self.width as i32 || ny >= self.height as i32 {
+                    continue;
+                }
+
+                let neighbor = ny as usize * self.width + nx as usize;
+
+                // Tiles the neighbor can be given what this cell can still be
+                let supported: Vec<bool> = (0..tile_count)
+                    .map(|b| (0..tile_count).any(|a| self.options[index][a] && self.model.allowed[direction][a][b]))
+                    .collect();
+
+                self.restrict(neighbor, |tile| supported[tile]);
+
+                if self.count(neighbor) == 0 {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+}
+
+pub fn wfc_map_gen(sample: &Map, width: usize, height: usize) -> Map {
+    wfc_map_gen_with_rng(sample, width, height, &mut thread_rng())
+}
+
+/**
+ * Grow a map that looks like the sample
+ * Disconnected pockets are tunneled together and stairs placed as in caves,
+ * falls back to rooms and corridors if the sample keeps contradicting itself
+ */
+pub fn wfc_map_gen_with_rng<R: Rng>(sample: &Map, width: usize, height: usize, rng: &mut R) -> Map {
+    let model = WfcModel::from_sample(sample);
+
+    match model.generate(width, height, rng) {
+        Some(map) => {
+            MapBuilder::from_map(map)
+                // Ruins are grown wall to wall, vaults go on top of them
+                .place_prefabs(Placement::Anywhere, rng)
+                .connect_regions()
+                .place_stairs()
+                .build()
+        }
+        None => {
+            warn!("Wave function collapse failed, falling back to rooms");
+            simple_map_gen_with_rng(width, height, rng)
+        }
+    }
+}
+
+lazy_static! {
+    static ref RUINS: Map = Map::from_buffer(include_str!("../../assets/ruins.map"));
+}
+
+/// Sample in assets/ruins.map
+pub fn ruins_sample() -> &'static Map {
+    &RUINS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn it_should_learn_adjacency_from_sample() {
+        let sample = Map::from_buffer("###\n#.#\n###");
+        let model = WfcModel::from_sample(&sample);
+
+        assert_eq!(model.tiles(), &[TileType::Wall, TileType::Floor]);
+
+        // Floor only ever touches walls
+        let floor = 1;
+        assert!((0..4).all(|direction| !model.allowed[direction][floor][floor]));
+    }
+
+    #[test]
+    fn it_should_only_produce_sampled_neighbors() {
+        let sample = Map::from_buffer(include_str!("../../assets/test.map"));
+        let model = WfcModel::from_sample(&sample);
+        let mut rng = StdRng::seed_from_u64(5);
+
+        let map = model.generate(40, 30, &mut rng).expect("Every attempt contradicted");
+
+        for y in 0..30 {
+            for x in 0..40 {
+                let a = model.tiles().iter().position(|tile| *tile == map.get_tile(x, y)).unwrap();
+
+                for (direction, (dx, dy)) in DIRECTIONS.iter().enumerate() {
+                    if let Some(neighbor) = map.get(x + dx, y + dy) {
+                        let b = model.tiles().iter().position(|tile| *tile == neighbor.tile).unwrap();
+                        assert!(model.allowed[direction][a][b]);
+                    }
+                }
+            }
+        }
+
+        // Walled in on every side
+        assert!((0..40).all(|x| map.get_tile(x, 0) == TileType::Wall && map.get_tile(x, 29) == TileType::Wall));
+    }
+
+    #[test]
+    fn it_should_repeat_with_same_seed() {
+        let first = wfc_map_gen_with_rng(ruins_sample(), 30, 20, &mut StdRng::seed_from_u64(9));
+        let second = wfc_map_gen_with_rng(ruins_sample(), 30, 20, &mut StdRng::seed_from_u64(9));
+
+        let tiles = |map: &Map| map.get_cells().iter().map(|cell| cell.tile).collect::<Vec<_>>();
+
+        assert_eq!(tiles(&first), tiles(&second));
+    }
+}
@@ -0,0 +1,69 @@
+// Menus and cursors take keys without spending the player's turn
+
+use super::{Entity, EntityManager, Component, components};
+
+/// The key this tick was used up by a menu or cursor, nothing after it reacts and the world waits
+pub fn consume(em: &mut EntityManager, entity: Entity) {
+    if let Some(input) = get_component!(mut, em, entity, components::Input) {
+        input.input = 0;
+    }
+
+    em.add_component(entity, components::MenuInput);
+}
+
+/// Whether a menu or cursor is up, a level up is waiting on a choice, or a key just worked one
+pub fn in_menu(em: &EntityManager, entity: Entity) -> bool {
+    em.has_component(entity, components::MenuInput::get_component_type())
+        || em.has_component(entity, components::Casting::get_component_type())
+        || em.has_component(entity, components::Targeting::get_component_type())
+        || get_component!(em, entity, components::Experience).map_or(false, |experience| experience.unspent > 0)
+}
+
+/**
+ * Whether the world holds still this tick because whoever gives input is in a menu
+ * Run after the menu systems and before the rest, clears this tick's used up keys
+ */
+pub fn world_waits(em: &mut EntityManager) -> bool {
+    let input_entities = em.get_entities_with_components(components::Input::get_component_type());
+
+    let waits = input_entities.iter().any(|entity| in_menu(em, *entity));
+
+    for entity in input_entities {
+        em.remove_component(entity, components::MenuInput::get_component_type());
+    }
+
+    waits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_wait_while_a_menu_has_the_keys() {
+        let mut em = EntityManager::new();
+
+        let player = em.create_entity();
+        em.add_component(player, components::Input { input: 116 });
+
+        assert!(!world_waits(&mut em));
+
+        consume(&mut em, player);
+        assert_eq!(get_component!(em, player, components::Input).unwrap().input, 0);
+        assert!(world_waits(&mut em));
+
+        // Only for the tick the key was used up in
+        assert!(!world_waits(&mut em));
+
+        em.add_component(player, components::Targeting { x: 0, y: 0, mode: components::TargetMode::Travel, range: 0 });
+        assert!(world_waits(&mut em));
+
+        em.remove_component(player, components::Targeting::get_component_type());
+        em.add_component(player, components::Casting { spell: None });
+        assert!(world_waits(&mut em));
+
+        em.remove_component(player, components::Casting::get_component_type());
+        em.add_component(player, components::Experience { level: 2, xp: 20, unspent: 1 });
+        assert!(world_waits(&mut em));
+    }
+}
@@ -0,0 +1,109 @@
+// Lines for the message log window, kept in the Log component of whoever should read them
+
+use super::{Entity, EntityManager, Component, components};
+use crate::map::Map;
+use crate::interrupt::SIGHT_RADIUS;
+use crate::targeting;
+
+/// Add a line to an entity's log, entities without one don't hear about it
+pub fn push(em: &mut EntityManager, entity: Entity, message: String) {
+    if let Some(log) = get_component!(mut, em, entity, components::Log) {
+        log.history.push(message);
+    }
+}
+
+/// Add a line to the player's log, for things the player sees happen to others
+pub fn player(em: &mut EntityManager, message: String) {
+    let players = em.get_entities_with_components(components::Player::get_component_type());
+
+    if let Some(player) = players.first() {
+        push(em, *player, message);
+    }
+}
+
+/// Whether the player can see an entity, the player always sees themself
+pub fn player_sees(em: &EntityManager, entity: Entity) -> bool {
+    let players = em.get_entities_with_components(components::Player::get_component_type());
+
+    let player = match players.first() {
+        Some(player) => *player,
+        None => return false
+    };
+
+    if player == entity {
+        return true;
+    }
+
+    let map = em.get_entity_by_name("Map").and_then(|map_entity| get_component!(em, map_entity, Map));
+    let eye = get_component!(em, player, components::Position).map(|position| (position.x, position.y));
+    let target = get_component!(em, entity, components::Position).map(|position| (position.x, position.y));
+
+    match (map, eye, target) {
+        (Some(map), Some(eye), Some(target)) => {
+            targeting::distance(eye, target) <= SIGHT_RADIUS as i32 && map.has_line_of_sight(eye, target)
+        }
+        _ => false
+    }
+}
+
+/// Add a line to the player's log about what an entity did, only when the player sees it happen
+pub fn seen(em: &mut EntityManager, entity: Entity, message: String) {
+    if player_sees(em, entity) {
+        player(em, message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+
+    #[test]
+    fn it_should_only_log_to_entities_with_a_log() {
+        let mut em = EntityManager::new();
+
+        let hero = em.create_entity();
+        em.add_component(hero, components::Player);
+        em.add_component(hero, components::Log::new());
+
+        let zombie = em.create_entity();
+
+        push(&mut em, zombie, "Unheard.".to_string());
+        player(&mut em, "You hear a noise.".to_string());
+
+        assert_eq!(get_component!(em, hero, components::Log).unwrap().history, vec!["You hear a noise.".to_string()]);
+    }
+
+    #[test]
+    fn it_should_only_tell_the_player_what_they_see() {
+        let mut em = EntityManager::new();
+
+        let map = MapBuilder::new(30, 10)
+            .create_room(&Rect::new(0, 0, 10, 9))
+            .create_room(&Rect::new(15, 0, 10, 9))
+            .build();
+
+        let map_entity = em.create_entity();
+        em.set_entity_name(map_entity, "Map");
+        em.add_component(map_entity, map);
+
+        let hero = em.create_entity();
+        em.add_component(hero, components::Player);
+        em.add_component(hero, components::Log::new());
+        em.add_component(hero, components::Position { x: 2, y: 2 });
+
+        let near = em.create_entity();
+        em.add_component(near, components::Position { x: 5, y: 2 });
+
+        // Behind the wall in the other room
+        let far = em.create_entity();
+        em.add_component(far, components::Position { x: 17, y: 2 });
+
+        seen(&mut em, hero, "You wave.".to_string());
+        seen(&mut em, near, "The zombie groans.".to_string());
+        seen(&mut em, far, "The goblin sneezes.".to_string());
+
+        assert_eq!(get_component!(em, hero, components::Log).unwrap().history, vec!["You wave.".to_string(), "The zombie groans.".to_string()]);
+    }
+}
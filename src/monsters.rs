@@ -15,6 +15,8 @@ pub fn create_monster(
     em.add_component(monster, components::Position { x: x, y: y });
     em.add_component(monster, components::Health { health: baseHitPoints, max_health: baseHitPoints });
     em.add_component(monster, components::Walk { dx: 0, dy: 0 });
+    em.add_component(monster, components::Energy { amount: 0 });
+    em.add_component(monster, components::Speed { amount: 10 });
     em.add_component(monster, components::Collidable);
 
     monster
@@ -30,6 +32,14 @@ pub fn create_goblin(em: &mut EntityManager, x: i32, y: i32) -> Entity {
        8
    );
 
+   em.add_component(goblin, components::ChaseAi);
+   em.add_component(goblin, components::Faction { name: "goblins".to_string() });
+   em.add_component(goblin, components::XpReward { amount: 10 });
+   em.add_component(goblin, components::Loot { table: "goblin".to_string() });
+   // Quick and stabby, but weak
+   em.add_component(goblin, components::Attributes::new(8, 14, 10, 8, 8, 6));
+   em.add_component(goblin, components::Weapon { name: "dagger".to_string() });
+
    goblin
 }
 
@@ -44,6 +54,29 @@ pub fn create_zombie(em: &mut EntityManager, x: i32, y: i32) -> Entity {
     );
 
     em.add_component(zombie, components::RandomWalkAi);
+    em.add_component(zombie, components::Faction { name: "undead".to_string() });
+    em.add_component(zombie, components::XpReward { amount: 15 });
+    em.add_component(zombie, components::Loot { table: "zombie".to_string() });
+    // Slow and clumsy, but bites hard
+    em.add_component(zombie, components::Attributes::new(16, 6, 14, 2, 4, 2));
+    em.add_component(zombie, components::Weapon { name: "teeth".to_string() });
+    em.add_component(zombie, components::Armor { reduction: 1, evasion: 0 });
+    // Nothing left to poison or chill, but dry as kindling
+    em.add_component(zombie, components::Resistances::new()
+        .with(components::DamageType::Poison, 100)
+        .with(components::DamageType::Cold, 50)
+        .with(components::DamageType::Fire, -50));
+    // Rotting flesh gives off a faint glow
+    em.add_component(zombie, components::LightSource { radius: 2, color: [120, 255, 120] });
 
     zombie
-}
\ No newline at end of file
+}
+
+/// Monster by breed name, for spells and scripts
+pub fn create_breed(em: &mut EntityManager, breed: &str, x: i32, y: i32) -> Option<Entity> {
+    match breed {
+        "goblin" => Some(create_goblin(em, x, y)),
+        "zombie" => Some(create_zombie(em, x, y)),
+        _ => None
+    }
+}
@@ -0,0 +1,195 @@
+// Experience, levels and what you get for them
+
+use super::{Entity, EntityManager, Component, components};
+use crate::message_log;
+use crate::interrupt;
+
+/// Experience for level 2, each level after costs this much more than the last
+pub const LEVEL_XP_STEP: i32 = 20;
+
+/// Max health gained every level, on top of the constitution modifier
+pub const HEALTH_PER_LEVEL: i32 = 5;
+
+/// Total experience needed to reach a level
+pub fn xp_for_level(level: i32) -> i32 {
+    LEVEL_XP_STEP * (level - 1) * level / 2
+}
+
+/// One pick on the level up screen
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LevelUpChoice {
+    Strength,
+    Dexterity,
+    Constitution,
+    Speed
+}
+
+impl LevelUpChoice {
+    pub const ALL: [LevelUpChoice; 4] = [
+        LevelUpChoice::Strength,
+        LevelUpChoice::Dexterity,
+        LevelUpChoice::Constitution,
+        LevelUpChoice::Speed
+    ];
+
+    /// Chosen with the number keys, '1' is the first
+    pub fn from_key(key: i32) -> Option<LevelUpChoice> {
+        let index = key - '1' as i32;
+
+        if index >= 0 && (index as usize) < Self::ALL.len() {
+            Some(Self::ALL[index as usize])
+        } else {
+            None
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            LevelUpChoice::Strength => "+2 strength, hit harder",
+            LevelUpChoice::Dexterity => "+2 dexterity, hit and dodge more",
+            LevelUpChoice::Constitution => "+2 constitution, +10 max health",
+            LevelUpChoice::Speed => "+2 speed, an extra action every few turns"
+        }
+    }
+
+    pub fn apply(&self, em: &mut EntityManager, entity: Entity) {
+        match self {
+            LevelUpChoice::Strength | LevelUpChoice::Dexterity | LevelUpChoice::Constitution => {
+                if get_component!(em, entity, components::Attributes).is_none() {
+                    em.add_component(entity, components::Attributes::default());
+                }
+
+                let attributes = get_component!(mut, em, entity, components::Attributes).unwrap();
+
+                match self {
+                    LevelUpChoice::Strength => attributes.strength += 2,
+                    LevelUpChoice::Dexterity => attributes.dexterity += 2,
+                    _ => attributes.constitution += 2
+                }
+
+                if *self == LevelUpChoice::Constitution {
+                    if let Some(health) = get_component!(mut, em, entity, components::Health) {
+                        health.max_health += 10;
+                        health.health += 10;
+                    }
+                }
+            }
+            LevelUpChoice::Speed => {
+                if let Some(speed) = get_component!(mut, em, entity, components::Speed) {
+                    speed.amount += 2;
+                }
+            }
+        }
+    }
+}
+
+/**
+ * Give experience, leveling up as many times as it pays for
+ * Every level adds max health straight away and a choice to spend later
+ */
+pub fn award_xp(em: &mut EntityManager, entity: Entity, amount: i32) {
+    let gained = match get_component!(mut, em, entity, components::Experience) {
+        Some(experience) => {
+            experience.xp += amount;
+
+            let mut gained = Vec::new();
+
+            while experience.xp >= xp_for_level(experience.level + 1) {
+                experience.level += 1;
+                experience.unspent += 1;
+                gained.push(experience.level);
+            }
+
+            gained
+        }
+        None => return
+    };
+
+    let constitution = get_component!(em, entity, components::Attributes)
+        .map_or(0, |attributes| attributes.modifier(attributes.constitution));
+
+    for level in gained {
+        if let Some(health) = get_component!(mut, em, entity, components::Health) {
+            let bonus = (HEALTH_PER_LEVEL + constitution).max(1);
+
+            health.max_health += bonus;
+            health.health += bonus;
+        }
+
+        message_log::push(em, entity, format!("Welcome to level {}! Choose an improvement.", level));
+
+        // The level up screen has the keys now
+        interrupt::stop(em, entity, "You stop to take stock.");
+    }
+}
+
+/// Spend one pending level up, false if there was nothing to spend
+pub fn choose(em: &mut EntityManager, entity: Entity, choice: LevelUpChoice) -> bool {
+    match get_component!(mut, em, entity, components::Experience) {
+        Some(experience) if experience.unspent > 0 => experience.unspent -= 1,
+        _ => return false
+    }
+
+    choice.apply(em, entity);
+
+    message_log::push(em, entity, format!("You feel stronger: {}.", choice.description()));
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hero(em: &mut EntityManager) -> Entity {
+        let entity = em.create_entity();
+
+        em.add_component(entity, components::Experience::new());
+        em.add_component(entity, components::Attributes::default());
+        em.add_component(entity, components::Health { health: 20, max_health: 20 });
+        em.add_component(entity, components::Speed { amount: 10 });
+
+        entity
+    }
+
+    #[test]
+    fn it_should_level_up_at_thresholds() {
+        assert_eq!(xp_for_level(1), 0);
+        assert_eq!(xp_for_level(2), 20);
+        assert_eq!(xp_for_level(3), 60);
+
+        let mut em = EntityManager::new();
+        let entity = hero(&mut em);
+
+        award_xp(&mut em, entity, 19);
+        assert_eq!(get_component!(em, entity, components::Experience).unwrap().level, 1);
+
+        // Enough for two levels at once
+        award_xp(&mut em, entity, 45);
+
+        let experience = get_component!(em, entity, components::Experience).unwrap();
+        assert_eq!((experience.level, experience.xp, experience.unspent), (3, 64, 2));
+        assert_eq!(get_component!(em, entity, components::Health).unwrap().max_health, 30);
+    }
+
+    #[test]
+    fn it_should_spend_level_ups_on_choices() {
+        let mut em = EntityManager::new();
+        let entity = hero(&mut em);
+
+        assert!(!choose(&mut em, entity, LevelUpChoice::Strength));
+
+        award_xp(&mut em, entity, 60);
+
+        assert_eq!(LevelUpChoice::from_key('4' as i32), Some(LevelUpChoice::Speed));
+        assert_eq!(LevelUpChoice::from_key('5' as i32), None);
+
+        assert!(choose(&mut em, entity, LevelUpChoice::Speed));
+        assert!(choose(&mut em, entity, LevelUpChoice::Constitution));
+        assert!(!choose(&mut em, entity, LevelUpChoice::Strength));
+
+        assert_eq!(get_component!(em, entity, components::Speed).unwrap().amount, 12);
+        assert_eq!(get_component!(em, entity, components::Attributes).unwrap().constitution, 12);
+        assert_eq!(get_component!(em, entity, components::Health).unwrap().max_health, 40);
+    }
+}
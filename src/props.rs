@@ -0,0 +1,70 @@
+use rand::Rng;
+
+use super::{Entity, EntityManager, components, items};
+use crate::loot::loot_tables;
+
+pub fn create_door(
+    em: &mut EntityManager,
+    x: i32,
+    y: i32,
+    key: Option<&str>
+) -> Entity {
+    let door = em.create_entity();
+
+    em.add_component(door, components::Name { name: "door".to_string() });
+    em.add_component(door, components::Position { x: x, y: y });
+    em.add_component(door, components::Render { glyph: '+', layer: components::RenderLayer::Item });
+    em.add_component(door, components::Collidable);
+    em.add_component(door, components::Door {
+        open: false,
+        locked: key.is_some(),
+        key: key.map(|key| key.to_string())
+    });
+
+    door
+}
+
+/// Wall torches light up the rooms they are placed in
+pub fn create_torch(
+    em: &mut EntityManager,
+    x: i32,
+    y: i32
+) -> Entity {
+    let torch = em.create_entity();
+
+    em.add_component(torch, components::Name { name: "torch".to_string() });
+    em.add_component(torch, components::Position { x: x, y: y });
+    em.add_component(torch, components::Render { glyph: '*', layer: components::RenderLayer::Item });
+    em.add_component(torch, components::LightSource { radius: 7, color: [255, 180, 80] });
+
+    torch
+}
+
+/// Chests are filled from a loot table when placed
+pub fn create_chest<R: Rng>(
+    em: &mut EntityManager,
+    x: i32,
+    y: i32,
+    table: &str,
+    rng: &mut R
+) -> Entity {
+    let chest = em.create_entity();
+
+    em.add_component(chest, components::Name { name: "chest".to_string() });
+    em.add_component(chest, components::Position { x: x, y: y });
+    em.add_component(chest, components::Render { glyph: '&', layer: components::RenderLayer::Item });
+    em.add_component(chest, components::Collidable);
+    em.add_component(chest, components::Chest { open: false });
+
+    let mut inventory = components::Inventory::new();
+
+    for drop in loot_tables().roll(table, rng) {
+        for item in items::create_item(em, &drop.item, drop.count) {
+            inventory.add_item(item);
+        }
+    }
+
+    em.add_component(chest, inventory);
+
+    chest
+}
@@ -0,0 +1,466 @@
+// Lua scripts that hook into the game: they run when something happens
+// and call back into the game to say what should come of it
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use rlua::{Lua, Table, Function};
+
+use super::{Entity, EntityManager, Component, components};
+use components::{DamageType, StatusKind};
+use crate::assets;
+use crate::combat;
+use crate::magic::{self, SpellDef, Reach, Grimoire};
+use crate::map::Map;
+use crate::map::los::collidable_occupants;
+use crate::monsters;
+use crate::spatial::SpatialIndex;
+use crate::status;
+use crate::message_log;
+
+/// What a script asked for, carried out once the script returns
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptEffect {
+    Damage { target: Entity, amount: i32, kind: DamageType },
+    Heal { target: Entity, amount: i32 },
+    Teleport { target: Entity, x: i32, y: i32 },
+    Summon { breed: String, x: i32, y: i32 },
+    Status { target: Entity, kind: StatusKind, turns: i32, magnitude: i32 },
+    Message(String)
+}
+
+// Names in scripts match the data files, "Fire" and "Poisoned"
+fn parse<T: serde::de::DeserializeOwned>(name: &str) -> rlua::Result<T> {
+    serde_json::from_value(serde_json::Value::String(name.to_string()))
+        .map_err(|_| rlua::Error::RuntimeError(format!("unknown name {}", name)))
+}
+
+const DEATH_HOOKS: &str = "hooks:death";
+
+/// Script folders inside the assets folder, loaded in this order
+const FOLDERS: [&str; 2] = ["scripts", "spells"];
+
+fn cast_key(name: &str) -> String {
+    format!("spell:{}", name)
+}
+
+/**
+ * The lua host every script runs in
+ * on_death(function(death) ... end) runs a function whenever something dies
+ * and spell { ... } registers a spell, see magic.rs, its cast function runs when it's cast
+ * Both call damage, heal, teleport, summon, status and message to say what happens
+ */
+pub struct Scripts {
+    lua: Lua,
+    spells: Arc<Mutex<Vec<SpellDef>>>,
+    effects: Arc<Mutex<Vec<ScriptEffect>>>
+}
+
+impl std::fmt::Debug for Scripts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Scripts")
+            .field("spells", &self.spells)
+            .finish()
+    }
+}
+
+impl Scripts {
+    /// A host without any scripts loaded, scripts that can't reach the game api fail when they run
+    pub fn new() -> Self {
+        let scripts = Self {
+            lua: Lua::new(),
+            spells: Arc::new(Mutex::new(Vec::new())),
+            effects: Arc::new(Mutex::new(Vec::new()))
+        };
+
+        if let Err(error) = scripts.setup_globals() {
+            error!("Failed to set up the script api: {}", error);
+        }
+
+        scripts
+    }
+
+    fn setup_globals(&self) -> rlua::Result<()> {
+        self.lua.context(|lua_ctx| {
+            let globals = lua_ctx.globals();
+
+            // on_death keeps its functions in a registry table, in the order they were added
+            lua_ctx.set_named_registry_value(DEATH_HOOKS, lua_ctx.create_table()?)?;
+
+            globals.set("on_death", lua_ctx.create_function(|ctx, hook: Function| {
+                let hooks: Table = ctx.named_registry_value(DEATH_HOOKS)?;
+                hooks.set(hooks.len()? + 1, hook)
+            })?)?;
+
+            let spells = self.spells.clone();
+            globals.set("spell", lua_ctx.create_function(move |ctx, table: Table| {
+                let def = magic::spell_def(&table)?;
+
+                let cast: Function = table.get("cast")?;
+                ctx.set_named_registry_value(&cast_key(&def.name), cast)?;
+
+                // Loading a script again replaces its spells
+                let mut spells = spells.lock().unwrap();
+                spells.retain(|other| other.name != def.name);
+                spells.push(def);
+
+                Ok(())
+            })?)?;
+
+            let effects = self.effects.clone();
+            globals.set("damage", lua_ctx.create_function(move |_, (id, amount, kind): (i32, i32, Option<String>)| {
+                let kind = match kind {
+                    Some(kind) => parse(&kind)?,
+                    None => DamageType::default()
+                };
+
+                effects.lock().unwrap().push(ScriptEffect::Damage { target: Entity { id: id }, amount: amount, kind: kind });
+                Ok(())
+            })?)?;
+
+            let effects = self.effects.clone();
+            globals.set("heal", lua_ctx.create_function(move |_, (id, amount): (i32, i32)| {
+                effects.lock().unwrap().push(ScriptEffect::Heal { target: Entity { id: id }, amount: amount });
+                Ok(())
+            })?)?;
+
+            let effects = self.effects.clone();
+            globals.set("teleport", lua_ctx.create_function(move |_, (id, x, y): (i32, i32, i32)| {
+                effects.lock().unwrap().push(ScriptEffect::Teleport { target: Entity { id: id }, x: x, y: y });
+                Ok(())
+            })?)?;
+
+            let effects = self.effects.clone();
+            globals.set("summon", lua_ctx.create_function(move |_, (breed, x, y): (String, i32, i32)| {
+                effects.lock().unwrap().push(ScriptEffect::Summon { breed: breed, x: x, y: y });
+                Ok(())
+            })?)?;
+
+            let effects = self.effects.clone();
+            globals.set("status", lua_ctx.create_function(move |_, (id, kind, turns, magnitude): (i32, String, i32, Option<i32>)| {
+                let kind = parse(&kind)?;
+
+                effects.lock().unwrap().push(ScriptEffect::Status {
+                    target: Entity { id: id },
+                    kind: kind,
+                    turns: turns,
+                    magnitude: magnitude.unwrap_or(0)
+                });
+                Ok(())
+            })?)?;
+
+            let effects = self.effects.clone();
+            globals.set("message", lua_ctx.create_function(move |_, text: String| {
+                effects.lock().unwrap().push(ScriptEffect::Message(text));
+                Ok(())
+            })?)?;
+
+            Ok(())
+        })
+    }
+
+    /// Run a script, any hooks and spells it adds stay in place
+    pub fn load(&self, source: &str) -> rlua::Result<()> {
+        self.lua.context(|lua_ctx| {
+            lua_ctx.load(source).exec()
+        })
+    }
+
+    /// Every .lua file in a directory, in name order
+    pub fn load_dir<P: AsRef<Path>>(&self, dir: P) -> Result<(), Box<dyn std::error::Error>> {
+        let mut paths: Vec<_> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().map_or(false, |extension| extension == "lua"))
+            .collect();
+
+        paths.sort();
+
+        for path in paths {
+            let source = std::fs::read_to_string(&path)?;
+
+            self.load(&source)
+                .map_err(|error| format!("{}: {}", path.display(), error))?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Run every on_death hook and collect what they asked for
+     * Each gets a table with the entity, its name, where it died and its killer, if any
+     */
+    pub fn death(
+        &self,
+        entity: Entity,
+        name: Option<&str>,
+        position: Option<(i32, i32)>,
+        killer: Option<Entity>
+    ) -> rlua::Result<Vec<ScriptEffect>> {
+        self.effects.lock().unwrap().clear();
+
+        self.lua.context(|lua_ctx| {
+            let hooks: Table = lua_ctx.named_registry_value(DEATH_HOOKS)?;
+
+            for hook in hooks.sequence_values::<Function>() {
+                let death = lua_ctx.create_table()?;
+                death.set("id", entity.id)?;
+                death.set("name", name)?;
+                death.set("x", position.map(|position| position.0))?;
+                death.set("y", position.map(|position| position.1))?;
+                death.set("killer", killer.map(|killer| killer.id))?;
+
+                hook?.call::<_, ()>(death)?;
+            }
+
+            Ok(())
+        })?;
+
+        Ok(self.effects.lock().unwrap().drain(..).collect())
+    }
+
+    pub fn spell(&self, name: &str) -> Option<SpellDef> {
+        self.spells.lock().unwrap().iter().find(|def| def.name == name).cloned()
+    }
+
+    pub fn grimoire(&self) -> Grimoire {
+        Grimoire {
+            spells: self.spells.lock().unwrap().clone()
+        }
+    }
+
+    /**
+     * Run a spell's cast function and collect what it asked for
+     * The script gets a table with the caster, the target tile and the entities reached
+     */
+    pub fn cast(&self, name: &str, caster: Entity, reach: &Reach) -> rlua::Result<Vec<ScriptEffect>> {
+        self.effects.lock().unwrap().clear();
+
+        self.lua.context(|lua_ctx| {
+            let cast: Function = lua_ctx.named_registry_value(&cast_key(name))?;
+
+            let targets = lua_ctx.create_table()?;
+
+            for (index, (entity, (x, y))) in reach.entities.iter().enumerate() {
+                let target = lua_ctx.create_table()?;
+                target.set("id", entity.id)?;
+                target.set("x", *x)?;
+                target.set("y", *y)?;
+
+                targets.set(index + 1, target)?;
+            }
+
+            let args = lua_ctx.create_table()?;
+            args.set("caster", caster.id)?;
+            args.set("x", reach.target.0)?;
+            args.set("y", reach.target.1)?;
+            args.set("targets", targets)?;
+
+            cast.call::<_, ()>(args)
+        })?;
+
+        Ok(self.effects.lock().unwrap().drain(..).collect())
+    }
+}
+
+/// A folder of scripts inside the assets folder
+pub fn folder(name: &str) -> Result<PathBuf, String> {
+    let dir = assets::dir()?.join(name);
+
+    match dir.is_dir() {
+        true => Ok(dir),
+        false => Err(format!("No {} folder at {}", name, dir.display()))
+    }
+}
+
+/// A host with every script in the script folders, or an empty one when they can't all be loaded
+pub fn load() -> Scripts {
+    let scripts = Scripts::new();
+
+    for name in FOLDERS.iter() {
+        let loaded = folder(name).and_then(|dir| {
+            scripts.load_dir(&dir)
+                .map_err(|error| format!("Failed to load scripts from {}: {}", dir.display(), error))
+        });
+
+        if let Err(error) = loaded {
+            error!("{}, carrying on without scripts", error);
+            return Scripts::new();
+        }
+    }
+
+    scripts
+}
+
+// Nothing in the way on the map or standing there
+fn free(em: &EntityManager, x: i32, y: i32) -> bool {
+    let open = em.get_entity_by_name("Map")
+        .and_then(|map_entity| get_component!(em, map_entity, Map))
+        .map_or(false, |map| !map.is_blocked(x, y));
+
+    let empty = match em.get_entity_by_name("SpatialIndex").and_then(|index_entity| get_component!(em, index_entity, SpatialIndex)) {
+        Some(index) => index.entities_at_with(em, x, y, components::Collidable::get_component_type()).is_empty(),
+        None => !collidable_occupants(em).contains_key(&(x, y))
+    };
+
+    open && empty
+}
+
+/// Carry out what a script asked for, the source is credited with any damage done under the given name
+pub fn apply(em: &mut EntityManager, source: Entity, name: &str, effects: Vec<ScriptEffect>) {
+    for effect in effects {
+        match effect {
+            ScriptEffect::Damage { target, amount, kind } => {
+                if !em.has_component(target, components::Health::get_component_type()) {
+                    continue;
+                }
+
+                combat::inflict(em, components::Damage {
+                    amount: amount,
+                    target: target,
+                    kind: kind,
+                    source: Some(source),
+                    weapon: Some(name.to_string())
+                });
+            }
+            ScriptEffect::Heal { target, amount } => {
+                let healed = match get_component!(mut, em, target, components::Health) {
+                    Some(health) => {
+                        let before = health.health;
+                        health.health = (health.health + amount).min(health.max_health);
+                        health.health - before
+                    }
+                    None => continue
+                };
+
+                let verb = match em.has_component(target, components::Player::get_component_type()) {
+                    true => "are",
+                    false => "is"
+                };
+
+                let message = combat::sentence(format!("{} {} healed for {}.", combat::describe(em, target), verb, healed));
+                message_log::seen(em, target, message);
+            }
+            ScriptEffect::Teleport { target, x, y } => {
+                if !free(em, x, y) {
+                    warn!("{} tried to teleport onto a taken tile {},{}", name, x, y);
+                    continue;
+                }
+
+                if let Some(position) = get_component!(mut, em, target, components::Position) {
+                    position.x = x;
+                    position.y = y;
+                }
+            }
+            ScriptEffect::Summon { breed, x, y } => {
+                if !free(em, x, y) {
+                    warn!("{} tried to summon a {} onto a taken tile {},{}", name, breed, x, y);
+                    continue;
+                }
+
+                let summoned = match monsters::create_breed(em, &breed, x, y) {
+                    Some(summoned) => summoned,
+                    None => {
+                        warn!("{} tried to summon unknown breed {}", name, breed);
+                        continue;
+                    }
+                };
+
+                // Fights for whoever summoned it and is worth nothing to them
+                if let Some(faction) = get_component!(em, source, components::Faction).cloned() {
+                    em.add_component(summoned, faction);
+                }
+
+                em.remove_component(summoned, components::XpReward::get_component_type());
+                em.remove_component(summoned, components::Loot::get_component_type());
+            }
+            ScriptEffect::Status { target, kind, turns, magnitude } => {
+                if !em.has_component(target, components::Health::get_component_type()) {
+                    continue;
+                }
+
+                status::apply_status(em, target, components::StatusEffect {
+                    kind: kind,
+                    turns: turns,
+                    magnitude: magnitude,
+                    source: Some(source)
+                });
+            }
+            ScriptEffect::Message(text) => message_log::seen(em, source, text)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_collect_what_death_hooks_ask_for() {
+        let scripts = Scripts::new();
+
+        scripts.load(r#"
+            on_death(function(death)
+                if death.name == "zombie" then
+                    damage(death.killer, 2, "Poison")
+                    status(death.killer, "Poisoned", 3, 1)
+                end
+            end)
+            on_death(function(death)
+                message(death.name .. " at " .. death.x .. "," .. death.y)
+            end)
+        "#).unwrap();
+
+        let killer = Entity { id: 1 };
+
+        assert_eq!(scripts.death(Entity { id: 2 }, Some("zombie"), Some((3, 4)), Some(killer)).unwrap(), vec![
+            ScriptEffect::Damage { target: killer, amount: 2, kind: DamageType::Poison },
+            ScriptEffect::Status { target: killer, kind: StatusKind::Poisoned, turns: 3, magnitude: 1 },
+            ScriptEffect::Message("zombie at 3,4".to_string())
+        ]);
+
+        assert!(scripts.load(r#"on_death(function(death) damage(death.killer, 1, "Acid") end)"#).is_ok());
+        assert!(scripts.death(Entity { id: 2 }, Some("zombie"), Some((3, 4)), Some(killer)).is_err());
+    }
+
+    #[test]
+    fn it_should_only_summon_onto_free_tiles() {
+        let mut em = EntityManager::new();
+
+        let map_entity = em.create_entity();
+        em.set_entity_name(map_entity, "Map");
+        em.add_component(map_entity, crate::map::MapBuilder::new(10, 10).create_room(&crate::types::Rect::new(0, 0, 9, 9)).build());
+
+        let caster = em.create_entity();
+        em.add_component(caster, components::Faction { name: "player".to_string() });
+        em.add_component(caster, components::Position { x: 2, y: 2 });
+        em.add_component(caster, components::Collidable);
+
+        let summon = |x: i32, y: i32| ScriptEffect::Summon { breed: "zombie".to_string(), x: x, y: y };
+
+        // A wall, the caster's own tile, then open floor
+        apply(&mut em, caster, "raise dead", vec![summon(0, 0), summon(2, 2), summon(4, 4)]);
+
+        let summoned = em.get_entities_with_components(components::RandomWalkAi::get_component_type());
+        assert_eq!(summoned.len(), 1);
+        assert_eq!(get_component!(em, summoned[0], components::Position), Some(&components::Position { x: 4, y: 4 }));
+        assert_eq!(get_component!(em, summoned[0], components::Faction).unwrap().name, "player");
+        assert!(!em.has_component(summoned[0], components::Loot::get_component_type()));
+
+        // Taken now, the index sees it as well as a scan would
+        let index_entity = em.create_entity();
+        em.set_entity_name(index_entity, "SpatialIndex");
+        em.add_component(index_entity, SpatialIndex::build(&em));
+
+        apply(&mut em, caster, "raise dead", vec![summon(4, 4)]);
+        assert_eq!(em.get_entities_with_components(components::RandomWalkAi::get_component_type()).len(), 1);
+    }
+
+    #[test]
+    fn it_should_load_the_script_folders() {
+        let scripts = Scripts::new();
+
+        for name in FOLDERS.iter() {
+            scripts.load_dir(folder(name).unwrap()).unwrap();
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use super::{Entity, EntityManager, Component, ComponentType, components};
+use crate::types::Rect;
+
+/**
+ * Grid of entity positions
+ * Answers "what's at (x, y)" without scanning every entity,
+ * kept up to date by the spatial index system from the positions that changed
+ */
+#[derive(Debug)]
+pub struct SpatialIndex {
+    cells: HashMap<(i32, i32), Vec<Entity>>,
+    positions: HashMap<Entity, (i32, i32)>
+}
+
+impl SpatialIndex {
+    pub fn new() -> Self {
+        Self {
+            cells: HashMap::new(),
+            positions: HashMap::new()
+        }
+    }
+
+    /// Index every entity with a position
+    pub fn build(em: &EntityManager) -> Self {
+        let mut index = Self::new();
+
+        for entity in em.get_entities_with_components(components::Position::get_component_type()) {
+            index.update(em, entity);
+        }
+
+        index
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: (i32, i32)) {
+        if let Some(previous) = self.positions.insert(entity, position) {
+            if previous == position {
+                return;
+            }
+
+            self.remove_from_cell(entity, previous);
+        }
+
+        self.cells.entry(position).or_insert_with(Vec::new).push(entity);
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some(previous) = self.positions.remove(&entity) {
+            self.remove_from_cell(entity, previous);
+        }
+    }
+
+    fn remove_from_cell(&mut self, entity: Entity, position: (i32, i32)) {
+        let now_empty = match self.cells.get_mut(&position) {
+            Some(entities) => {
+                entities.retain(|other| *other != entity);
+                entities.is_empty()
+            }
+            None => false
+        };
+
+        if now_empty {
+            self.cells.remove(&position);
+        }
+    }
+
+    /// Move an entity to wherever its position component says, or drop it when it has none
+    pub fn update(&mut self, em: &EntityManager, entity: Entity) {
+        match get_component!(em, entity, components::Position) {
+            Some(position) => self.insert(entity, (position.x, position.y)),
+            None => self.remove(entity)
+        }
+    }
+
+    pub fn position(&self, entity: Entity) -> Option<(i32, i32)> {
+        self.positions.get(&entity).cloned()
+    }
+
+    pub fn entities_at(&self, x: i32, y: i32) -> &[Entity] {
+        self.cells.get(&(x, y)).map_or(&[], |entities| entities.as_slice())
+    }
+
+    /// Entities at a position that have a component
+    pub fn entities_at_with(&self, em: &EntityManager, x: i32, y: i32, component_type: ComponentType) -> Vec<Entity> {
+        self.entities_at(x, y)
+            .iter()
+            .filter(|entity| em.has_component(**entity, component_type))
+            .cloned()
+            .collect()
+    }
+
+    /// Entities inside the rect, edges included
+    pub fn entities_in_rect(&self, rect: &Rect) -> Vec<Entity> {
+        let area = (rect.width() + 1) as usize * (rect.height() + 1) as usize;
+
+        // Small rects look up each tile, large ones walk the occupied tiles
+        if area <= self.cells.len() {
+            let mut entities = Vec::new();
+
+            for y in rect.y1..(rect.y2 + 1) {
+                for x in rect.x1..(rect.x2 + 1) {
+                    entities.extend_from_slice(self.entities_at(x, y));
+                }
+            }
+
+            entities
+        } else {
+            self.cells.iter()
+                .filter(|((x, y), _)| *x >= rect.x1 && *x <= rect.x2 && *y >= rect.y1 && *y <= rect.y2)
+                .flat_map(|(_, entities)| entities.iter().cloned())
+                .collect()
+        }
+    }
+
+    /**
+     * Closest entity with a component, searching outward in square rings
+     * Distance is measured in king moves, ties go to whichever is found first
+     */
+    pub fn nearest_with(
+        &self,
+        em: &EntityManager,
+        origin: (i32, i32),
+        component_type: ComponentType,
+        max_distance: i32
+    ) -> Option<Entity> {
+        let (ox, oy) = origin;
+
+        for distance in 0..(max_distance + 1) {
+            for y in (oy - distance)..(oy + distance + 1) {
+                for x in (ox - distance)..(ox + distance + 1) {
+                    // Only the ring at this distance, the inside was already searched
+                    if (x - ox).abs() != distance && (y - oy).abs() != distance {
+                        continue;
+                    }
+
+                    if let Some(entity) = self.entities_at(x, y).iter().find(|entity| em.has_component(**entity, component_type)) {
+                        return Some(*entity);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl Component for SpatialIndex {
+    derive_component!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_track_moving_entities() {
+        let mut em = EntityManager::new();
+
+        let entity = em.create_entity();
+        em.add_component(entity, components::Position { x: 3, y: 4 });
+
+        let mut index = SpatialIndex::build(&em);
+        assert_eq!(index.entities_at(3, 4), &[entity]);
+
+        get_component!(mut, em, entity, components::Position).unwrap().x = 5;
+        index.update(&em, entity);
+
+        assert!(index.entities_at(3, 4).is_empty());
+        assert_eq!(index.entities_at(5, 4), &[entity]);
+
+        em.remove_component(entity, components::Position::get_component_type());
+        index.update(&em, entity);
+
+        assert!(index.entities_at(5, 4).is_empty());
+        assert_eq!(index.position(entity), None);
+    }
+
+    #[test]
+    fn it_should_query_rects_and_nearest() {
+        let mut em = EntityManager::new();
+
+        let near = em.create_entity();
+        em.add_component(near, components::Position { x: 2, y: 2 });
+        em.add_component(near, components::Item);
+
+        let far = em.create_entity();
+        em.add_component(far, components::Position { x: 8, y: 8 });
+        em.add_component(far, components::Item);
+
+        let other = em.create_entity();
+        em.add_component(other, components::Position { x: 1, y: 1 });
+
+        let index = SpatialIndex::build(&em);
+
+        let in_rect = index.entities_in_rect(&Rect::new(0, 0, 3, 3));
+        assert_eq!(in_rect.len(), 2);
+        assert!(in_rect.contains(&near) && in_rect.contains(&other));
+
+        let item = components::Item::get_component_type();
+        assert_eq!(index.nearest_with(&em, (0, 0), item, 10), Some(near));
+        assert_eq!(index.nearest_with(&em, (9, 9), item, 10), Some(far));
+        assert_eq!(index.nearest_with(&em, (0, 0), item, 1), None);
+    }
+}
@@ -0,0 +1,190 @@
+// Status effects: how they stack and what they change while they last
+
+use rand::Rng;
+
+use super::{Entity, EntityManager, Component, components};
+use components::{StatusEffect, StatusKind};
+use crate::combat;
+use crate::message_log;
+
+/// What happens when an effect lands on someone who already has it
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Stacking {
+    /// Keep the longer duration and the stronger magnitude
+    Refresh,
+    /// Magnitudes add up, keep the longer duration
+    Intensify,
+    /// Durations add up
+    Extend
+}
+
+impl StatusKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatusKind::Poisoned => "poisoned",
+            StatusKind::Burning => "burning",
+            StatusKind::Slowed => "slowed",
+            StatusKind::Hasted => "hasted",
+            StatusKind::Stunned => "stunned",
+            StatusKind::Regenerating => "regenerating",
+            StatusKind::Confused => "confused"
+        }
+    }
+
+    pub fn stacking(&self) -> Stacking {
+        match self {
+            StatusKind::Poisoned => Stacking::Intensify,
+            StatusKind::Stunned => Stacking::Extend,
+            _ => Stacking::Refresh
+        }
+    }
+
+    /// The effect this one cancels out when applied
+    pub fn opposite(&self) -> Option<StatusKind> {
+        match self {
+            StatusKind::Slowed => Some(StatusKind::Hasted),
+            StatusKind::Hasted => Some(StatusKind::Slowed),
+            _ => None
+        }
+    }
+}
+
+/// Add an effect to an entity, stacking with one it already has
+pub fn apply_status(em: &mut EntityManager, entity: Entity, effect: StatusEffect) {
+    if get_component!(em, entity, components::StatusEffects).is_none() {
+        em.add_component(entity, components::StatusEffects::default());
+    }
+
+    let status = get_component!(mut, em, entity, components::StatusEffects).unwrap();
+
+    if let Some(opposite) = effect.kind.opposite() {
+        status.effects.retain(|other| other.kind != opposite);
+    }
+
+    match status.effects.iter_mut().find(|other| other.kind == effect.kind) {
+        Some(existing) => match effect.kind.stacking() {
+            Stacking::Refresh => {
+                existing.turns = existing.turns.max(effect.turns);
+                existing.magnitude = existing.magnitude.max(effect.magnitude);
+            }
+            Stacking::Intensify => {
+                existing.turns = existing.turns.max(effect.turns);
+                existing.magnitude += effect.magnitude;
+            }
+            Stacking::Extend => {
+                existing.turns += effect.turns;
+            }
+        },
+        None => status.effects.push(effect)
+    }
+
+    message_log::push(em, entity, format!("You are {}!", effect.kind.name()));
+}
+
+pub fn has_status(em: &EntityManager, entity: Entity, kind: StatusKind) -> bool {
+    get_component!(em, entity, components::StatusEffects).map_or(false, |status| status.has(kind))
+}
+
+/// Speed after haste and slow, the TurnSystem turns it into actions
+pub fn effective_speed(em: &EntityManager, entity: Entity, speed: i32) -> i32 {
+    if has_status(em, entity, StatusKind::Hasted) {
+        speed * 2
+    } else if has_status(em, entity, StatusKind::Slowed) {
+        speed / 2
+    } else {
+        speed
+    }
+}
+
+/// Attributes after effects, used when resolving attacks
+pub fn effective_attributes(em: &EntityManager, entity: Entity, attributes: components::Attributes) -> components::Attributes {
+    let mut attributes = attributes;
+
+    let status = match get_component!(em, entity, components::StatusEffects) {
+        Some(status) => status,
+        None => return attributes
+    };
+
+    for effect in status.effects.iter() {
+        match effect.kind {
+            StatusKind::Poisoned => attributes.strength -= 2,
+            StatusKind::Slowed => attributes.dexterity -= 2,
+            StatusKind::Hasted => attributes.dexterity += 2,
+            StatusKind::Stunned => attributes.dexterity -= 4,
+            _ => {}
+        }
+    }
+
+    attributes
+}
+
+/**
+ * Movement after effects, stunned entities stand still and confused ones stagger about
+ * Haste and slow work through speed instead
+ */
+pub fn effective_walk<R: Rng>(status: &components::StatusEffects, walk: (i32, i32), rng: &mut R) -> (i32, i32) {
+    if walk == (0, 0) {
+        return walk;
+    }
+
+    if status.has(StatusKind::Stunned) {
+        return (0, 0);
+    }
+
+    if status.has(StatusKind::Confused) {
+        return (rng.gen_range(-1, 2), rng.gen_range(-1, 2));
+    }
+
+    walk
+}
+
+/// Effects for the player info panel, "poisoned 3"
+pub fn describe(status: &components::StatusEffects) -> Vec<String> {
+    status.effects.iter()
+        .map(|effect| format!("{} {}", combat::sentence(effect.kind.name().to_string()), effect.turns))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn effect(kind: StatusKind, turns: i32, magnitude: i32) -> StatusEffect {
+        StatusEffect { kind: kind, turns: turns, magnitude: magnitude, source: None }
+    }
+
+    #[test]
+    fn it_should_stack_by_kind() {
+        let mut em = EntityManager::new();
+        let entity = em.create_entity();
+
+        apply_status(&mut em, entity, effect(StatusKind::Poisoned, 5, 1));
+        apply_status(&mut em, entity, effect(StatusKind::Poisoned, 3, 2));
+        apply_status(&mut em, entity, effect(StatusKind::Stunned, 2, 0));
+        apply_status(&mut em, entity, effect(StatusKind::Stunned, 2, 0));
+        apply_status(&mut em, entity, effect(StatusKind::Burning, 4, 2));
+        apply_status(&mut em, entity, effect(StatusKind::Burning, 2, 1));
+
+        let status = get_component!(em, entity, components::StatusEffects).unwrap();
+
+        assert_eq!(status.get(StatusKind::Poisoned), Some(&effect(StatusKind::Poisoned, 5, 3)));
+        assert_eq!(status.get(StatusKind::Stunned).unwrap().turns, 4);
+        assert_eq!(status.get(StatusKind::Burning), Some(&effect(StatusKind::Burning, 4, 2)));
+    }
+
+    #[test]
+    fn it_should_cancel_haste_with_slow() {
+        let mut em = EntityManager::new();
+        let entity = em.create_entity();
+
+        apply_status(&mut em, entity, effect(StatusKind::Hasted, 5, 0));
+        assert_eq!(effective_speed(&em, entity, 10), 20);
+
+        apply_status(&mut em, entity, effect(StatusKind::Slowed, 5, 0));
+        assert_eq!(effective_speed(&em, entity, 10), 5);
+        assert!(!has_status(&em, entity, StatusKind::Hasted));
+
+        let attributes = effective_attributes(&em, entity, components::Attributes::default());
+        assert_eq!(attributes.dexterity, 8);
+    }
+}
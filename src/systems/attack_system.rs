@@ -1,37 +1,136 @@
 use super::{System};
 use crate::entities::EntityManager;
 use crate::components::{Component, self};
+use crate::combat::{self, AttackOutcome, Combatant};
+use crate::message_log;
+use crate::factions;
+use crate::status;
 
 use rand::{Rng, thread_rng};
 
+/**
+ * Turns collisions with something hostile that has health into melee attacks
+ * Hits are rolled from attributes, weapons and armor in assets/combat.json
+ */
 #[derive(Debug)]
 pub struct AttackSystem;
 
 impl System for AttackSystem {
     fn process(&self, em: &mut EntityManager) {
-        // Get all entities with an attack component on them
-        // Get collision components
-        // Check if health component exists
-        // Add damage component
+        let rules = combat::rules();
+        let mut rng = thread_rng();
+
         let entities = em.get_entities_with_components(components::Event::get_component_type());
 
         for entity in entities {
-            if let Some(event) = get_component!(em, entity, components::Event) {
-                if let components::Event::Collision(collider) = event {
-                    if em.has_component(*collider, components::Health::get_component_type()) {
-                        let mut rng = thread_rng();
-
-                        let damage_amount = rng.gen_range(1, 4);
-                        // let damage_entity = {
-                        //     let entity = em.create_entity();
-                        //     entity
-                        // };
-                        // em.add_component(damage_entity, components::Damage { amount: damage_amount, target: *collider });
-
-                        em.add_component(*collider, components::Damage { amount: damage_amount, target: *collider });
+            let target = match get_component!(em, entity, components::Event) {
+                Some(components::Event::Collision(collider)) => *collider,
+                _ => continue
+            };
+
+            if !em.has_component(target, components::Health::get_component_type()) {
+                continue;
+            }
+
+            // Bumping into friends and bystanders is the SwapSystem's business
+            if !factions::is_hostile(em, entity, target) {
+                continue;
+            }
+
+            let attacker = Combatant::from_entity(em, entity, rules);
+            let defender = Combatant::from_entity(em, target, rules);
+
+            let attacker_name = combat::describe(em, entity);
+            let target_name = combat::describe(em, target);
+
+            match combat::resolve_attack(rules, &attacker, &defender, &mut rng) {
+                AttackOutcome::Fumble => {
+                    let verb = combat::conjugate(em, entity, "stumble");
+                    message_log::player(em, combat::sentence(format!("{} {} wildly at {}.", attacker_name, verb, target_name)));
+                }
+                AttackOutcome::Miss => {
+                    let verb = combat::conjugate(em, entity, "miss");
+                    message_log::player(em, combat::sentence(format!("{} {} {}.", attacker_name, verb, target_name)));
+                }
+                AttackOutcome::Hit { damage, critical } => {
+                    if critical {
+                        let verb = combat::conjugate(em, entity, "land");
+                        message_log::player(em, combat::sentence(format!("{} {} a critical hit on {}!", attacker_name, verb, target_name)));
+                    }
+
+                    combat::inflict(em, components::Damage {
+                        amount: damage,
+                        target: target,
+                        kind: components::DamageType::Physical,
+                        source: Some(entity),
+                        weapon: Some(attacker.weapon.name.clone())
+                    });
+
+                    if let Some(on_hit) = attacker.weapon.on_hit.as_ref() {
+                        if rng.gen_range(0, 100) < on_hit.chance {
+                            status::apply_status(em, target, components::StatusEffect {
+                                kind: on_hit.status,
+                                turns: on_hit.turns,
+                                magnitude: on_hit.magnitude,
+                                source: Some(entity)
+                            });
+                        }
                     }
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_only_attack_hostile_things_with_health() {
+        let mut em = EntityManager::new();
+
+        let player = em.create_entity();
+        em.add_component(player, components::Player);
+        em.add_component(player, components::Log::new());
+
+        let wall = em.create_entity();
+
+        let friend = em.create_entity();
+        em.add_component(player, components::Faction { name: "player".to_string() });
+        em.add_component(friend, components::Faction { name: "townsfolk".to_string() });
+        em.add_component(friend, components::Health { health: 8, max_health: 8 });
+
+        em.add_component(player, components::Event::Collision(friend));
+
+        for _ in 0..20 {
+            AttackSystem.process(&mut em);
+        }
+
+        assert!(get_component!(em, friend, components::DamageQueue).is_none());
+
+        let goblin = em.create_entity();
+        em.add_component(goblin, components::Health { health: 8, max_health: 8 });
+
+        em.add_component(player, components::Event::Collision(wall));
+
+        AttackSystem.process(&mut em);
+        assert!(get_component!(em, wall, components::DamageQueue).is_none());
+
+        // Keep swinging until something connects
+        em.add_component(player, components::Event::Collision(goblin));
+
+        for _ in 0..100 {
+            AttackSystem.process(&mut em);
+
+            if let Some(damage) = get_component!(em, goblin, components::DamageQueue).and_then(|queue| queue.instances.first()) {
+                assert!(damage.amount >= 1);
+                assert_eq!(damage.source, Some(player));
+                assert_eq!(damage.weapon.as_ref().unwrap(), "fists");
+                return;
+            }
+        }
+
+        panic!("Never hit the goblin");
+    }
+}
@@ -0,0 +1,150 @@
+use super::{System};
+use crate::entities::{Entity, EntityManager};
+use crate::components::{Component, self};
+use crate::map::{Map, DijkstraMap};
+use crate::map::dijkstra::unexplored_goals;
+use crate::message_log;
+use crate::interrupt;
+use crate::targeting::{self, KEY_ESCAPE};
+
+use std::cell::RefCell;
+
+/**
+ * Player auto explore
+ * 'x' starts exploring and it carries on turn after turn until a movement key or escape,
+ * a hostile coming into view or getting hurt stops it
+ */
+#[derive(Debug)]
+pub struct AutoExploreSystem {
+    explore_map: RefCell<DijkstraMap>
+}
+
+impl AutoExploreSystem {
+    pub fn new() -> Self {
+        Self {
+            explore_map: RefCell::new(DijkstraMap::new(0, 0))
+        }
+    }
+
+    fn handle_input(&self, em: &mut EntityManager) {
+        let input_entities = em.get_entities_with_components(components::Input::get_component_type());
+
+        for entity in input_entities {
+            let input = get_component!(em, entity, components::Input).unwrap().input;
+
+            match input {
+                120 => {                    // x
+                    em.add_component(entity, components::AutoExplore);
+                }
+                KEY_ESCAPE => {
+                    em.remove_component(entity, components::AutoExplore::get_component_type());
+                }
+                _ if targeting::nudge(input).is_some() => {
+                    em.remove_component(entity, components::AutoExplore::get_component_type());
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl System for AutoExploreSystem {
+    fn process(&self, em: &mut EntityManager) {
+        self.handle_input(em);
+
+        for entity in em.get_entities_with_components(components::AutoExplore::get_component_type()) {
+            if interrupt::hostile_in_view(em, entity) {
+                interrupt::stop(em, entity, "You stop exploring, there's something hostile in view.");
+            }
+        }
+
+        let explorers = em.get_entities_with_components(components::AutoExplore::get_component_type());
+
+        if explorers.is_empty() {
+            return;
+        }
+
+        let map_entity = match em.get_entity_by_name("Map") {
+            Some(entity) => entity,
+            None => return
+        };
+
+        let steps: Vec<(Entity, Option<(i32, i32)>)> = {
+            let map = get_component!(em, map_entity, Map).unwrap();
+            let mut explore_map = self.explore_map.borrow_mut();
+
+            explore_map.update(map, &unexplored_goals(map));
+
+            explorers.iter()
+                .filter_map(|entity| {
+                    let position = get_component!(em, *entity, components::Position)?;
+                    Some((*entity, explore_map.downhill(map, position.x, position.y)))
+                })
+                .collect()
+        };
+
+        for (entity, step) in steps {
+            match step {
+                Some((dx, dy)) => {
+                    if let Some(walk) = get_component!(mut, em, entity, components::Walk) {
+                        walk.dx = dx;
+                        walk.dy = dy;
+                    }
+                }
+                None => {
+                    em.remove_component(entity, components::AutoExplore::get_component_type());
+
+                    message_log::push(em, entity, "Explored everything within reach.".to_string());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+
+    fn press(system: &AutoExploreSystem, em: &mut EntityManager, entity: Entity, key: i32) {
+        get_component!(mut, em, entity, components::Input).unwrap().input = key;
+        system.process(em);
+    }
+
+    #[test]
+    fn it_should_keep_exploring_until_a_movement_key() {
+        let system = AutoExploreSystem::new();
+        let mut em = EntityManager::new();
+
+        let mut map = MapBuilder::new(20, 10).create_room(&Rect::new(0, 0, 19, 9)).build();
+        map.reveal(2, 2);
+
+        let map_entity = em.create_entity();
+        em.set_entity_name(map_entity, "Map");
+        em.add_component(map_entity, map);
+
+        let player = em.create_entity();
+        em.add_component(player, components::Input::new());
+        em.add_component(player, components::Log::new());
+        em.add_component(player, components::Position { x: 2, y: 2 });
+        em.add_component(player, components::Walk::new());
+
+        press(&system, &mut em, player, 120);
+
+        // Turns pass without a key, other keys don't get in the way
+        for key in &[0, 0, 101] {
+            get_component!(mut, em, player, components::Walk).unwrap().dx = 0;
+            get_component!(mut, em, player, components::Walk).unwrap().dy = 0;
+
+            press(&system, &mut em, player, *key);
+
+            let walk = get_component!(em, player, components::Walk).unwrap();
+            assert!(em.has_component(player, components::AutoExplore::get_component_type()));
+            assert_ne!((walk.dx, walk.dy), (0, 0));
+        }
+
+        press(&system, &mut em, player, 119);
+        assert!(!em.has_component(player, components::AutoExplore::get_component_type()));
+    }
+}
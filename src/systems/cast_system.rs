@@ -0,0 +1,74 @@
+use std::rc::Rc;
+
+use super::System;
+use super::turn_system::can_act;
+use crate::entities::{Entity, EntityManager};
+use crate::components::{Component, self};
+use crate::magic;
+use crate::scripts::{self, Scripts};
+use crate::message_log;
+
+/**
+ * Casts the spells picked with the spell system
+ * A cast costs an action, without one this turn it's dropped like a walk
+ */
+#[derive(Debug)]
+pub struct CastSystem {
+    scripts: Rc<Scripts>
+}
+
+impl CastSystem {
+    pub fn new(scripts: Rc<Scripts>) -> Self {
+        Self {
+            scripts: scripts
+        }
+    }
+
+    fn cast(&self, em: &mut EntityManager, entity: Entity, cast: &components::Cast) {
+        let def = match self.scripts.spell(&cast.spell) {
+            Some(def) => def,
+            None => return
+        };
+
+        if get_component!(em, entity, components::Mana).map_or(0, |mana| mana.mana) < def.cost {
+            return message_log::push(em, entity, format!("You don't have enough mana to cast {}.", def.name));
+        }
+
+        let reach = match magic::reach(em, entity, &def, (cast.x, cast.y)) {
+            Ok(reach) => reach,
+            Err(message) => return message_log::push(em, entity, message)
+        };
+
+        let effects = match self.scripts.cast(&def.name, entity, &reach) {
+            Ok(effects) => effects,
+            Err(error) => {
+                error!("{} failed: {}", def.name, error);
+                return message_log::push(em, entity, format!("Your {} fizzles.", def.name));
+            }
+        };
+
+        if let Some(mana) = get_component!(mut, em, entity, components::Mana) {
+            mana.mana -= def.cost;
+        }
+
+        if !reach.path.is_empty() {
+            em.add_component(entity, components::Projectile { path: reach.path.clone(), glyph: def.glyph });
+        }
+
+        scripts::apply(em, entity, &def.name, effects);
+    }
+}
+
+impl System for CastSystem {
+    fn process(&self, em: &mut EntityManager) {
+        for entity in em.get_entities_with_components(components::Cast::get_component_type()) {
+            let cast = get_component!(em, entity, components::Cast).cloned().unwrap();
+
+            em.remove_component(entity, components::Cast::get_component_type());
+
+            if can_act(em, entity) {
+                self.cast(em, entity, &cast);
+            }
+        }
+    }
+}
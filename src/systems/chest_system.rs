@@ -0,0 +1,94 @@
+use super::System;
+use crate::entities::{Entity, EntityManager};
+use crate::components::{Component, self};
+use crate::message_log;
+
+/**
+ * Bumping into a closed chest opens it and spills what's inside onto its tile
+ */
+#[derive(Debug)]
+pub struct ChestSystem;
+
+impl ChestSystem {
+    fn open(&self, em: &mut EntityManager, entity: Entity, chest: Entity) {
+        let position = match get_component!(em, chest, components::Position) {
+            Some(position) => *position,
+            None => return
+        };
+
+        get_component!(mut, em, chest, components::Chest).unwrap().open = true;
+
+        // Open chests are just scenery
+        em.remove_component(chest, components::Collidable::get_component_type());
+        em.add_component(chest, components::Render { glyph: '_', layer: components::RenderLayer::Map });
+
+        let items = em.remove_component(chest, components::Inventory::get_component_type())
+            .and_then(|inventory| inventory.as_any().downcast_ref::<components::Inventory>().map(|inventory| inventory.items().to_vec()))
+            .unwrap_or_default();
+
+        let empty = items.is_empty();
+
+        for item in items {
+            em.add_component(item, position);
+        }
+
+        let message = match empty {
+            true => "You open the chest. It's empty.",
+            false => "You open the chest."
+        };
+
+        message_log::push(em, entity, message.to_string());
+    }
+}
+
+impl System for ChestSystem {
+    fn process(&self, em: &mut EntityManager) {
+        let entities = em.get_entities_with_components(components::Event::get_component_type());
+
+        for entity in entities {
+            let collider = match get_component!(em, entity, components::Event) {
+                Some(components::Event::Collision(collider)) => *collider,
+                _ => continue
+            };
+
+            let closed = get_component!(em, collider, components::Chest).map_or(false, |chest| !chest.open);
+
+            if closed && em.has_component(entity, components::Player::get_component_type()) {
+                self.open(em, entity, collider);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn it_should_spill_chest_loot() {
+        let mut em = EntityManager::new();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let player = em.create_entity();
+        em.add_component(player, components::Player);
+        em.add_component(player, components::Log::new());
+
+        let chest = crate::props::create_chest(&mut em, 3, 3, "potions", &mut rng);
+
+        // The potions table always has a potion
+        let inside = get_component!(em, chest, components::Inventory).unwrap().items().to_vec();
+        assert!(!inside.is_empty());
+
+        em.add_component(player, components::Event::Collision(chest));
+        ChestSystem.process(&mut em);
+
+        assert!(get_component!(em, chest, components::Chest).unwrap().open);
+        assert!(!em.has_component(chest, components::Collidable::get_component_type()));
+
+        for item in inside {
+            assert_eq!(get_component!(em, item, components::Position), Some(&components::Position { x: 3, y: 3 }));
+        }
+    }
+}
@@ -1,19 +1,45 @@
 use crate::entities::*;
 use crate::systems::*;
 use crate::components::{self, Position, Collidable};
-
-use rand::{thread_rng, Rng};
+use crate::map::Map;
+use crate::spatial::SpatialIndex;
 
 #[derive(Debug)]
 pub struct CollisionSystem;
 
 impl CollisionSystem {
-    fn get_occupied_spaces(&self, em: &EntityManager) -> Vec<(Entity, (i32, i32))> {
-        em.get_entities_with_components(Collidable::get_component_type())
+    // Collidable entity standing at the destination, other than the walker
+    fn find_occupier(&self, em: &EntityManager, index: &SpatialIndex, entity: Entity, dest: &Position) -> Option<Entity> {
+        index.entities_at(dest.x, dest.y)
             .iter()
-            .filter_map(|entity| get_component!(em, *entity, Position).map(|position| (entity, position)))
-            .map(|(entity, position)| (*entity, (position.x, position.y)))
-            .collect()
+            .find(|other| **other != entity && em.has_component(**other, Collidable::get_component_type()))
+            .cloned()
+    }
+
+    fn is_terrain_blocked(&self, em: &EntityManager, map_entity: Option<Entity>, dest: &Position) -> bool {
+        map_entity
+            .and_then(|map_entity| get_component!(em, map_entity, Map))
+            .map_or(false, |map| map.is_blocked(dest.x, dest.y))
+    }
+
+    // Steps a fast walker gets to take past the first one, stopping short of anything in the way
+    fn extra_steps(&self, em: &EntityManager, index: &SpatialIndex, map_entity: Option<Entity>, entity: Entity, dest: &Position, walk: &components::Walk) -> i32 {
+        let actions = get_component!(em, entity, components::Turn).map_or(1, |turn| turn.actions);
+
+        let mut steps = 0;
+        let mut next = *dest;
+
+        while steps + 1 < actions {
+            next = Position { x: next.x + walk.dx, y: next.y + walk.dy };
+
+            if self.find_occupier(em, index, entity, &next).is_some() || self.is_terrain_blocked(em, map_entity, &next) {
+                break;
+            }
+
+            steps += 1;
+        }
+
+        steps
     }
 }
 
@@ -26,7 +52,21 @@ impl System for CollisionSystem {
         //  Flag the space is occupied
         let walk_entities = em.get_entities_with_components(components::Walk::get_component_type());
 
-        let occupied_spaces = self.get_occupied_spaces(em);
+        let map_entity = em.get_entity_by_name("Map");
+
+        // Fall back to a one off index when the spatial index system isn't running
+        let built_index;
+        let index = match em.get_entity_by_name("SpatialIndex").and_then(|entity| get_component!(em, entity, SpatialIndex)) {
+            Some(index) => index,
+            None => {
+                built_index = SpatialIndex::build(em);
+                &built_index
+            }
+        };
+
+        let mut collisions = Vec::new();
+        let mut blocked = Vec::new();
+        let mut hurried = Vec::new();
 
         for entity in walk_entities {
             let position = get_component!(em, entity, components::Position).unwrap().clone();
@@ -41,15 +81,41 @@ impl System for CollisionSystem {
                 y: position.y + walk.dy
             };
 
-            if let Some((occupier, _)) = occupied_spaces.iter().find(|(_, (x, y))| dest.x == *x && dest.y == *y) {
-                // debug!("Space ({}, {}) occupied", dest.x, dest.y);
-                let walk = get_component!(mut, em, entity, components::Walk).unwrap();
+            if let Some(occupier) = self.find_occupier(em, index, entity, &dest) {
+                collisions.push((entity, occupier));
+            } else if self.is_terrain_blocked(em, map_entity, &dest) {
+                // Walls aren't entities, just stop the walk
+                blocked.push(entity);
+            } else {
+                let steps = self.extra_steps(em, index, map_entity, entity, &dest, &walk);
+
+                if steps > 0 {
+                    hurried.push((entity, steps));
+                }
+            }
+        }
 
-                walk.dx = 0;
-                walk.dy = 0;
+        for (entity, steps) in hurried {
+            let walk = get_component!(mut, em, entity, components::Walk).unwrap();
 
-                em.add_component(entity, components::Event::Collision(*occupier));
-            }
+            walk.dx *= steps + 1;
+            walk.dy *= steps + 1;
+        }
+
+        for (entity, occupier) in collisions {
+            let walk = get_component!(mut, em, entity, components::Walk).unwrap();
+
+            walk.dx = 0;
+            walk.dy = 0;
+
+            em.add_component(entity, components::Event::Collision(occupier));
+        }
+
+        for entity in blocked {
+            let walk = get_component!(mut, em, entity, components::Walk).unwrap();
+
+            walk.dx = 0;
+            walk.dy = 0;
         }
     }
 }
@@ -90,4 +156,35 @@ impl System for CollisionSystem {
 
 //     assert_eq!(position.x, 0);
 //     assert_eq!(position.y, 0);
-// }
\ No newline at end of file
+// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+
+    #[test]
+    fn it_should_let_fast_walkers_go_further_when_clear() {
+        let mut em = EntityManager::new();
+
+        let map_entity = em.create_entity();
+        em.set_entity_name(map_entity, "Map");
+        em.add_component(map_entity, MapBuilder::new(10, 10).create_room(&Rect::new(0, 0, 9, 9)).build());
+
+        let hasted = em.create_entity();
+        em.add_component(hasted, Position { x: 2, y: 2 });
+        em.add_component(hasted, components::Walk { dx: 1, dy: 0 });
+        em.add_component(hasted, components::Turn { actions: 2 });
+
+        // Only one step of room before the wall
+        let cornered = em.create_entity();
+        em.add_component(cornered, Position { x: 7, y: 5 });
+        em.add_component(cornered, components::Walk { dx: 1, dy: 0 });
+        em.add_component(cornered, components::Turn { actions: 2 });
+
+        CollisionSystem.process(&mut em);
+
+        assert_eq!(get_component!(em, hasted, components::Walk).map(|walk| (walk.dx, walk.dy)), Some((2, 0)));
+        assert_eq!(get_component!(em, cornered, components::Walk).map(|walk| (walk.dx, walk.dy)), Some((1, 0)));
+    }
+}
@@ -1,37 +1,179 @@
 use super::{System};
 use crate::components::{Component, self};
 use crate::entities::*;
+use crate::combat;
+use crate::interrupt;
+use crate::message_log;
 
+/**
+ * Applies every hit queued this tick to health, after resistances, and reports who did it
+ * The source is remembered on the target for kill credit
+ */
 #[derive(Debug)]
 pub struct DamageSystem;
 
 impl System for DamageSystem {
     fn process(&self, em: &mut EntityManager) {
 
-        let damage_entities = em.get_entities_with_components(components::Damage::get_component_type());
+        let damage_entities = em.get_entities_with_components(components::DamageQueue::get_component_type());
 
         // Apply damage if they have a health component
         for entity in damage_entities.into_iter() {
-            let damage = get_component!(em, entity, components::Damage).unwrap().clone();
-            let name = get_component!(em, entity, components::Name).map(|c| c.name.clone()).unwrap_or(entity.to_string());
+            let queue = em.remove_component(entity, components::DamageQueue::get_component_type())
+                .and_then(|component| component.as_any().downcast_ref::<components::DamageQueue>().cloned())
+                .unwrap_or_default();
 
-            let mut damaged = None;
+            if !em.has_component(entity, components::Health::get_component_type()) {
+                continue;
+            }
 
-            if let Some(health) = get_component!(mut, em, entity, components::Health) {
-                health.health -= damage.amount;
+            let resistances = get_component!(em, entity, components::Resistances).cloned().unwrap_or_default();
 
-                em.remove_component(entity, components::Damage::get_component_type());
+            for mut damage in queue.instances {
+                if resistances.is_immune(damage.kind) {
+                    let message = combat::immune_message(em, &damage);
+                    report(em, &damage, message);
+                    continue;
+                }
 
-                damaged = Some(damage.amount);
-            }
+                damage.amount = resistances.apply(damage.kind, damage.amount);
+
+                if let Some(health) = get_component!(mut, em, entity, components::Health) {
+                    health.health -= damage.amount;
+                }
+
+                if let Some(source) = damage.source {
+                    em.add_component(entity, components::LastAttacker { entity: source });
+                }
+
+                let message = combat::damage_message(em, &damage);
+                report(em, &damage, message);
 
-            if let Some(damaged) = damaged {
-                let player = em.get_entities_with_components(components::Player::get_component_type())[0];
-                if let Some(log) = get_component!(mut, em, player, components::Log) {
-                    debug!("Damage System - Logging Damage");
-                    log.history.push(format!("{} took {} damage.", name, damaged));
+                if damage.amount > 0 {
+                    interrupt::stop(em, entity, "You stop, something hurt you.");
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// The player hears about their own hits, and about others' only when they see the target
+fn report(em: &mut EntityManager, damage: &components::Damage, message: String) {
+    match damage.source {
+        Some(source) if em.has_component(source, components::Player::get_component_type()) => message_log::player(em, message),
+        _ => message_log::seen(em, damage.target, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+
+    // The player and a goblin in plain view of each other
+    fn setup() -> (EntityManager, Entity, Entity) {
+        let mut em = EntityManager::new();
+
+        let map_entity = em.create_entity();
+        em.set_entity_name(map_entity, "Map");
+        em.add_component(map_entity, MapBuilder::new(20, 10).create_room(&Rect::new(0, 0, 10, 9)).build());
+
+        let player = em.create_entity();
+        em.add_component(player, components::Player);
+        em.add_component(player, components::Log::new());
+        em.add_component(player, components::Position { x: 2, y: 2 });
+
+        let goblin = em.create_entity();
+        em.add_component(goblin, components::Name { name: "goblin".to_string() });
+        em.add_component(goblin, components::Health { health: 20, max_health: 20 });
+        em.add_component(goblin, components::Position { x: 5, y: 5 });
+
+        (em, player, goblin)
+    }
+
+    fn hit(target: Entity, amount: i32, kind: components::DamageType, source: Option<Entity>) -> components::Damage {
+        components::Damage {
+            amount: amount,
+            target: target,
+            kind: kind,
+            source: source,
+            weapon: Some("dagger".to_string())
+        }
+    }
+
+    #[test]
+    fn it_should_credit_the_attacker() {
+        let (mut em, player, goblin) = setup();
+
+        combat::inflict(&mut em, hit(goblin, 5, components::DamageType::Physical, Some(player)));
+
+        DamageSystem.process(&mut em);
+
+        assert_eq!(get_component!(em, goblin, components::Health).unwrap().health, 15);
+        assert_eq!(get_component!(em, goblin, components::LastAttacker), Some(&components::LastAttacker { entity: player }));
+        assert_eq!(get_component!(em, player, components::Log).unwrap().history, vec!["You stab the goblin for 5."]);
+        assert!(get_component!(em, goblin, components::DamageQueue).is_none());
+    }
+
+    #[test]
+    fn it_should_stop_traveling_when_hurt() {
+        let (mut em, player, goblin) = setup();
+
+        em.add_component(player, components::Health { health: 10, max_health: 10 });
+        em.add_component(player, components::Travel { x: 5, y: 5 });
+
+        combat::inflict(&mut em, hit(player, 2, components::DamageType::Physical, Some(goblin)));
+
+        DamageSystem.process(&mut em);
+
+        assert!(!em.has_component(player, components::Travel::get_component_type()));
+        assert_eq!(get_component!(em, player, components::Log).unwrap().history.last().unwrap(), "You stop, something hurt you.");
+    }
+
+    #[test]
+    fn it_should_stack_hits_and_apply_resistances() {
+        let (mut em, player, goblin) = setup();
+
+        let rat = em.create_entity();
+
+        em.add_component(goblin, components::Resistances::new()
+            .with(components::DamageType::Fire, 50)
+            .with(components::DamageType::Poison, 100)
+            .with(components::DamageType::Cold, -50));
+
+        combat::inflict(&mut em, hit(goblin, 3, components::DamageType::Physical, Some(player)));
+        combat::inflict(&mut em, hit(goblin, 3, components::DamageType::Physical, Some(rat)));
+        combat::inflict(&mut em, hit(goblin, 4, components::DamageType::Fire, None));
+        combat::inflict(&mut em, hit(goblin, 9, components::DamageType::Poison, None));
+        combat::inflict(&mut em, hit(goblin, 2, components::DamageType::Cold, None));
+
+        DamageSystem.process(&mut em);
+
+        // 3 + 3 + 2 fire + 0 poison + 3 cold
+        assert_eq!(get_component!(em, goblin, components::Health).unwrap().health, 9);
+        assert_eq!(get_component!(em, goblin, components::LastAttacker), Some(&components::LastAttacker { entity: rat }));
+
+        let history = &get_component!(em, player, components::Log).unwrap().history;
+        assert_eq!(history.len(), 5);
+        assert_eq!(history[3], "The goblin is immune to poison.");
+    }
+
+    #[test]
+    fn it_should_not_report_hits_out_of_sight() {
+        let (mut em, player, goblin) = setup();
+
+        let rat = em.create_entity();
+
+        // Behind the room's wall
+        em.add_component(goblin, components::Position { x: 15, y: 5 });
+
+        combat::inflict(&mut em, hit(goblin, 3, components::DamageType::Physical, Some(rat)));
+        combat::inflict(&mut em, hit(goblin, 3, components::DamageType::Physical, Some(player)));
+
+        DamageSystem.process(&mut em);
+
+        assert_eq!(get_component!(em, goblin, components::Health).unwrap().health, 14);
+        assert_eq!(get_component!(em, player, components::Log).unwrap().history, vec!["You stab the goblin for 3."]);
+    }
+}
@@ -0,0 +1,111 @@
+use super::{System};
+use crate::entities::{Entity, EntityManager};
+use crate::components::{Component, self};
+use crate::map::{Map, DijkstraMap};
+use crate::map::dijkstra::player_goals;
+use crate::map::lighting::notice_chance;
+
+use std::cell::RefCell;
+
+use rand::{thread_rng, Rng};
+
+/**
+ * Chase and flee ai
+ * Both share the player's dijkstra map, which is only rebuilt
+ * when the player moves or the terrain changes
+ * Chasers only move while they can see the player, which is harder in the dark
+ */
+#[derive(Debug)]
+pub struct DijkstraAiSystem {
+    player_map: RefCell<DijkstraMap>,
+    flee_map: RefCell<DijkstraMap>
+}
+
+impl DijkstraAiSystem {
+    pub fn new() -> Self {
+        Self {
+            player_map: RefCell::new(DijkstraMap::new(0, 0)),
+            flee_map: RefCell::new(DijkstraMap::new(0, 0))
+        }
+    }
+
+    // Stealth check against the first player, lit players are easier to spot
+    fn notices_player(&self, em: &EntityManager, map: &Map, observer: Entity) -> bool {
+        let players = em.get_entities_with_components(components::Player::get_component_type());
+
+        let player = match players.first() {
+            Some(player) => *player,
+            None => return false
+        };
+
+        let (observer_position, player_position) = match (
+            get_component!(em, observer, components::Position),
+            get_component!(em, player, components::Position)
+        ) {
+            (Some(a), Some(b)) => (*a, *b),
+            _ => return false
+        };
+
+        let from = (observer_position.x, observer_position.y);
+        let to = (player_position.x, player_position.y);
+
+        if !map.has_line_of_sight(from, to) {
+            return false;
+        }
+
+        let distance = (to.0 - from.0).abs().max((to.1 - from.1).abs());
+        let stealth = get_component!(em, player, components::Stealth).map_or(0, |stealth| stealth.skill);
+
+        thread_rng().gen::<f32>() < notice_chance(map.light_level(to.0, to.1), distance, stealth)
+    }
+}
+
+impl System for DijkstraAiSystem {
+    fn process(&self, em: &mut EntityManager) {
+        let chasers = em.get_entities_with_components(components::ChaseAi::get_component_type());
+        let fleers = em.get_entities_with_components(components::FleeAi::get_component_type());
+
+        if chasers.is_empty() && fleers.is_empty() {
+            return;
+        }
+
+        let map_entity = match em.get_entity_by_name("Map") {
+            Some(entity) => entity,
+            None => return
+        };
+
+        let steps: Vec<(Entity, (i32, i32))> = {
+            let map = get_component!(em, map_entity, Map).unwrap();
+            let goals = player_goals(em);
+
+            let mut player_map = self.player_map.borrow_mut();
+            let mut flee_map = self.flee_map.borrow_mut();
+
+            if player_map.update(map, &goals) {
+                debug!("Rebuilding player dijkstra map");
+                *flee_map = player_map.flee(map);
+            }
+
+            let chase_steps = chasers.iter()
+                .filter(|entity| self.notices_player(em, map, **entity))
+                .map(|entity| (*entity, &*player_map));
+            let flee_steps = fleers.iter()
+                .map(|entity| (*entity, &*flee_map));
+
+            chase_steps.chain(flee_steps)
+                .filter_map(|(entity, dijkstra)| {
+                    let position = get_component!(em, entity, components::Position)?;
+                    dijkstra.downhill(map, position.x, position.y)
+                        .map(|step| (entity, step))
+                })
+                .collect()
+        };
+
+        for (entity, (dx, dy)) in steps {
+            if let Some(walk) = get_component!(mut, em, entity, components::Walk) {
+                walk.dx = dx;
+                walk.dy = dy;
+            }
+        }
+    }
+}
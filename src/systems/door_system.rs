@@ -0,0 +1,183 @@
+use super::{System};
+use super::turn_system::can_act;
+use crate::entities::{Entity, EntityManager};
+use crate::components::{Component, self};
+use crate::map::{Map, TileType};
+use crate::message_log;
+
+/**
+ * Opens doors that are bumped into and handles the open, close and lock actions
+ * 'o' opens, 'c' closes and 'l' locks or unlocks an adjacent door
+ */
+#[derive(Debug)]
+pub struct DoorSystem;
+
+impl DoorSystem {
+    fn adjacent_doors(&self, em: &EntityManager, entity: Entity) -> Vec<Entity> {
+        let position = match get_component!(em, entity, components::Position) {
+            Some(position) => *position,
+            None => return vec![]
+        };
+
+        em.get_entities_with_components(components::Door::get_component_type())
+            .into_iter()
+            .filter(|door| {
+                get_component!(em, *door, components::Position)
+                    .map_or(false, |door_position| {
+                        (door_position.x - position.x).abs() <= 1 && (door_position.y - position.y).abs() <= 1
+                    })
+            })
+            .collect()
+    }
+
+    fn find_key(&self, em: &EntityManager, entity: Entity, key_id: &Option<String>) -> Option<String> {
+        let key_id = key_id.as_ref()?;
+        let inventory = get_component!(em, entity, components::Inventory)?;
+
+        inventory.items().iter()
+            .find(|item| get_component!(em, **item, components::DoorKey).map_or(false, |key| &key.id == key_id))
+            .map(|item| {
+                get_component!(em, *item, components::Name)
+                    .map(|name| name.name.clone())
+                    .unwrap_or("key".to_string())
+            })
+    }
+
+    fn open(&self, em: &mut EntityManager, opener: Entity, door_entity: Entity) {
+        let door = get_component!(em, door_entity, components::Door).unwrap().clone();
+
+        if door.open {
+            return;
+        }
+
+        if door.locked {
+            match self.find_key(em, opener, &door.key) {
+                Some(key_name) => {
+                    get_component!(mut, em, door_entity, components::Door).unwrap().locked = false;
+                    self.report(em, opener, &format!("unlock the door with the {}", key_name), "unlocks a door");
+                }
+                None => {
+                    self.report(em, opener, "find the door is locked", "rattles a locked door");
+                    return;
+                }
+            }
+        }
+
+        self.set_open(em, door_entity, true);
+        self.report(em, opener, "open the door", "opens a door");
+    }
+
+    fn close(&self, em: &mut EntityManager, closer: Entity, door_entity: Entity) {
+        let door_position = *get_component!(em, door_entity, components::Position).unwrap();
+
+        let blocked = em.get_entities_with_components(components::Collidable::get_component_type())
+            .iter()
+            .filter(|entity| **entity != door_entity)
+            .any(|entity| get_component!(em, *entity, components::Position) == Some(&door_position));
+
+        if blocked {
+            self.report(em, closer, "can't close the door, something is in the way", "fails to close a door");
+            return;
+        }
+
+        self.set_open(em, door_entity, false);
+        self.report(em, closer, "close the door", "closes a door");
+    }
+
+    fn toggle_lock(&self, em: &mut EntityManager, entity: Entity, door_entity: Entity) {
+        let door = get_component!(em, door_entity, components::Door).unwrap().clone();
+
+        if door.open {
+            return;
+        }
+
+        match self.find_key(em, entity, &door.key) {
+            Some(key_name) => {
+                get_component!(mut, em, door_entity, components::Door).unwrap().locked = !door.locked;
+
+                let action = if door.locked { "unlock" } else { "lock" };
+                self.report(em, entity, &format!("{} the door with the {}", action, key_name), &format!("{}s a door", action));
+            }
+            None => {
+                self.report(em, entity, "have no key for this door", "fumbles with a lock");
+            }
+        }
+    }
+
+    fn set_open(&self, em: &mut EntityManager, door_entity: Entity, open: bool) {
+        let tile = if open { TileType::DoorOpen } else { TileType::DoorClosed };
+
+        get_component!(mut, em, door_entity, components::Door).unwrap().open = open;
+
+        if let Some(render) = get_component!(mut, em, door_entity, components::Render) {
+            render.glyph = tile.def().glyph;
+        }
+
+        if open {
+            em.remove_component(door_entity, components::Collidable::get_component_type());
+        } else {
+            em.add_component(door_entity, components::Collidable);
+        }
+
+        let position = *get_component!(em, door_entity, components::Position).unwrap();
+
+        if let Some(map_entity) = em.get_entity_by_name("Map") {
+            if let Some(map) = get_component!(mut, em, map_entity, Map) {
+                map.set_tile(position.x, position.y, tile);
+            }
+        }
+    }
+
+    // Log as "You ..." for the player and "The goblin ..." for anyone the player can see
+    fn report(&self, em: &mut EntityManager, actor: Entity, player_action: &str, other_action: &str) {
+        let message = if em.has_component(actor, components::Player::get_component_type()) {
+            format!("You {}.", player_action)
+        } else {
+            let name = get_component!(em, actor, components::Name).map(|c| c.name.clone()).unwrap_or(actor.to_string());
+            format!("The {} {}.", name, other_action)
+        };
+
+        message_log::seen(em, actor, message);
+    }
+}
+
+impl System for DoorSystem {
+    fn process(&self, em: &mut EntityManager) {
+        // Bumping into a closed door opens it
+        let entities_with_events = em.get_entities_with_components(components::Event::get_component_type());
+
+        for entity in entities_with_events {
+            let event = *get_component!(em, entity, components::Event).unwrap();
+
+            if let components::Event::Collision(collider) = event {
+                if em.has_component(collider, components::Door::get_component_type()) {
+                    self.open(em, entity, collider);
+                }
+            }
+        }
+
+        let input_entities = em.get_entities_with_components(components::Input::get_component_type());
+
+        for entity in input_entities {
+            let input = get_component!(em, entity, components::Input).unwrap().input;
+
+            let doors = match input {
+                111 | 99 | 108 if can_act(em, entity) => self.adjacent_doors(em, entity),  // o, c, l
+                _ => continue
+            };
+
+            for door in doors {
+                let open = get_component!(em, door, components::Door).unwrap().open;
+
+                match input {
+                    111 if !open => self.open(em, entity, door),
+                    99 if open => self.close(em, entity, door),
+                    108 if !open => self.toggle_lock(em, entity, door),
+                    _ => continue
+                }
+
+                break;
+            }
+        }
+    }
+}
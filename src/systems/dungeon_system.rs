@@ -0,0 +1,73 @@
+use super::{System};
+use super::turn_system::can_act;
+use crate::entities::{Entity, EntityManager};
+use crate::components::{Component, self};
+use crate::dungeon::change_level;
+use crate::map::{Map, TileType};
+use crate::message_log;
+
+/**
+ * Stairs
+ * '>' descends and '<' ascends while standing on stairs,
+ * anywhere else they travel to the nearest stairs seen so far
+ */
+#[derive(Debug)]
+pub struct DungeonSystem;
+
+impl DungeonSystem {
+    fn use_stairs(&self, em: &mut EntityManager, entity: Entity, down: bool) {
+        let map_entity = match em.get_entity_by_name("Map") {
+            Some(entity) => entity,
+            None => return
+        };
+
+        let position = *get_component!(em, entity, components::Position).unwrap();
+        let stairs = if down { TileType::StairsDown } else { TileType::StairsUp };
+
+        let (on_stairs, nearest_stairs) = {
+            let map = get_component!(em, map_entity, Map).unwrap();
+
+            let nearest = map.find_tiles(stairs)
+                .into_iter()
+                .filter(|(x, y)| map.is_revealed(*x, *y))
+                .min_by_key(|(x, y)| (x - position.x).abs().max((y - position.y).abs()));
+
+            (map.get_tile(position.x, position.y) == stairs, nearest)
+        };
+
+        let message = if on_stairs {
+            match change_level(em, entity, down) {
+                Some(depth) if down => format!("You descend to depth {}.", depth + 1),
+                Some(depth) => format!("You climb up to depth {}.", depth + 1),
+                None => "The stairs lead nowhere.".to_string()
+            }
+        } else if let Some((x, y)) = nearest_stairs {
+            em.add_component(entity, components::Travel { x: x, y: y });
+            "You head for the stairs.".to_string()
+        } else {
+            "You haven't found any stairs.".to_string()
+        };
+
+        message_log::push(em, entity, message);
+    }
+}
+
+impl System for DungeonSystem {
+    fn process(&self, em: &mut EntityManager) {
+        let input_entities = em.get_entities_with_components(components::Input::get_component_type());
+
+        for entity in input_entities {
+            if !can_act(em, entity) {
+                continue;
+            }
+
+            let input = get_component!(em, entity, components::Input).unwrap().input;
+
+            match input {
+                62 => self.use_stairs(em, entity, true),    // >
+                60 => self.use_stairs(em, entity, false),   // <
+                _ => {}
+            }
+        }
+    }
+}
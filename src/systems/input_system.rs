@@ -3,8 +3,13 @@ use ncurses as nc;
 use super::{System};
 use crate::entities::*;
 use crate::components::{Component, Input};
+use crate::interrupt;
 
 use std::cell::RefCell;
+use std::time::Duration;
+
+// How long a turn waits for a key while the player walks on their own
+const BUSY_TICK: Duration = Duration::from_millis(50);
 
 #[derive(Debug)]
 pub struct InputSystem {
@@ -38,8 +43,12 @@ impl InputSystem {
         self.event_sender.clone()
     }
 
-    pub fn get_input(&self) -> Option<i32> {
-       self.event_receiver.recv().ok()
+    /// Waits for a key, unless the player is busy walking and only gets a moment to interrupt
+    pub fn get_input(&self, busy: bool) -> Option<i32> {
+        match busy {
+            true => self.event_receiver.recv_timeout(BUSY_TICK).ok(),
+            false => self.event_receiver.recv().ok()
+        }
     }
 
     fn process_input_events(&self, entity_manager: &mut EntityManager) {
@@ -55,8 +64,10 @@ impl InputSystem {
         debug!("Found {} entities with input", input_entities.len());
         // let input_key = nc::getch();
 
+        let busy = input_entities.iter().any(|entity| interrupt::busy(entity_manager, *entity));
+
         // If an input event is received, notify all input components
-        if let Some(input_key) = self.get_input() {
+        if let Some(input_key) = self.get_input(busy) {
         // if input_key != 0 {
             debug!("Received input {}", input_key);
             self.history.borrow_mut().push(input_key);
@@ -0,0 +1,66 @@
+use super::System;
+use crate::entities::EntityManager;
+use crate::components::{Component, self};
+use crate::progression::{self, LevelUpChoice};
+use crate::menu;
+
+/**
+ * Spends pending level ups, the number keys pick from the level up screen
+ * The screen holds every key and the world waits until each level is spent
+ */
+#[derive(Debug)]
+pub struct LevelUpSystem;
+
+impl System for LevelUpSystem {
+    fn process(&self, em: &mut EntityManager) {
+        let input_entities = em.get_entities_with_components(components::Input::get_component_type());
+
+        for entity in input_entities {
+            let unspent = get_component!(em, entity, components::Experience).map_or(0, |experience| experience.unspent);
+
+            if unspent == 0 {
+                continue;
+            }
+
+            let input = get_component!(em, entity, components::Input).unwrap().input;
+
+            if let Some(choice) = LevelUpChoice::from_key(input) {
+                progression::choose(em, entity, choice);
+            }
+
+            menu::consume(em, entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_pick_with_number_keys() {
+        let mut em = EntityManager::new();
+
+        let player = em.create_entity();
+        em.add_component(player, components::Input::new());
+        em.add_component(player, components::Experience { level: 2, xp: 20, unspent: 1 });
+        em.add_component(player, components::Attributes::default());
+
+        // Walking is held off until a choice is made
+        get_component!(mut, em, player, components::Input).unwrap().input = 119;
+        LevelUpSystem.process(&mut em);
+        assert_eq!(get_component!(em, player, components::Input).unwrap().input, 0);
+        assert!(menu::world_waits(&mut em));
+
+        get_component!(mut, em, player, components::Input).unwrap().input = '2' as i32;
+
+        LevelUpSystem.process(&mut em);
+        assert!(menu::world_waits(&mut em));
+
+        LevelUpSystem.process(&mut em);
+        assert!(!menu::world_waits(&mut em));
+
+        assert_eq!(get_component!(em, player, components::Experience).unwrap().unspent, 0);
+        assert_eq!(get_component!(em, player, components::Attributes).unwrap().dexterity, 12);
+    }
+}
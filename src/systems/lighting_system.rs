@@ -0,0 +1,39 @@
+use super::{System};
+use crate::entities::{EntityManager};
+use crate::components::{Component, self};
+use crate::map::Map;
+
+/// Light everywhere, without it unlit corners would be pitch black
+const AMBIENT_LIGHT: f32 = 0.1;
+
+/**
+ * Recomputes the light on the map from every light source
+ */
+#[derive(Debug)]
+pub struct LightingSystem;
+
+impl System for LightingSystem {
+    fn process(&self, em: &mut EntityManager) {
+        let map_entity = match em.get_entity_by_name("Map") {
+            Some(entity) => entity,
+            None => return
+        };
+
+        let lights: Vec<(components::Position, components::LightSource)> = em.get_entities_with_components(components::LightSource::get_component_type())
+            .iter()
+            .filter_map(|entity| {
+                let position = get_component!(em, *entity, components::Position)?;
+                let light = get_component!(em, *entity, components::LightSource)?;
+                Some((*position, *light))
+            })
+            .collect();
+
+        let map = get_component!(mut, em, map_entity, Map).unwrap();
+
+        map.clear_light(AMBIENT_LIGHT);
+
+        for (position, light) in lights {
+            map.cast_light((position.x, position.y), light.radius, light.color);
+        }
+    }
+}
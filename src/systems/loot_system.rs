@@ -2,37 +2,140 @@ use super::{System};
 
 use crate::entities::EntityManager;
 use crate::components::{Component, self};
+use crate::items;
+use crate::loot::loot_tables;
+use crate::dungeon::Dungeon;
 
-/// Generate loot entity for all dead entities
+use rand::thread_rng;
+
+/**
+ * Drop the inventory of dying entities where they fall
+ * Monsters with a loot table roll on it and on the table for the depth they died at
+ */
 #[derive(Debug)]
 pub struct LootSystem;
 
 impl System for LootSystem {
     fn process(&self, em: &mut EntityManager) {
+        let mut rng = thread_rng();
+
         let health_entities = em.get_entities_with_components(components::Health::get_component_type());
 
         for entity in health_entities {
             let health = get_component!(em, entity, components::Health).unwrap();
-            let position = get_component!(em, entity, components::Position);
 
-            if position.is_none() {
+            if health.health > 0 {
                 continue;
             }
 
-            let position = position.unwrap().clone();
+            let position = match get_component!(em, entity, components::Position) {
+                Some(position) => *position,
+                None => continue
+            };
+
+            let mut items = match get_component!(mut, em, entity, components::Inventory) {
+                Some(inventory) => {
+                    let items = inventory.items().to_vec();
+
+                    for item in items.iter() {
+                        inventory.remove_item(*item);
+                    }
 
-            if health.health <= 0 {
-                let loot = em.create_entity();
+                    items
+                }
+                None => Vec::new()
+            };
 
-                // TODO
-                // Spawn entity item template
+            if let Some(table) = em.remove_component(entity, components::Loot::get_component_type()) {
+                let table = table.as_any().downcast_ref::<components::Loot>().unwrap().table.clone();
 
-                em.add_component(loot, position);
-                em.add_component(loot, components::Render { glyph: '!', layer: components::RenderLayer::Item });
-                em.add_component(loot, components::Name { name: "Potion of Health".to_string() });
-                em.add_component(loot, components::Item);
-                em.add_component(loot, components::Consumable);
+                let depth_table = em.get_entity_by_name("Dungeon")
+                    .and_then(|dungeon| get_component!(em, dungeon, Dungeon))
+                    .and_then(|dungeon| loot_tables().for_depth(dungeon.depth()));
+
+                let mut drops = loot_tables().roll(&table, &mut rng);
+
+                if let Some(table) = depth_table {
+                    drops.extend(loot_tables().roll(table, &mut rng));
+                }
+
+                for drop in drops {
+                    items.extend(items::create_item(em, &drop.item, drop.count));
+                }
+            }
+
+            for item in items {
+                em.add_component(item, position);
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_drop_inventory_of_the_dying() {
+        let mut em = EntityManager::new();
+
+        let goblin = em.create_entity();
+        em.add_component(goblin, components::Health { health: 0, max_health: 8 });
+        em.add_component(goblin, components::Position { x: 4, y: 2 });
+        em.add_component(goblin, components::Inventory::new());
+
+        let key = crate::items::create_key(&mut em, "iron key", "iron");
+        get_component!(mut, em, goblin, components::Inventory).unwrap().add_item(key);
+
+        LootSystem.process(&mut em);
+
+        assert_eq!(get_component!(em, key, components::Position), Some(&components::Position { x: 4, y: 2 }));
+        assert!(get_component!(em, goblin, components::Inventory).unwrap().items().is_empty());
+    }
+
+    #[test]
+    fn it_should_roll_the_loot_table_once() {
+        let mut em = EntityManager::new();
+
+        let goblin = em.create_entity();
+        em.add_component(goblin, components::Health { health: 0, max_health: 8 });
+        em.add_component(goblin, components::Position { x: 4, y: 2 });
+        em.add_component(goblin, components::Loot { table: "potions".to_string() });
+
+        LootSystem.process(&mut em);
+        LootSystem.process(&mut em);
+
+        let items = em.get_entities_with_components(components::Item::get_component_type());
+        assert_eq!(items.len(), 1);
+        assert_eq!(get_component!(em, items[0], components::Name).unwrap().name, "potion of healing");
+        assert_eq!(get_component!(em, items[0], components::Position), Some(&components::Position { x: 4, y: 2 }));
+    }
+
+    #[test]
+    fn it_should_roll_the_table_for_the_depth_on_monster_deaths() {
+        let mut em = EntityManager::new();
+
+        let dungeon = em.create_entity();
+        em.set_entity_name(dungeon, "Dungeon");
+        em.add_component(dungeon, Dungeon::new(20, 20, 5, None));
+
+        // Potions always drop exactly one, anything past that came from the depth
+        for x in 0..30 {
+            let goblin = em.create_entity();
+            em.add_component(goblin, components::Health { health: 0, max_health: 8 });
+            em.add_component(goblin, components::Position { x: x, y: 2 });
+            em.add_component(goblin, components::Loot { table: "potions".to_string() });
+        }
+
+        // Without a loot table there's no depth roll either, like for summons
+        let summon = em.create_entity();
+        em.add_component(summon, components::Health { health: 0, max_health: 8 });
+        em.add_component(summon, components::Position { x: 0, y: 5 });
+
+        LootSystem.process(&mut em);
+
+        let items = em.get_entities_with_components(components::Item::get_component_type());
+        assert!(items.len() > 30);
+        assert!(items.iter().all(|item| get_component!(em, *item, components::Position).unwrap().y == 2));
+    }
+}
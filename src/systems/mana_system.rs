@@ -0,0 +1,47 @@
+use super::System;
+use crate::entities::EntityManager;
+use crate::components::{Component, self};
+
+/**
+ * Mana comes back a little every turn the world moves on
+ * Regen is in tenths of a point, progress keeps the remainder between turns
+ */
+#[derive(Debug)]
+pub struct ManaSystem;
+
+impl System for ManaSystem {
+    fn process(&self, em: &mut EntityManager) {
+        for entity in em.get_entities_with_components(components::Mana::get_component_type()) {
+            let mana = get_component!(mut, em, entity, components::Mana).unwrap();
+
+            if mana.mana >= mana.max_mana {
+                mana.progress = 0;
+                continue;
+            }
+
+            mana.progress += mana.regen;
+            mana.mana = (mana.mana + mana.progress / 10).min(mana.max_mana);
+            mana.progress %= 10;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_regenerate_mana() {
+        let mut em = EntityManager::new();
+
+        let entity = em.create_entity();
+        em.add_component(entity, components::Mana { mana: 0, max_mana: 2, regen: 4, progress: 0 });
+
+        for _ in 0..5 {
+            ManaSystem.process(&mut em);
+        }
+
+        assert_eq!(get_component!(em, entity, components::Mana).unwrap().mana, 2);
+        assert_eq!(get_component!(em, entity, components::Mana).unwrap().progress, 0);
+    }
+}
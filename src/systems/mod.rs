@@ -61,6 +61,33 @@ pub use self::move_system::MoveSystem;
 mod ai_system;
 pub use self::ai_system::AiSystem;
 
+mod chest_system;
+pub use self::chest_system::ChestSystem;
+
+mod spell_system;
+pub use self::spell_system::SpellSystem;
+
+mod cast_system;
+pub use self::cast_system::CastSystem;
+
+mod mana_system;
+pub use self::mana_system::ManaSystem;
+
+mod ranged_system;
+pub use self::ranged_system::RangedSystem;
+
+mod shoot_system;
+pub use self::shoot_system::ShootSystem;
+
+mod level_up_system;
+pub use self::level_up_system::LevelUpSystem;
+
+mod status_system;
+pub use self::status_system::StatusSystem;
+
+mod swap_system;
+pub use self::swap_system::SwapSystem;
+
 mod attack_system;
 pub use self::attack_system::AttackSystem;
 
@@ -84,3 +111,33 @@ pub use self::event_log_system::EventLogSystem;
 
 mod turn_system;
 pub use self::turn_system::TurnSystem;
+
+mod travel_system;
+pub use self::travel_system::TravelSystem;
+
+mod travel_cursor_system;
+pub use self::travel_cursor_system::TravelCursorSystem;
+
+mod dijkstra_ai_system;
+pub use self::dijkstra_ai_system::DijkstraAiSystem;
+
+mod auto_explore_system;
+pub use self::auto_explore_system::AutoExploreSystem;
+
+mod visibility_system;
+pub use self::visibility_system::VisibilitySystem;
+
+mod terrain_system;
+pub use self::terrain_system::TerrainSystem;
+
+mod door_system;
+pub use self::door_system::DoorSystem;
+
+mod dungeon_system;
+pub use self::dungeon_system::DungeonSystem;
+
+mod spatial_index_system;
+pub use self::spatial_index_system::SpatialIndexSystem;
+
+mod lighting_system;
+pub use self::lighting_system::LightingSystem;
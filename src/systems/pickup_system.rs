@@ -1,65 +1,101 @@
 use super::System;
-use crate::entities::EntityManager;
+use crate::entities::{Entity, EntityManager};
 use crate::components::{Component, self};
+use crate::spatial::SpatialIndex;
+use crate::message_log;
 
+/**
+ * Picks up the items under an entity when 'e' is pressed
+ */
 #[derive(Debug)]
 pub struct PickupSystem;
 
+impl PickupSystem {
+    fn items_at(&self, em: &EntityManager, position: &components::Position) -> Vec<Entity> {
+        let item_type = components::Item::get_component_type();
+
+        match em.get_entity_by_name("SpatialIndex").and_then(|entity| get_component!(em, entity, SpatialIndex)) {
+            Some(index) => index.entities_at_with(em, position.x, position.y, item_type),
+            None => SpatialIndex::build(em).entities_at_with(em, position.x, position.y, item_type)
+        }
+    }
+
+    fn pick_up(&self, em: &mut EntityManager, entity: Entity, item: Entity) {
+        match get_component!(mut, em, entity, components::Inventory) {
+            Some(inventory) => inventory.add_item(item),
+            None => return
+        }
+
+        em.remove_component(item, components::Position::get_component_type());
+
+        // Keep the index current for anything else that looks this tick
+        if let Some(index_entity) = em.get_entity_by_name("SpatialIndex") {
+            if let Some(index) = get_component!(mut, em, index_entity, SpatialIndex) {
+                index.remove(item);
+            }
+        }
+
+        if !em.has_component(entity, components::Player::get_component_type()) {
+            return;
+        }
+
+        let name = get_component!(em, item, components::Name).map(|c| c.name.clone()).unwrap_or("item".to_string());
+
+        message_log::push(em, entity, format!("You pick up the {}.", name));
+    }
+}
+
 impl System for PickupSystem {
     fn process(&self, em: &mut EntityManager) {
-        // Get all entities that have a pickup component
-        let pickup_entities = em.get_entities_with_components(components::Pickup::get_component_type());
+        let input_entities = em.get_entities_with_components(components::Input::get_component_type());
 
-        let ownable_entities = em.get_entities_with_components(components::Ownable::get_component_type());
-        let ownable_positions = ownable_entities.iter().map(|entity| get_component!(em, *entity, components::Position));
+        for entity in input_entities {
+            // e
+            if get_component!(em, entity, components::Input).unwrap().input != 101 {
+                continue;
+            }
 
-        for entity in pickup_entities {
-            let pickup_position = get_component!(em, entity, components::Position);
+            let position = match get_component!(em, entity, components::Position) {
+                Some(position) => *position,
+                None => continue
+            };
 
-            // if let Some((item, item_position)) = ownable_entities.iter().find(|(item, item_position)| item_position == pickup_position) {
-            // }
+            for item in self.items_at(em, &position) {
+                self.pick_up(em, entity, item);
+            }
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_pick_up_items_underfoot() {
+        let mut em = EntityManager::new();
+
+        let player = em.create_entity();
+        em.add_component(player, components::Player);
+        em.add_component(player, components::Position { x: 2, y: 2 });
+        em.add_component(player, components::Input { input: 101 });
+        em.add_component(player, components::Inventory::new());
+        em.add_component(player, components::Log { history: Vec::new() });
+
+        let item = em.create_entity();
+        em.add_component(item, components::Item);
+        em.add_component(item, components::Name { name: "potion".to_string() });
+        em.add_component(item, components::Position { x: 2, y: 2 });
+
+        let elsewhere = em.create_entity();
+        em.add_component(elsewhere, components::Item);
+        em.add_component(elsewhere, components::Position { x: 3, y: 2 });
+
+        PickupSystem.process(&mut em);
 
-        // Check if entity has same position as another entity that can be owned
-
-        // Add the entity to the pickup entity's inventory
-
-        // Preprocess
-        // check if input was to pickup
-        // let input_entities = em.get_entities_with_components(components::Input::get_component_type());
-
-        // for entity in input_entities {
-        //     let input = get_component!(em, entity, components::Input).unwrap();
-
-        //     match input.input {
-        //         101 => {
-        //             let position = get_component!(em, entity, components::Position).unwrap();
-        //             // E, Pickup action
-        //             // check if there is an item at the input entitiy's position
-        //             // If there is then add a Pickup component to the item
-        //             let item_entities = em.get_entities_with_components(components::Item::get_component_type());
-
-        //             let mut target = None;
-
-        //             for item_entity in item_entities {
-        //                 if let Some(item_position) = get_component!(em, entity, components::Position) {
-        //                     if item_position == position {
-        //                         target = Some(item_entity);
-        //                         // remove position component from entity
-        //                     }
-        //                 }
-        //             }
-
-        //             // Add the item's template to the entity's inventory
-        //             if let Some(item) = target {
-        //                 let inventory = get_component!(mut, em, entity, components::Inventory).unwrap();
-        //                 inventory.add_item(item);
-
-        //                 em.remove_component(item, components::Position::get_component_type());
-        //             }
-        //         }
-        //         _ => {}
-        //     }
-        // }
+        assert_eq!(get_component!(em, player, components::Inventory).unwrap().items(), &[item]);
+        assert!(!em.has_component(item, components::Position::get_component_type()));
+        assert!(em.has_component(elsewhere, components::Position::get_component_type()));
+        assert_eq!(get_component!(em, player, components::Log).unwrap().history.last().unwrap(), "You pick up the potion.");
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,308 @@
+use super::System;
+use crate::entities::{Entity, EntityManager};
+use crate::components::{Component, self, TargetMode};
+use crate::combat::{self, RangedDef};
+use crate::menu;
+use crate::message_log;
+use crate::targeting::{self, Steer};
+
+const KEY_FIRE: i32 = 102;  // f
+const KEY_READY: i32 = 114;  // r
+
+// Ranged weapons in the inventory, in the order they were picked up
+fn carried(em: &EntityManager, entity: Entity) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+
+    let items = get_component!(em, entity, components::Inventory).map_or(&[][..], |inventory| inventory.items());
+
+    for item in items {
+        if let Some(weapon) = get_component!(em, *item, components::RangedWeapon) {
+            if !names.contains(&weapon.name) {
+                names.push(weapon.name.clone());
+            }
+        }
+    }
+
+    names
+}
+
+/// The weapon at the ready, as long as it's still carried
+pub fn ranged_def(em: &EntityManager, entity: Entity) -> Option<&'static RangedDef> {
+    let weapon = get_component!(em, entity, components::RangedWeapon)?;
+
+    if !carried(em, entity).contains(&weapon.name) {
+        return None;
+    }
+
+    combat::rules().ranged(&weapon.name)
+}
+
+/// Stack in the inventory with shots left of the kind the weapon uses
+pub fn find_ammo(em: &EntityManager, entity: Entity, kind: &str) -> Option<Entity> {
+    get_component!(em, entity, components::Inventory)?
+        .items()
+        .iter()
+        .find(|item| get_component!(em, **item, components::Ammo).map_or(false, |ammo| ammo.kind == kind && ammo.count > 0))
+        .cloned()
+}
+
+/**
+ * Aiming with the ranged weapon at the ready
+ * 'r' readies the next bow, darts or wand in the inventory,
+ * 'f' starts aiming at the closest enemy in view, tab cycles enemies,
+ * the movement keys nudge the cursor and 'f' or enter lets fly,
+ * the shoot system takes the shot once the turn hands out actions
+ */
+#[derive(Debug)]
+pub struct RangedSystem;
+
+impl RangedSystem {
+    /// Switch to the next ranged weapon in the inventory, true when there was one
+    fn ready(&self, em: &mut EntityManager, entity: Entity) -> bool {
+        let carried = carried(em, entity);
+
+        let current = get_component!(em, entity, components::RangedWeapon)
+            .and_then(|weapon| carried.iter().position(|name| *name == weapon.name));
+
+        let next = match current {
+            Some(index) => carried[(index + 1) % carried.len()].clone(),
+            None => match carried.first() {
+                Some(name) => name.clone(),
+                None => {
+                    message_log::push(em, entity, "You have no ranged weapons.".to_string());
+                    return false;
+                }
+            }
+        };
+
+        message_log::push(em, entity, format!("You ready the {}.", next));
+        em.add_component(entity, components::RangedWeapon { name: next });
+
+        true
+    }
+
+    fn start(&self, em: &mut EntityManager, entity: Entity) {
+        let def = match ranged_def(em, entity) {
+            Some(def) => def,
+            None => return message_log::push(em, entity, "You have nothing to shoot with.".to_string())
+        };
+
+        if find_ammo(em, entity, &def.ammo).is_none() {
+            return message_log::push(em, entity, format!("You have no {}s left.", def.ammo));
+        }
+
+        targeting::start(em, entity, TargetMode::Shoot, def.range, true);
+    }
+}
+
+impl System for RangedSystem {
+    fn process(&self, em: &mut EntityManager) {
+        let input_entities = em.get_entities_with_components(components::Input::get_component_type());
+
+        for entity in input_entities {
+            let input = get_component!(em, entity, components::Input).unwrap().input;
+
+            match get_component!(em, entity, components::Targeting).map(|cursor| cursor.mode) {
+                Some(TargetMode::Shoot) => {}
+                // Some other cursor or menu has the keys
+                Some(_) => continue,
+                None => {
+                    if menu::in_menu(em, entity) {
+                        continue;
+                    }
+
+                    match input {
+                        KEY_FIRE => {
+                            self.start(em, entity);
+                            menu::consume(em, entity);
+                        }
+                        // Readying takes the turn, only failing to is free
+                        KEY_READY => {
+                            if !self.ready(em, entity) {
+                                menu::consume(em, entity);
+                            }
+                        }
+                        _ => {}
+                    }
+
+                    continue;
+                }
+            }
+
+            // Letting fly takes the turn
+            match targeting::steer(em, entity, input, KEY_FIRE) {
+                Steer::Confirm(x, y) => em.add_component(entity, components::Shot { x: x, y: y }),
+                Steer::Aiming | Steer::Cancel => menu::consume(em, entity)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+    use crate::systems::ShootSystem;
+
+    // A key and, unless a menu used it up, the turn it starts, true when the world waited
+    fn press(em: &mut EntityManager, entity: Entity, key: i32) -> bool {
+        get_component!(mut, em, entity, components::Input).unwrap().input = key;
+        RangedSystem.process(em);
+
+        let waits = menu::world_waits(em);
+
+        if !waits {
+            ShootSystem.process(em);
+        }
+
+        waits
+    }
+
+    #[test]
+    fn it_should_aim_cycle_and_shoot() {
+        let mut em = EntityManager::new();
+
+        let map_entity = em.create_entity();
+        em.set_entity_name(map_entity, "Map");
+        em.add_component(map_entity, MapBuilder::new(20, 20).create_room(&Rect::new(0, 0, 19, 19)).build());
+
+        let player = em.create_entity();
+        em.add_component(player, components::Player);
+        em.add_component(player, components::Input::new());
+        em.add_component(player, components::Log::new());
+        em.add_component(player, components::Faction { name: "player".to_string() });
+        em.add_component(player, components::Position { x: 2, y: 2 });
+        em.add_component(player, components::RangedWeapon { name: "short bow".to_string() });
+        em.add_component(player, components::Inventory::new());
+
+        let bow = crate::items::create_ranged_weapon(&mut em, "short bow", ')', None);
+        let arrows = crate::items::create_ammo(&mut em, "arrows", "arrow", 1);
+        get_component!(mut, em, player, components::Inventory).unwrap().add_item(bow);
+        get_component!(mut, em, player, components::Inventory).unwrap().add_item(arrows);
+
+        let goblin = |em: &mut EntityManager, x: i32| {
+            let goblin = em.create_entity();
+            em.add_component(goblin, components::Faction { name: "goblins".to_string() });
+            em.add_component(goblin, components::Health { health: 8, max_health: 8 });
+            em.add_component(goblin, components::Collidable);
+            em.add_component(goblin, components::Position { x: x, y: 2 });
+            goblin
+        };
+
+        goblin(&mut em, 5);
+        goblin(&mut em, 7);
+        // Out of range
+        goblin(&mut em, 15);
+
+        press(&mut em, player, KEY_FIRE);
+        assert_eq!(get_component!(em, player, components::Targeting).map(|cursor| (cursor.x, cursor.y)), Some((5, 2)));
+
+        press(&mut em, player, targeting::KEY_TAB);
+        press(&mut em, player, targeting::KEY_TAB);
+        assert_eq!(get_component!(em, player, components::Targeting).map(|cursor| (cursor.x, cursor.y)), Some((5, 2)));
+
+        press(&mut em, player, KEY_FIRE);
+
+        // The only arrow is gone and flew as far as the first goblin
+        assert!(get_component!(em, player, components::Targeting).is_none());
+        assert_eq!(get_component!(em, player, components::Inventory).unwrap().items(), &[bow]);
+        assert_eq!(get_component!(em, player, components::Projectile).unwrap().path, vec![(3, 2), (4, 2), (5, 2)]);
+
+        press(&mut em, player, KEY_FIRE);
+        assert!(get_component!(em, player, components::Targeting).is_none());
+        assert_eq!(get_component!(em, player, components::Log).unwrap().history.last().unwrap(), "You have no arrows left.");
+    }
+
+    #[test]
+    fn it_should_ready_darts_and_wands_from_the_inventory() {
+        let mut em = EntityManager::new();
+
+        let map_entity = em.create_entity();
+        em.set_entity_name(map_entity, "Map");
+        em.add_component(map_entity, MapBuilder::new(20, 20).create_room(&Rect::new(0, 0, 19, 19)).build());
+
+        let player = em.create_entity();
+        em.add_component(player, components::Input::new());
+        em.add_component(player, components::Log::new());
+        em.add_component(player, components::Faction { name: "player".to_string() });
+        em.add_component(player, components::Position { x: 2, y: 2 });
+        em.add_component(player, components::Inventory::new());
+
+        let goblin = em.create_entity();
+        em.add_component(goblin, components::Faction { name: "goblins".to_string() });
+        em.add_component(goblin, components::Health { health: 100, max_health: 100 });
+        em.add_component(goblin, components::Collidable);
+        em.add_component(goblin, components::Position { x: 4, y: 2 });
+
+        press(&mut em, player, KEY_FIRE);
+        assert_eq!(get_component!(em, player, components::Log).unwrap().history.last().unwrap(), "You have nothing to shoot with.");
+
+        assert!(press(&mut em, player, KEY_READY));
+        assert_eq!(get_component!(em, player, components::Log).unwrap().history.last().unwrap(), "You have no ranged weapons.");
+
+        let darts = crate::items::create_ranged_weapon(&mut em, "darts", '-', Some(components::Ammo { kind: "dart".to_string(), count: 2 }));
+        let wand = crate::items::create_ranged_weapon(&mut em, "wand of fire", '/', Some(components::Ammo { kind: "fire charge".to_string(), count: 1 }));
+        get_component!(mut, em, player, components::Inventory).unwrap().add_item(darts);
+        get_component!(mut, em, player, components::Inventory).unwrap().add_item(wand);
+
+        // Readying takes the turn
+        assert!(!press(&mut em, player, KEY_READY));
+        assert_eq!(get_component!(em, player, components::RangedWeapon).unwrap().name, "darts");
+
+        press(&mut em, player, KEY_FIRE);
+        press(&mut em, player, KEY_FIRE);
+        assert_eq!(get_component!(em, darts, components::Ammo).unwrap().count, 1);
+
+        press(&mut em, player, KEY_READY);
+        assert_eq!(get_component!(em, player, components::RangedWeapon).unwrap().name, "wand of fire");
+        assert_eq!(get_component!(em, player, components::Log).unwrap().history.last().unwrap(), "You ready the wand of fire.");
+
+        // The last charge goes with the wand
+        press(&mut em, player, KEY_FIRE);
+        press(&mut em, player, KEY_FIRE);
+        assert_eq!(get_component!(em, player, components::Projectile).unwrap().glyph, '*');
+        assert_eq!(get_component!(em, player, components::Inventory).unwrap().items(), &[darts]);
+
+        press(&mut em, player, KEY_FIRE);
+        assert_eq!(get_component!(em, player, components::Log).unwrap().history.last().unwrap(), "You have nothing to shoot with.");
+
+        press(&mut em, player, KEY_READY);
+        assert_eq!(get_component!(em, player, components::RangedWeapon).unwrap().name, "darts");
+    }
+
+    #[test]
+    fn it_should_not_shoot_without_an_action_this_turn() {
+        let mut em = EntityManager::new();
+
+        let map_entity = em.create_entity();
+        em.set_entity_name(map_entity, "Map");
+        em.add_component(map_entity, MapBuilder::new(20, 20).create_room(&Rect::new(0, 0, 19, 19)).build());
+
+        let player = em.create_entity();
+        em.add_component(player, components::Input::new());
+        em.add_component(player, components::Log::new());
+        em.add_component(player, components::Faction { name: "player".to_string() });
+        em.add_component(player, components::Position { x: 2, y: 2 });
+        em.add_component(player, components::Inventory::new());
+        em.add_component(player, components::RangedWeapon { name: "darts".to_string() });
+        // Slowed, this turn goes by without an action
+        em.add_component(player, components::Turn { actions: 0 });
+
+        let darts = crate::items::create_ranged_weapon(&mut em, "darts", '-', Some(components::Ammo { kind: "dart".to_string(), count: 2 }));
+        get_component!(mut, em, player, components::Inventory).unwrap().add_item(darts);
+
+        let goblin = em.create_entity();
+        em.add_component(goblin, components::Faction { name: "goblins".to_string() });
+        em.add_component(goblin, components::Health { health: 100, max_health: 100 });
+        em.add_component(goblin, components::Collidable);
+        em.add_component(goblin, components::Position { x: 4, y: 2 });
+
+        press(&mut em, player, KEY_FIRE);
+        assert!(!press(&mut em, player, KEY_FIRE));
+
+        assert_eq!(get_component!(em, darts, components::Ammo).unwrap().count, 2);
+        assert!(get_component!(em, player, components::Projectile).is_none());
+        assert!(get_component!(em, player, components::Shot).is_none());
+    }
+}
@@ -1,9 +1,60 @@
+use std::rc::Rc;
+
 use super::{System};
-use crate::entities::EntityManager;
+use crate::entities::{Entity, EntityManager};
 use crate::components::{Component, self};
+use crate::combat;
+use crate::progression;
+use crate::scripts::{self, Scripts};
+use crate::message_log;
 
+/**
+ * Takes the dead off the board
+ * Credits the killer, leaves a corpse, publishes a death event and runs
+ * the on_death hooks of the lua scripts, then keeps the player around,
+ * marked dead, for the game over screen
+ */
 #[derive(Debug)]
-pub struct Reaper;
+pub struct Reaper {
+    scripts: Rc<Scripts>
+}
+
+impl Reaper {
+    pub fn new(scripts: Rc<Scripts>) -> Self {
+        Self {
+            scripts: scripts
+        }
+    }
+
+    fn run_hooks(&self, em: &mut EntityManager, entity: Entity, killer: Option<Entity>) {
+        let name = get_component!(em, entity, components::Name).map(|name| name.name.clone());
+        let position = get_component!(em, entity, components::Position).map(|position| (position.x, position.y));
+
+        match self.scripts.death(entity, name.as_deref(), position, killer) {
+            Ok(effects) => scripts::apply(em, entity, "death", effects),
+            Err(error) => error!("on_death hook failed: {}", error)
+        }
+    }
+
+    fn leave_corpse(&self, em: &mut EntityManager, entity: Entity) {
+        let position = match get_component!(em, entity, components::Position) {
+            Some(position) => *position,
+            None => return
+        };
+
+        let name = match get_component!(em, entity, components::Name) {
+            Some(name) => name.name.clone(),
+            None => return
+        };
+
+        let corpse = em.create_entity();
+
+        em.add_component(corpse, components::Name { name: format!("{} corpse", name) });
+        em.add_component(corpse, components::Render { glyph: '%', layer: components::RenderLayer::Item });
+        em.add_component(corpse, position);
+        em.add_component(corpse, components::Corpse { of: name });
+    }
+}
 
 impl System for Reaper {
     fn process(&self, em: &mut EntityManager) {
@@ -12,18 +63,155 @@ impl System for Reaper {
         for entity in health_entities.into_iter() {
             let health = get_component!(em, entity, components::Health).unwrap();
 
-            if health.health <= 0 {
-                if let Some(name) = get_component!(em, entity, components::Name) {
-                    let message = format!("{} has died", &name.name);
-                    info!("{}", message);
-                    let player = em.get_entities_with_components(components::Player::get_component_type())[0];
-                    if let Some(log) = get_component!(mut, em, player, components::Log) {
-                        log.history.push(message);
-                    }
+            if health.health > 0 || em.has_component(entity, components::Dead::get_component_type()) {
+                continue;
+            }
+
+            let killer = get_component!(em, entity, components::LastAttacker)
+                .map(|attacker| attacker.entity)
+                .filter(|attacker| *attacker != entity);
+
+            if let Some(name) = get_component!(em, entity, components::Name).map(|name| name.name.clone()) {
+                let message = match killer {
+                    Some(killer) => combat::sentence(format!("The {} was killed by {}.", name, combat::describe(em, killer))),
+                    None => combat::sentence(format!("The {} has died.", name))
+                };
+
+                info!("{}", message);
+
+                // The player hears about their own kills, and about other deaths only when they see them
+                match killer {
+                    // "You die..." says it for the player
+                    _ if em.has_component(entity, components::Player::get_component_type()) => {}
+                    Some(killer) if em.has_component(killer, components::Player::get_component_type()) => message_log::player(em, message),
+                    _ => message_log::seen(em, entity, message)
                 }
+            }
+
+            let reward = get_component!(em, entity, components::XpReward).map(|reward| reward.amount);
+
+            if let (Some(killer), Some(reward)) = (killer, reward) {
+                progression::award_xp(em, killer, reward);
+            }
+
+            let event = serde_json::json!({
+                "event": "death",
+                "entity": entity.id,
+                "name": get_component!(em, entity, components::Name).map(|name| name.name.clone()),
+                "killer": killer.map(|killer| killer.id)
+            });
+            em.publish(&event.to_string());
+
+            self.run_hooks(em, entity, killer);
+
+            if em.has_component(entity, components::Player::get_component_type()) {
+                // Stay in place as a corpse, the game loop ends things from here
+                em.add_component(entity, components::Dead);
+                em.add_component(entity, components::Render { glyph: '%', layer: components::RenderLayer::Player });
+                em.remove_component(entity, components::Walk::get_component_type());
 
-                em.kill_entity(entity);
+                message_log::player(em, "You die...".to_string());
+                continue;
             }
+
+            self.leave_corpse(em, entity);
+
+            em.kill_entity(entity);
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+
+    fn reaper() -> Reaper {
+        Reaper::new(Rc::new(Scripts::new()))
+    }
+
+    fn setup() -> (EntityManager, Entity, Entity) {
+        let mut em = EntityManager::new();
+
+        let player = em.create_entity();
+        em.add_component(player, components::Player);
+        em.add_component(player, components::Log::new());
+        em.add_component(player, components::Health { health: 10, max_health: 10 });
+        em.add_component(player, components::Position { x: 1, y: 1 });
+
+        let zombie = em.create_entity();
+        em.add_component(zombie, components::Name { name: "zombie".to_string() });
+        em.add_component(zombie, components::Health { health: 0, max_health: 10 });
+        em.add_component(zombie, components::Position { x: 3, y: 4 });
+        em.add_component(zombie, components::LastAttacker { entity: player });
+
+        (em, player, zombie)
+    }
+
+    #[test]
+    fn it_should_leave_a_corpse_and_publish_death() {
+        let (mut em, _, zombie) = setup();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        em.subscribe(sender);
+
+        reaper().process(&mut em);
+
+        assert!(!em.has_component(zombie, components::Health::get_component_type()));
+
+        let corpses = em.get_entities_with_components(components::Corpse::get_component_type());
+        assert_eq!(corpses.len(), 1);
+        assert_eq!(get_component!(em, corpses[0], components::Name).unwrap().name, "zombie corpse");
+        assert_eq!(get_component!(em, corpses[0], components::Position), Some(&components::Position { x: 3, y: 4 }));
+
+        let event: serde_json::Value = serde_json::from_str(&receiver.try_recv().unwrap()).unwrap();
+        assert_eq!(event["event"], "death");
+        assert_eq!(event["name"], "zombie");
+    }
+
+    #[test]
+    fn it_should_run_the_on_death_hooks() {
+        let (mut em, player, zombie) = setup();
+
+        // Hook messages are about the dead, the player has to see it happen
+        let map_entity = em.create_entity();
+        em.set_entity_name(map_entity, "Map");
+        em.add_component(map_entity, MapBuilder::new(10, 10).create_room(&Rect::new(0, 0, 9, 9)).build());
+
+        let scripts = Scripts::new();
+
+        scripts.load(r#"
+            on_death(function(death)
+                if death.name == "zombie" then
+                    message("The " .. death.name .. " bursts at " .. death.x .. "," .. death.y .. "!")
+                    damage(death.killer, 2, "Poison")
+                end
+            end)
+        "#).unwrap();
+
+        Reaper::new(Rc::new(scripts)).process(&mut em);
+
+        let history = &get_component!(em, player, components::Log).unwrap().history;
+        assert_eq!(history, &vec!["The zombie was killed by you.".to_string(), "The zombie bursts at 3,4!".to_string()]);
+
+        let damage = &get_component!(em, player, components::DamageQueue).unwrap().instances[0];
+        assert_eq!((damage.amount, damage.source), (2, Some(zombie)));
+    }
+
+    #[test]
+    fn it_should_keep_the_dead_player_around() {
+        let (mut em, player, _) = setup();
+
+        get_component!(mut, em, player, components::Health).unwrap().health = -3;
+
+        reaper().process(&mut em);
+        reaper().process(&mut em);
+
+        assert!(em.has_component(player, components::Dead::get_component_type()));
+        assert_eq!(em.get_entities_with_components(components::Player::get_component_type()), vec![player]);
+
+        let history = &get_component!(em, player, components::Log).unwrap().history;
+        assert_eq!(history.iter().filter(|line| *line == "You die...").count(), 1);
+    }
+}
@@ -6,6 +6,66 @@ use ncurses as nc;
 use crate::entities::*;
 use super::{System};
 use crate::components::{Component, self, Position};
+use crate::dungeon::Dungeon;
+use crate::map::Map;
+use crate::status;
+use crate::progression::{self, LevelUpChoice};
+use crate::magic::{Grimoire, SpellShape};
+
+// Basic curses colors, color pair n draws color n on black
+const PALETTE: [(i16, [u8; 3]); 7] = [
+    (nc::COLOR_RED, [255, 0, 0]),
+    (nc::COLOR_GREEN, [0, 255, 0]),
+    (nc::COLOR_YELLOW, [255, 255, 0]),
+    (nc::COLOR_BLUE, [0, 0, 255]),
+    (nc::COLOR_MAGENTA, [255, 0, 255]),
+    (nc::COLOR_CYAN, [0, 255, 255]),
+    (nc::COLOR_WHITE, [255, 255, 255]),
+];
+
+fn init_color_pairs() {
+    for (color, _) in PALETTE.iter() {
+        nc::init_pair(*color, *color, nc::COLOR_BLACK);
+    }
+}
+
+/// Closest curses color pair to an rgb color
+fn color_pair(color: [u8; 3]) -> i16 {
+    let distance = |other: &[u8; 3]| -> i32 {
+        (0..3).map(|i| (color[i] as i32 - other[i] as i32).pow(2)).sum()
+    };
+
+    PALETTE.iter()
+        .min_by_key(|(_, rgb)| distance(rgb))
+        .map(|(color, _)| *color)
+        .unwrap_or(nc::COLOR_WHITE)
+}
+
+// Light below this draws dim, above bright
+const DIM_LIGHT: f32 = 0.3;
+const BRIGHT_LIGHT: f32 = 0.8;
+
+fn brightness(light: f32) -> nc::attr_t {
+    if light < DIM_LIGHT {
+        nc::A_DIM()
+    } else if light > BRIGHT_LIGHT {
+        nc::A_BOLD()
+    } else {
+        nc::A_NORMAL()
+    }
+}
+
+/// Tile color tinted by colored light, dimmed or brightened by how much light there is
+fn shade(color: [u8; 3], light: f32, light_color: [u8; 3]) -> nc::attr_t {
+    let tinted = if light >= DIM_LIGHT && light_color != [255, 255, 255] {
+        let mix = |i: usize| ((color[i] as u16 + light_color[i] as u16) / 2) as u8;
+        [mix(0), mix(1), mix(2)]
+    } else {
+        color
+    };
+
+    nc::COLOR_PAIR(color_pair(tinted)) | brightness(light)
+}
 
 #[derive(Debug)]
 pub struct CursesRenderer {
@@ -54,6 +114,8 @@ fn init_ncurses() {
     // Allow colors
     nc::start_color();
 
+    init_color_pairs();
+
     // colors::init();
 
     // Line buffering disabled
@@ -111,7 +173,7 @@ impl RenderSystem {
         let player_info_window_x = 0;
         let player_info_window_y = 0;
         let player_info_window_width = 20;
-        let player_info_window_height = 10;
+        let player_info_window_height = 11;
 
         let player_info_window = nc::newwin(
             player_info_window_height, 
@@ -164,6 +226,9 @@ impl RenderSystem {
     fn render_player_info(&self, entity_manager: &EntityManager) {
         let window = self.player_info_window.unwrap();
 
+        // Effects come and go, don't leave old lines behind
+        nc::werase(window);
+
         // Player name
         let player = entity_manager.get_entities_with_components(components::Player::get_component_type())[0];
 
@@ -180,6 +245,27 @@ impl RenderSystem {
         let speed = get_component!(entity_manager, player, components::Speed).unwrap();
         nc::mvwaddstr(window, 4, 1, &format!("Speed: {}", speed.amount));
 
+        if let Some(dungeon_entity) = entity_manager.get_entity_by_name("Dungeon") {
+            let dungeon = get_component!(entity_manager, dungeon_entity, Dungeon).unwrap();
+            nc::mvwaddstr(window, 5, 1, &format!("Depth: {}", dungeon.depth() + 1));
+        }
+
+        if let Some(experience) = get_component!(entity_manager, player, components::Experience) {
+            let next = progression::xp_for_level(experience.level + 1);
+            nc::mvwaddstr(window, 6, 1, &format!("Lvl {} XP {}/{}", experience.level, experience.xp, next));
+        }
+
+        if let Some(mana) = get_component!(entity_manager, player, components::Mana) {
+            nc::mvwaddstr(window, 7, 1, &format!("MP: {}/{}", mana.mana, mana.max_mana));
+        }
+
+        // Whatever fits below, the box takes the last row
+        if let Some(status) = get_component!(entity_manager, player, components::StatusEffects) {
+            for (row, line) in (8..10).zip(status::describe(status)) {
+                nc::mvwaddstr(window, row, 1, &line);
+            }
+        }
+
         // let (gte, _) = entity_manager.get_entity_by_name("GameTime")
             // .expect("No gametime found");
         // let gt = get_component!(entity_manager, gte, components::GameTime).cloned().unwrap();
@@ -191,6 +277,128 @@ impl RenderSystem {
         nc::wrefresh(window);
     }
 
+    /// Death screen over the map once the player is dead
+    fn render_game_over(&self, entity_manager: &EntityManager) {
+        let player = entity_manager.get_entities_with_components(components::Player::get_component_type())[0];
+
+        if !entity_manager.has_component(player, components::Dead::get_component_type()) {
+            return;
+        }
+
+        let map_window = self.map_window.unwrap();
+
+        let mut map_width = 0;
+        let mut map_height = 0;
+        nc::getmaxyx(map_window, &mut map_height, &mut map_width);
+
+        let mut map_x = 0;
+        let mut map_y = 0;
+        nc::getbegyx(map_window, &mut map_y, &mut map_x);
+
+        let width = 36;
+        let height = 5;
+
+        let window = nc::newwin(height, width, map_y + (map_height - height) / 2, map_x + (map_width - width) / 2);
+
+        let name = get_component!(entity_manager, player, components::Name).map_or("You", |name| name.name.as_str());
+
+        nc::mvwaddstr(window, 1, 2, &format!("{} has died.", name));
+        nc::mvwaddstr(window, 3, 2, "Press q to quit.");
+
+        nc::box_(window, 0, 0);
+        nc::wrefresh(window);
+        nc::delwin(window);
+    }
+
+    /// Choices over the map while the player has a level up to spend
+    fn render_level_up(&self, entity_manager: &EntityManager) {
+        let player = entity_manager.get_entities_with_components(components::Player::get_component_type())[0];
+
+        let experience = match get_component!(entity_manager, player, components::Experience) {
+            Some(experience) if experience.unspent > 0 => experience,
+            _ => return
+        };
+
+        let map_window = self.map_window.unwrap();
+
+        let mut map_width = 0;
+        let mut map_height = 0;
+        nc::getmaxyx(map_window, &mut map_height, &mut map_width);
+
+        let width = 40;
+        let height = LevelUpChoice::ALL.len() as i32 + 4;
+
+        let mut map_x = 0;
+        let mut map_y = 0;
+        nc::getbegyx(map_window, &mut map_y, &mut map_x);
+
+        let window = nc::newwin(height, width, map_y + (map_height - height) / 2, map_x + (map_width - width) / 2);
+
+        nc::mvwaddstr(window, 1, 2, &format!("Level {}! Choose one:", experience.level - experience.unspent + 1));
+
+        for (index, choice) in LevelUpChoice::ALL.iter().enumerate() {
+            nc::mvwaddstr(window, index as i32 + 2, 2, &format!("{}) {}", index + 1, choice.description()));
+        }
+
+        nc::box_(window, 0, 0);
+        nc::wrefresh(window);
+        nc::delwin(window);
+    }
+
+    fn spell<'a>(&self, entity_manager: &'a EntityManager, name: &str) -> Option<&'a crate::magic::SpellDef> {
+        entity_manager.get_entity_by_name("Grimoire")
+            .and_then(|grimoire| get_component!(entity_manager, grimoire, Grimoire))
+            .and_then(|grimoire| grimoire.get(name))
+    }
+
+    /// Known spells over the map while the spellbook is open
+    fn render_spellbook(&self, entity_manager: &EntityManager) {
+        let player = entity_manager.get_entities_with_components(components::Player::get_component_type())[0];
+
+        match get_component!(entity_manager, player, components::Casting) {
+            Some(components::Casting { spell: None }) => {}
+            _ => return
+        }
+
+        let spells = match get_component!(entity_manager, player, components::Spellbook) {
+            Some(book) => &book.spells,
+            None => return
+        };
+
+        let map_window = self.map_window.unwrap();
+
+        let mut map_width = 0;
+        let mut map_height = 0;
+        nc::getmaxyx(map_window, &mut map_height, &mut map_width);
+
+        let width = 48;
+        let height = spells.len() as i32 * 2 + 4;
+
+        let mut map_x = 0;
+        let mut map_y = 0;
+        nc::getbegyx(map_window, &mut map_y, &mut map_x);
+
+        let window = nc::newwin(height, width, map_y + (map_height - height) / 2, map_x + (map_width - width) / 2);
+
+        nc::mvwaddstr(window, 1, 2, "Cast which spell? (esc to close)");
+
+        for (index, name) in spells.iter().enumerate() {
+            let row = index as i32 * 2 + 2;
+
+            match self.spell(entity_manager, name) {
+                Some(spell) => {
+                    nc::mvwaddstr(window, row, 2, &format!("{}) {} ({} mp)", index + 1, spell.name, spell.cost));
+                    nc::mvwaddstr(window, row + 1, 5, &spell.description);
+                }
+                None => { nc::mvwaddstr(window, row, 2, &format!("{}) {}", index + 1, name)); }
+            }
+        }
+
+        nc::box_(window, 0, 0);
+        nc::wrefresh(window);
+        nc::delwin(window);
+    }
+
     fn render_log(&self, entity_manager: &EntityManager) {
         let window = self.log_window.unwrap();
 
@@ -213,19 +421,12 @@ impl RenderSystem {
     }
 
     fn render_map(&self, entity_manager: &EntityManager) {
-        use std::convert::TryInto;
-
-        let mut entities: Vec<_> = entity_manager.get_entities_with_components(components::Render::get_component_type())
-            .iter()
-            .filter_map(|entity| get_component!(entity_manager, *entity, components::Render).map(|render| (entity, render)))
-            .filter_map(|(entity, render)| get_component!(entity_manager, *entity, components::Position).map(|position| (*entity, render.clone(), position.clone())))
-            .collect();
-
-        entities.sort_by(|(_, render_a, _), (_, render_b, _)| render_a.layer.cmp(&render_b.layer));
-
         let camera_pos = self.get_camera_position(entity_manager);
         let map_window = self.map_window.unwrap();
 
+        // Terrain is no longer covered by tile entities, clear last frame
+        nc::werase(map_window);
+
         // TODO
         // Cache this
         let mut map_window_width = 0;
@@ -233,13 +434,59 @@ impl RenderSystem {
 
         nc::getmaxyx(map_window, &mut map_window_height, &mut map_window_width);
 
+        let map = entity_manager.get_entity_by_name("Map")
+            .and_then(|map_entity| get_component!(entity_manager, map_entity, Map));
+
+        if let Some(map) = map {
+            self.render_terrain(map, &camera_pos, map_window_width, map_window_height);
+        }
+
+        let mut entities: Vec<_> = entity_manager.get_entities_with_components(components::Render::get_component_type())
+            .iter()
+            .filter_map(|entity| get_component!(entity_manager, *entity, components::Render).map(|render| (entity, render)))
+            .filter_map(|(entity, render)| get_component!(entity_manager, *entity, components::Position).map(|position| (*entity, render.clone(), position.clone())))
+            // Only draw what the player has seen
+            .filter(|(_, _, position)| map.map_or(true, |map| map.is_revealed(position.x, position.y)))
+            .collect();
+
+        entities.sort_by(|(_, render_a, _), (_, render_b, _)| render_a.layer.cmp(&render_b.layer));
+
         for (_, render, position) in entities.iter() {
             let world_pos = self.get_world_position(&camera_pos, &position);
             if world_pos.x > 0 && world_pos.y > 0 && world_pos.x < map_window_width - 1 && world_pos.y < map_window_height - 1 {
-                if cfg!(macos) {
-                    nc::mvwaddch(map_window, world_pos.y, world_pos.x, (render.glyph as u32).try_into().unwrap());
-                } else {
-                    nc::mvwaddch(map_window, world_pos.y, world_pos.x, (render.glyph as u64).try_into().unwrap());
+                let shading = brightness(map.map_or(1.0, |map| map.light_level(position.x, position.y)));
+
+                nc::wattron(map_window, shading);
+                nc::mvwaddch(map_window, world_pos.y, world_pos.x, render.glyph as nc::chtype);
+                nc::wattroff(map_window, shading);
+            }
+        }
+
+        let player = entity_manager.get_entities_with_components(components::Player::get_component_type())[0];
+
+        if let Some(cursor) = get_component!(entity_manager, player, components::Targeting) {
+            let world_pos = self.get_world_position(&camera_pos, &Position { x: cursor.x, y: cursor.y });
+
+            if world_pos.x > 0 && world_pos.y > 0 && world_pos.x < map_window_width - 1 && world_pos.y < map_window_height - 1 {
+                nc::mvwchgat(map_window, world_pos.y, world_pos.x, 1, nc::A_REVERSE(), 0);
+            }
+
+            // Show what an area spell will catch around the cursor
+            let radius = get_component!(entity_manager, player, components::Casting)
+                .and_then(|casting| casting.spell.as_ref())
+                .and_then(|spell| self.spell(entity_manager, spell))
+                .filter(|spell| spell.shape == SpellShape::Area)
+                .map_or(0, |spell| spell.radius);
+
+            for y in (cursor.y - radius)..(cursor.y + radius + 1) {
+                for x in (cursor.x - radius)..(cursor.x + radius + 1) {
+                    let world_pos = self.get_world_position(&camera_pos, &Position { x: x, y: y });
+
+                    if (x, y) == (cursor.x, cursor.y) || world_pos.x <= 0 || world_pos.y <= 0 || world_pos.x >= map_window_width - 1 || world_pos.y >= map_window_height - 1 {
+                        continue;
+                    }
+
+                    nc::mvwchgat(map_window, world_pos.y, world_pos.x, 1, nc::A_BOLD(), 0);
                 }
             }
         }
@@ -249,6 +496,65 @@ impl RenderSystem {
         nc::wrefresh(map_window);
     }
 
+    /// Fly each shot's glyph along its path, then let the map redraw over it
+    fn animate_projectiles(&self, entity_manager: &mut EntityManager) {
+        const FRAME_MS: i32 = 30;
+
+        let map_window = self.map_window.unwrap();
+        let camera_pos = self.get_camera_position(entity_manager);
+
+        let mut map_window_width = 0;
+        let mut map_window_height = 0;
+        nc::getmaxyx(map_window, &mut map_window_height, &mut map_window_width);
+
+        for entity in entity_manager.get_entities_with_components(components::Projectile::get_component_type()) {
+            let projectile = get_component!(entity_manager, entity, components::Projectile).unwrap().clone();
+
+            for (x, y) in projectile.path {
+                let world_pos = self.get_world_position(&camera_pos, &Position { x: x, y: y });
+
+                if world_pos.x <= 0 || world_pos.y <= 0 || world_pos.x >= map_window_width - 1 || world_pos.y >= map_window_height - 1 {
+                    continue;
+                }
+
+                // Put back what was under the glyph once it moves on
+                let under = nc::mvwinch(map_window, world_pos.y, world_pos.x);
+
+                nc::mvwaddch(map_window, world_pos.y, world_pos.x, projectile.glyph as nc::chtype | nc::A_BOLD());
+                nc::wrefresh(map_window);
+                nc::napms(FRAME_MS);
+
+                nc::mvwaddch(map_window, world_pos.y, world_pos.x, under);
+            }
+
+            nc::wrefresh(map_window);
+
+            entity_manager.remove_component(entity, components::Projectile::get_component_type());
+        }
+    }
+
+    // Draw the revealed cells inside the camera view
+    fn render_terrain(&self, map: &Map, camera_pos: &Position, window_width: i32, window_height: i32) {
+        let map_window = self.map_window.unwrap();
+
+        for screen_y in 1..(window_height - 1) {
+            for screen_x in 1..(window_width - 1) {
+                let x = camera_pos.x + screen_x;
+                let y = camera_pos.y + screen_y;
+
+                let cell = match map.get(x, y) {
+                    Some(cell) if map.is_revealed(x, y) => cell,
+                    _ => continue
+                };
+                let color = shade(cell.tile.def().color, map.light_level(x, y), map.light_color(x, y));
+
+                nc::wattron(map_window, color);
+                nc::mvwaddch(map_window, screen_y, screen_x, cell.glyph as nc::chtype);
+                nc::wattroff(map_window, color);
+            }
+        }
+    }
+
     pub fn unmount(&self) {
         debug!("Unmounting render system");
         drop_ncurses();
@@ -286,6 +592,14 @@ impl System for RenderSystem {
 
         self.render_map(entity_manager);
 
+        self.animate_projectiles(entity_manager);
+
+        self.render_level_up(entity_manager);
+
+        self.render_spellbook(entity_manager);
+
+        self.render_game_over(entity_manager);
+
         self.render_player_info(entity_manager);
 
         self.render_log(entity_manager);
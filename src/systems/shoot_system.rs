@@ -0,0 +1,116 @@
+use super::System;
+use super::ranged_system::{ranged_def, find_ammo};
+use super::turn_system::can_act;
+use crate::entities::{Entity, EntityManager};
+use crate::components::{Component, self};
+use crate::combat::{self, AttackOutcome, Combatant};
+use crate::map::Map;
+use crate::map::los::{ProjectileHit, collidable_occupants};
+use crate::message_log;
+
+use rand::thread_rng;
+
+/**
+ * Takes the shots aimed with the ranged system
+ * A shot costs an action, without one this turn it's dropped like a walk
+ */
+#[derive(Debug)]
+pub struct ShootSystem;
+
+impl ShootSystem {
+    fn fire(&self, em: &mut EntityManager, entity: Entity, cursor: (i32, i32)) {
+        let def = match ranged_def(em, entity) {
+            Some(def) => def,
+            None => return
+        };
+
+        let ammo = match find_ammo(em, entity, &def.ammo) {
+            Some(ammo) => ammo,
+            None => return message_log::push(em, entity, format!("You have no {}s left.", def.ammo))
+        };
+
+        let origin = match get_component!(em, entity, components::Position) {
+            Some(position) => (position.x, position.y),
+            None => return
+        };
+
+        if origin == cursor {
+            return;
+        }
+
+        let trace = {
+            let map = match em.get_entity_by_name("Map").and_then(|map_entity| get_component!(em, map_entity, Map)) {
+                Some(map) => map,
+                None => return
+            };
+
+            let mut occupants = collidable_occupants(em);
+            occupants.remove(&origin);
+
+            map.trace_projectile(origin, cursor, def.range, &occupants)
+        };
+
+        let spent = {
+            let ammo = get_component!(mut, em, ammo, components::Ammo).unwrap();
+            ammo.count -= 1;
+            ammo.count == 0
+        };
+
+        if spent {
+            if let Some(inventory) = get_component!(mut, em, entity, components::Inventory) {
+                inventory.remove_item(ammo);
+            }
+
+            em.kill_entity(ammo);
+        }
+
+        em.add_component(entity, components::Projectile { path: trace.path.clone(), glyph: def.glyph });
+
+        let target = match trace.hit {
+            ProjectileHit::Entity(target) if em.has_component(target, components::Health::get_component_type()) => target,
+            _ => return
+        };
+
+        let rules = combat::rules();
+
+        let mut shooter = Combatant::from_entity(em, entity, rules);
+        shooter.weapon = def.as_weapon();
+
+        let defender = Combatant::from_entity(em, target, rules);
+
+        match combat::resolve_attack(rules, &shooter, &defender, &mut thread_rng()) {
+            AttackOutcome::Miss | AttackOutcome::Fumble => {
+                let message = combat::sentence(format!("The {} misses {}.", def.ammo, combat::describe(em, target)));
+                message_log::push(em, entity, message);
+            }
+            AttackOutcome::Hit { damage, critical } => {
+                if critical {
+                    let message = combat::sentence(format!("A perfect shot on {}!", combat::describe(em, target)));
+                    message_log::push(em, entity, message);
+                }
+
+                combat::inflict(em, components::Damage {
+                    amount: damage,
+                    target: target,
+                    kind: def.kind,
+                    source: Some(entity),
+                    weapon: Some(def.name.clone())
+                });
+            }
+        }
+    }
+}
+
+impl System for ShootSystem {
+    fn process(&self, em: &mut EntityManager) {
+        for entity in em.get_entities_with_components(components::Shot::get_component_type()) {
+            let shot = *get_component!(em, entity, components::Shot).unwrap();
+
+            em.remove_component(entity, components::Shot::get_component_type());
+
+            if can_act(em, entity) {
+                self.fire(em, entity, (shot.x, shot.y));
+            }
+        }
+    }
+}
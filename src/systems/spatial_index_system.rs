@@ -0,0 +1,89 @@
+use super::{System};
+use crate::entities::{EntityManager};
+use crate::components::{Component, Position};
+use crate::spatial::SpatialIndex;
+
+/**
+ * Keeps the "SpatialIndex" entity in step with entity positions
+ * Only entities whose position was added, removed or borrowed mutably since the last pass are updated,
+ * run it once a tick right before the systems that query it
+ */
+#[derive(Debug)]
+pub struct SpatialIndexSystem;
+
+impl System for SpatialIndexSystem {
+    fn mount(&mut self, em: &mut EntityManager) {
+        if em.get_entity_by_name("SpatialIndex").is_some() {
+            return;
+        }
+
+        em.watch(Position::get_component_type());
+
+        let index = SpatialIndex::build(em);
+
+        let index_entity = em.create_entity();
+        em.add_component(index_entity, index);
+        em.set_entity_name(index_entity, "SpatialIndex");
+    }
+
+    fn process(&self, em: &mut EntityManager) {
+        let index_entity = match em.get_entity_by_name("SpatialIndex") {
+            Some(entity) => entity,
+            None => return
+        };
+
+        let changed = em.take_changed(Position::get_component_type());
+
+        if changed.is_empty() {
+            return;
+        }
+
+        // Take the index out so it can read the positions while it updates
+        let mut index_component = match em.remove_component(index_entity, SpatialIndex::get_component_type()) {
+            Some(component) => component,
+            None => return
+        };
+
+        let index = index_component.as_any_mut().downcast_mut::<SpatialIndex>().unwrap();
+
+        for entity in changed {
+            index.update(em, entity);
+        }
+
+        em.add_boxed_component(index_entity, index_component);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_create_one_index_and_update_what_moved() {
+        let mut em = EntityManager::new();
+
+        let entity = em.create_entity();
+        em.add_component(entity, Position { x: 1, y: 1 });
+
+        let mut first = SpatialIndexSystem;
+        let mut second = SpatialIndexSystem;
+        first.mount(&mut em);
+        second.mount(&mut em);
+
+        assert_eq!(em.get_entities_with_components(SpatialIndex::get_component_type()).len(), 1);
+
+        get_component!(mut, em, entity, Position).unwrap().y = 2;
+
+        let spawned = em.create_entity();
+        em.add_component(spawned, Position { x: 3, y: 3 });
+
+        first.process(&mut em);
+
+        let index_entity = em.get_entity_by_name("SpatialIndex").unwrap();
+        let index = get_component!(em, index_entity, SpatialIndex).unwrap();
+
+        assert_eq!(index.entities_at(1, 2), &[entity]);
+        assert_eq!(index.entities_at(3, 3), &[spawned]);
+        assert!(index.entities_at(1, 1).is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+use std::rc::Rc;
+
+use super::System;
+use crate::entities::{Entity, EntityManager};
+use crate::components::{Component, self, TargetMode};
+use crate::magic::{self, SpellDef, SpellShape};
+use crate::scripts::Scripts;
+use crate::menu;
+use crate::message_log;
+use crate::targeting::{self, Steer, KEY_ESCAPE};
+
+const KEY_SPELLBOOK: i32 = 122;  // z
+
+/**
+ * Spellcasting
+ * 'z' opens the spellbook, a number key picks a spell and spells that need aiming
+ * get a cursor like a ranged weapon, 'z' or enter casts and escape gives up
+ * The cast system casts it once the turn hands out actions,
+ * what a spell does is up to its script in assets/spells
+ */
+#[derive(Debug)]
+pub struct SpellSystem {
+    scripts: Rc<Scripts>
+}
+
+impl SpellSystem {
+    pub fn new(scripts: Rc<Scripts>) -> Self {
+        Self {
+            scripts: scripts
+        }
+    }
+
+    fn close(&self, em: &mut EntityManager, entity: Entity) {
+        em.remove_component(entity, components::Casting::get_component_type());
+        em.remove_component(entity, components::Targeting::get_component_type());
+    }
+
+    /// Pick a spell from the book, true when it's cast right away
+    fn choose(&self, em: &mut EntityManager, entity: Entity, index: usize) -> bool {
+        let name = match get_component!(em, entity, components::Spellbook).and_then(|book| book.spells.get(index)) {
+            Some(name) => name.clone(),
+            None => return false
+        };
+
+        self.close(em, entity);
+
+        let def = match self.scripts.spell(&name) {
+            Some(def) => def,
+            None => {
+                message_log::push(em, entity, format!("You can't remember how to cast {}.", name));
+                return false;
+            }
+        };
+
+        let mana = get_component!(em, entity, components::Mana).map_or(0, |mana| mana.mana);
+
+        if mana < def.cost {
+            message_log::push(em, entity, format!("You don't have enough mana to cast {}.", def.name));
+            return false;
+        }
+
+        if !def.shape.needs_target() {
+            let position = *get_component!(em, entity, components::Position).unwrap();
+            return self.cast(em, entity, &def, (position.x, position.y));
+        }
+
+        em.add_component(entity, components::Casting { spell: Some(def.name.clone()) });
+
+        // Blinks and summons start on the caster and get moved away from there
+        targeting::start(em, entity, TargetMode::Cast, def.range, def.shape != SpellShape::Tile);
+
+        false
+    }
+
+    /// Cast at the target this turn, true unless it can't be cast there
+    fn cast(&self, em: &mut EntityManager, entity: Entity, def: &SpellDef, target: (i32, i32)) -> bool {
+        if let Err(message) = magic::reach(em, entity, def, target) {
+            message_log::push(em, entity, message);
+            return false;
+        }
+
+        em.add_component(entity, components::Cast { spell: def.name.clone(), x: target.0, y: target.1 });
+
+        true
+    }
+
+    /// Work the spell's cursor, true when the spell is cast
+    fn aim(&self, em: &mut EntityManager, entity: Entity, spell: &str, input: i32) -> bool {
+        match targeting::steer(em, entity, input, KEY_SPELLBOOK) {
+            Steer::Confirm(x, y) => {
+                self.close(em, entity);
+
+                match self.scripts.spell(spell) {
+                    Some(def) => self.cast(em, entity, &def, (x, y)),
+                    None => false
+                }
+            }
+            Steer::Cancel => {
+                self.close(em, entity);
+                false
+            }
+            Steer::Aiming => false
+        }
+    }
+}
+
+impl System for SpellSystem {
+    fn mount(&mut self, em: &mut EntityManager) {
+        let grimoire = em.create_entity();
+        em.add_component(grimoire, self.scripts.grimoire());
+        em.set_entity_name(grimoire, "Grimoire");
+    }
+
+    fn process(&self, em: &mut EntityManager) {
+        let input_entities = em.get_entities_with_components(components::Input::get_component_type());
+
+        for entity in input_entities {
+            let input = get_component!(em, entity, components::Input).unwrap().input;
+            let casting = get_component!(em, entity, components::Casting).cloned();
+
+            let cast = match casting {
+                None => {
+                    if input == KEY_SPELLBOOK && em.has_component(entity, components::Spellbook::get_component_type()) && !menu::in_menu(em, entity) {
+                        em.add_component(entity, components::Casting { spell: None });
+                        menu::consume(em, entity);
+                    }
+
+                    continue;
+                }
+                Some(components::Casting { spell: None }) => match input {
+                    KEY_ESCAPE | KEY_SPELLBOOK => {
+                        self.close(em, entity);
+                        false
+                    }
+                    49..=57 => self.choose(em, entity, (input - 49) as usize),  // 1 - 9
+                    _ => false
+                },
+                Some(components::Casting { spell: Some(spell) }) => self.aim(em, entity, &spell, input)
+            };
+
+            // Only casting takes the turn
+            if !cast {
+                menu::consume(em, entity);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+    use crate::systems::CastSystem;
+
+    // A key and, unless a menu used it up, the turn it starts, true when the world waited
+    fn press(system: &SpellSystem, em: &mut EntityManager, entity: Entity, key: i32) -> bool {
+        get_component!(mut, em, entity, components::Input).unwrap().input = key;
+        system.process(em);
+
+        let waits = menu::world_waits(em);
+
+        if !waits {
+            CastSystem::new(system.scripts.clone()).process(em);
+        }
+
+        waits
+    }
+
+    fn system() -> SpellSystem {
+        let spells = Scripts::new();
+
+        spells.load(r#"
+            spell {
+                name = "spark",
+                cost = 4,
+                range = 6,
+                shape = "bolt",
+                cast = function(target)
+                    for _, victim in ipairs(target.targets) do
+                        damage(victim.id, 5, "Fire")
+                    end
+                end
+            }
+
+            spell {
+                name = "mend",
+                cost = 3,
+                shape = "self",
+                cast = function(target) heal(target.caster, 4) end
+            }
+        "#).unwrap();
+
+        SpellSystem::new(Rc::new(spells))
+    }
+
+    #[test]
+    fn it_should_pick_aim_and_cast_from_the_spellbook() {
+        let system = system();
+        let mut em = EntityManager::new();
+
+        let map_entity = em.create_entity();
+        em.set_entity_name(map_entity, "Map");
+        em.add_component(map_entity, MapBuilder::new(20, 20).create_room(&Rect::new(0, 0, 19, 19)).build());
+
+        let player = em.create_entity();
+        em.add_component(player, components::Player);
+        em.add_component(player, components::Input::new());
+        em.add_component(player, components::Log::new());
+        em.add_component(player, components::Faction { name: "player".to_string() });
+        em.add_component(player, components::Position { x: 2, y: 2 });
+        em.add_component(player, components::Health { health: 5, max_health: 10 });
+        em.add_component(player, components::Mana { mana: 7, max_mana: 10, regen: 0, progress: 0 });
+        em.add_component(player, components::Spellbook { spells: vec!["spark".to_string(), "mend".to_string()] });
+
+        let goblin = em.create_entity();
+        em.add_component(goblin, components::Faction { name: "goblins".to_string() });
+        em.add_component(goblin, components::Health { health: 8, max_health: 8 });
+        em.add_component(goblin, components::Collidable);
+        em.add_component(goblin, components::Position { x: 5, y: 2 });
+
+        assert!(press(&system, &mut em, player, KEY_SPELLBOOK));
+        assert_eq!(get_component!(em, player, components::Casting), Some(&components::Casting { spell: None }));
+
+        assert!(press(&system, &mut em, player, '1' as i32));
+        assert_eq!(get_component!(em, player, components::Targeting).map(|cursor| (cursor.x, cursor.y)), Some((5, 2)));
+
+        // Casting takes the turn
+        assert!(!press(&system, &mut em, player, targeting::KEY_ENTER));
+        assert!(get_component!(em, player, components::Casting).is_none());
+        assert_eq!(get_component!(em, player, components::Mana).unwrap().mana, 3);
+        assert_eq!(get_component!(em, goblin, components::DamageQueue).unwrap().instances[0].amount, 5);
+
+        press(&system, &mut em, player, KEY_SPELLBOOK);
+        press(&system, &mut em, player, '2' as i32);
+        assert_eq!(get_component!(em, player, components::Mana).unwrap().mana, 0);
+        assert_eq!(get_component!(em, player, components::Health).unwrap().health, 9);
+
+        press(&system, &mut em, player, KEY_SPELLBOOK);
+        assert!(press(&system, &mut em, player, '2' as i32));
+        assert!(get_component!(em, player, components::Casting).is_none());
+        assert_eq!(get_component!(em, player, components::Log).unwrap().history.last().unwrap(), "You don't have enough mana to cast mend.");
+    }
+
+    #[test]
+    fn it_should_not_cast_without_an_action_this_turn() {
+        let system = system();
+        let mut em = EntityManager::new();
+
+        let player = em.create_entity();
+        em.add_component(player, components::Input::new());
+        em.add_component(player, components::Log::new());
+        em.add_component(player, components::Position { x: 2, y: 2 });
+        em.add_component(player, components::Health { health: 5, max_health: 10 });
+        em.add_component(player, components::Mana { mana: 7, max_mana: 10, regen: 0, progress: 0 });
+        em.add_component(player, components::Spellbook { spells: vec!["mend".to_string()] });
+        // Slowed, this turn goes by without an action
+        em.add_component(player, components::Turn { actions: 0 });
+
+        press(&system, &mut em, player, KEY_SPELLBOOK);
+        assert!(!press(&system, &mut em, player, '1' as i32));
+
+        assert_eq!(get_component!(em, player, components::Mana).unwrap().mana, 7);
+        assert_eq!(get_component!(em, player, components::Health).unwrap().health, 5);
+        assert!(get_component!(em, player, components::Cast).is_none());
+    }
+}
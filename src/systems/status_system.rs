@@ -0,0 +1,157 @@
+use super::System;
+use crate::entities::{Entity, EntityManager};
+use crate::components::{Component, self, StatusKind};
+use crate::combat;
+use crate::status;
+use crate::message_log;
+
+use rand::thread_rng;
+
+/**
+ * Runs status effects after actions are handed out and before anyone moves
+ * Bends walks for stunned and confused entities. Poison, fire and regeneration
+ * tick and effects count down once per action, so they last as many of the
+ * entity's own turns whether it is hasted or slowed
+ */
+#[derive(Debug)]
+pub struct StatusSystem;
+
+impl StatusSystem {
+    fn tick(&self, em: &mut EntityManager, entity: Entity, effect: &components::StatusEffect) {
+        let kind = match effect.kind {
+            StatusKind::Poisoned => components::DamageType::Poison,
+            StatusKind::Burning => components::DamageType::Fire,
+            StatusKind::Regenerating => {
+                if let Some(health) = get_component!(mut, em, entity, components::Health) {
+                    health.health = (health.health + effect.magnitude).min(health.max_health);
+                }
+
+                return;
+            }
+            _ => return
+        };
+
+        // The LastAttacker from whatever caused it keeps the kill credit
+        combat::inflict(em, components::Damage {
+            amount: effect.magnitude,
+            target: entity,
+            kind: kind,
+            source: None,
+            weapon: None
+        });
+    }
+}
+
+impl System for StatusSystem {
+    fn process(&self, em: &mut EntityManager) {
+        let mut rng = thread_rng();
+
+        let entities = em.get_entities_with_components(components::StatusEffects::get_component_type());
+
+        for entity in entities {
+            let status = get_component!(em, entity, components::StatusEffects).unwrap().clone();
+
+            if let Some(walk) = get_component!(mut, em, entity, components::Walk) {
+                let (dx, dy) = status::effective_walk(&status, (walk.dx, walk.dy), &mut rng);
+
+                walk.dx = dx;
+                walk.dy = dy;
+            }
+
+            // Entities the TurnSystem doesn't track get one turn per tick
+            let actions = get_component!(em, entity, components::Turn).map_or(1, |turn| turn.actions);
+
+            let mut expired = Vec::new();
+
+            for _ in 0..actions {
+                let status = get_component!(em, entity, components::StatusEffects).unwrap().clone();
+
+                for effect in status.effects.iter() {
+                    self.tick(em, entity, effect);
+                }
+
+                if let Some(status) = get_component!(mut, em, entity, components::StatusEffects) {
+                    for effect in status.effects.iter_mut() {
+                        effect.turns -= 1;
+
+                        if effect.turns <= 0 {
+                            expired.push(effect.kind);
+                        }
+                    }
+
+                    status.effects.retain(|effect| effect.turns > 0);
+                }
+            }
+
+            if !em.has_component(entity, components::Player::get_component_type()) {
+                continue;
+            }
+
+            for kind in expired {
+                message_log::push(em, entity, format!("You are no longer {}.", kind.name()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_tick_and_expire_effects() {
+        let mut em = EntityManager::new();
+
+        let player = em.create_entity();
+        em.add_component(player, components::Player);
+        em.add_component(player, components::Log::new());
+        em.add_component(player, components::Health { health: 10, max_health: 20 });
+        em.add_component(player, components::Walk { dx: 1, dy: 0 });
+
+        status::apply_status(&mut em, player, components::StatusEffect { kind: StatusKind::Poisoned, turns: 2, magnitude: 3, source: None });
+        status::apply_status(&mut em, player, components::StatusEffect { kind: StatusKind::Stunned, turns: 1, magnitude: 0, source: None });
+        status::apply_status(&mut em, player, components::StatusEffect { kind: StatusKind::Regenerating, turns: 5, magnitude: 2, source: None });
+
+        StatusSystem.process(&mut em);
+
+        // Stunned in place, poison queued and regeneration already applied
+        let walk = get_component!(em, player, components::Walk).unwrap();
+        assert_eq!((walk.dx, walk.dy), (0, 0));
+        assert_eq!(get_component!(em, player, components::DamageQueue).unwrap().instances[0].amount, 3);
+        assert_eq!(get_component!(em, player, components::Health).unwrap().health, 12);
+
+        StatusSystem.process(&mut em);
+
+        let status = get_component!(em, player, components::StatusEffects).unwrap();
+        assert_eq!(status.effects.len(), 1);
+        assert!(status.has(StatusKind::Regenerating));
+
+        let history = &get_component!(em, player, components::Log).unwrap().history;
+        assert!(history.contains(&"You are no longer stunned.".to_string()));
+        assert!(history.contains(&"You are no longer poisoned.".to_string()));
+    }
+
+    #[test]
+    fn it_should_count_down_on_the_entitys_own_turns() {
+        let mut em = EntityManager::new();
+
+        let hasted = em.create_entity();
+        let slowed = em.create_entity();
+
+        for entity in &[hasted, slowed] {
+            em.add_component(*entity, components::Health { health: 20, max_health: 20 });
+            status::apply_status(&mut em, *entity, components::StatusEffect { kind: StatusKind::Poisoned, turns: 4, magnitude: 1, source: None });
+        }
+
+        em.add_component(hasted, components::Turn { actions: 2 });
+        em.add_component(slowed, components::Turn { actions: 0 });
+
+        StatusSystem.process(&mut em);
+
+        assert_eq!(get_component!(em, hasted, components::StatusEffects).unwrap().get(StatusKind::Poisoned).unwrap().turns, 2);
+        assert_eq!(get_component!(em, hasted, components::DamageQueue).unwrap().instances.len(), 2);
+
+        assert_eq!(get_component!(em, slowed, components::StatusEffects).unwrap().get(StatusKind::Poisoned).unwrap().turns, 4);
+        assert!(get_component!(em, slowed, components::DamageQueue).is_none());
+    }
+}
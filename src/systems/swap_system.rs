@@ -0,0 +1,95 @@
+use super::System;
+use crate::entities::{Entity, EntityManager};
+use crate::components::{Component, self};
+use crate::combat;
+use crate::message_log;
+use crate::factions::{self, Relation};
+
+/**
+ * Bumping into an ally trades places with them,
+ * bumping into a neutral just gets a grumble in the log
+ */
+#[derive(Debug)]
+pub struct SwapSystem;
+
+impl SwapSystem {
+    fn swap(&self, em: &mut EntityManager, entity: Entity, other: Entity) {
+        let (position, other_position) = match (get_component!(em, entity, components::Position), get_component!(em, other, components::Position)) {
+            (Some(position), Some(other_position)) => (*position, *other_position),
+            _ => return
+        };
+
+        em.add_component(entity, other_position);
+        em.add_component(other, position);
+
+        if em.has_component(entity, components::Player::get_component_type()) {
+            let message = combat::sentence(format!("You swap places with {}.", combat::describe(em, other)));
+            message_log::push(em, entity, message);
+        }
+    }
+}
+
+impl System for SwapSystem {
+    fn process(&self, em: &mut EntityManager) {
+        let entities = em.get_entities_with_components(components::Event::get_component_type());
+
+        for entity in entities {
+            let other = match get_component!(em, entity, components::Event) {
+                Some(components::Event::Collision(collider)) => *collider,
+                _ => continue
+            };
+
+            // Doors and other furniture don't have a side
+            if !em.has_component(other, components::Faction::get_component_type()) {
+                continue;
+            }
+
+            match factions::relation(em, entity, other) {
+                Relation::Allied => self.swap(em, entity, other),
+                Relation::Neutral => {
+                    let message = combat::sentence(format!("You bump into {}.", combat::describe(em, other)));
+                    message_log::push(em, entity, message);
+                }
+                Relation::Hostile => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_swap_places_with_allies() {
+        let mut em = EntityManager::new();
+
+        let player = em.create_entity();
+        em.add_component(player, components::Player);
+        em.add_component(player, components::Log::new());
+        em.add_component(player, components::Faction { name: "player".to_string() });
+        em.add_component(player, components::Position { x: 1, y: 1 });
+
+        let villager = em.create_entity();
+        em.add_component(villager, components::Name { name: "villager".to_string() });
+        em.add_component(villager, components::Faction { name: "townsfolk".to_string() });
+        em.add_component(villager, components::Position { x: 2, y: 1 });
+
+        let goblin = em.create_entity();
+        em.add_component(goblin, components::Faction { name: "goblins".to_string() });
+        em.add_component(goblin, components::Position { x: 1, y: 2 });
+
+        em.add_component(player, components::Event::Collision(villager));
+        SwapSystem.process(&mut em);
+
+        assert_eq!(get_component!(em, player, components::Position), Some(&components::Position { x: 2, y: 1 }));
+        assert_eq!(get_component!(em, villager, components::Position), Some(&components::Position { x: 1, y: 1 }));
+        assert_eq!(get_component!(em, player, components::Log).unwrap().history, vec!["You swap places with the villager."]);
+
+        // Enemies stay put, the AttackSystem deals with them
+        em.add_component(player, components::Event::Collision(goblin));
+        SwapSystem.process(&mut em);
+
+        assert_eq!(get_component!(em, goblin, components::Position), Some(&components::Position { x: 1, y: 2 }));
+    }
+}
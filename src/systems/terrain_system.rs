@@ -0,0 +1,128 @@
+use super::{System};
+use crate::entities::{Entity, EntityManager};
+use crate::components::{Component, self};
+use crate::map::Map;
+use crate::combat;
+use crate::message_log;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/**
+ * Applies terrain effects to entities that step onto a tile
+ * Runs after the move system
+ */
+#[derive(Debug)]
+pub struct TerrainSystem {
+    last_positions: RefCell<HashMap<Entity, (i32, i32)>>
+}
+
+impl TerrainSystem {
+    pub fn new() -> Self {
+        Self {
+            last_positions: RefCell::new(HashMap::new())
+        }
+    }
+}
+
+impl System for TerrainSystem {
+    fn process(&self, em: &mut EntityManager) {
+        let map_entity = match em.get_entity_by_name("Map") {
+            Some(entity) => entity,
+            None => return
+        };
+
+        let health_entities = em.get_entities_with_components(components::Health::get_component_type());
+
+        let damages: Vec<(Entity, i32, components::DamageType, String)> = {
+            let map = get_component!(em, map_entity, Map).unwrap();
+            let mut last_positions = self.last_positions.borrow_mut();
+
+            // Forget whoever died or left with their level
+            last_positions.retain(|entity, _| health_entities.contains(entity));
+
+            health_entities.iter()
+                .filter_map(|entity| {
+                    let position = get_component!(em, *entity, components::Position)?;
+                    let current = (position.x, position.y);
+
+                    // The first time an entity is seen it's where it was put, it didn't step there
+                    let entered = last_positions.insert(*entity, current)
+                        .map_or(false, |previous| previous != current);
+
+                    if !entered {
+                        return None;
+                    }
+
+                    let def = map.get_tile(position.x, position.y).def();
+
+                    if def.damage_on_enter > 0 {
+                        Some((*entity, def.damage_on_enter, def.damage_type, def.name.clone()))
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        };
+
+        for (entity, amount, kind, tile_name) in damages {
+            debug!("Entity {} stepped into {}", entity, tile_name);
+
+            combat::inflict(em, components::Damage {
+                amount: amount,
+                target: entity,
+                kind: kind,
+                source: None,
+                weapon: None
+            });
+
+            message_log::push(em, entity, format!("You step into the {}.", tile_name));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{MapBuilder, Cell, TileType};
+    use crate::types::Rect;
+
+    #[test]
+    fn it_should_only_hurt_entities_stepping_onto_lava() {
+        let system = TerrainSystem::new();
+        let mut em = EntityManager::new();
+
+        let mut map = MapBuilder::new(10, 10).create_room(&Rect::new(0, 0, 9, 9)).build();
+        map.set_cell(3, 3, Cell::new(TileType::Lava));
+        map.set_cell(4, 3, Cell::new(TileType::Lava));
+
+        let map_entity = em.create_entity();
+        em.set_entity_name(map_entity, "Map");
+        em.add_component(map_entity, map);
+
+        // Spawned on lava
+        let salamander = em.create_entity();
+        em.add_component(salamander, components::Health { health: 10, max_health: 10 });
+        em.add_component(salamander, components::Position { x: 3, y: 3 });
+
+        let player = em.create_entity();
+        em.add_component(player, components::Health { health: 10, max_health: 10 });
+        em.add_component(player, components::Position { x: 2, y: 3 });
+
+        system.process(&mut em);
+
+        assert!(!em.has_component(salamander, components::DamageQueue::get_component_type()));
+
+        em.add_component(player, components::Position { x: 4, y: 3 });
+        system.process(&mut em);
+
+        assert!(!em.has_component(salamander, components::DamageQueue::get_component_type()));
+        assert!(em.has_component(player, components::DamageQueue::get_component_type()));
+
+        // Gone, and not tracked any more
+        em.remove_component(salamander, components::Health::get_component_type());
+        system.process(&mut em);
+
+        assert!(!system.last_positions.borrow().contains_key(&salamander));
+    }
+}
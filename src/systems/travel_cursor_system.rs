@@ -0,0 +1,125 @@
+use super::System;
+use crate::entities::{Entity, EntityManager};
+use crate::components::{Component, self, TargetMode};
+use crate::map::Map;
+use crate::menu;
+use crate::message_log;
+use crate::targeting::{self, Steer};
+
+const KEY_TRAVEL: i32 = 116;  // t
+
+/**
+ * Travel command
+ * 't' puts a cursor on the player, 't' or enter sends them walking there
+ * Only tiles the player has seen can be picked, the travel system finds the way
+ */
+#[derive(Debug)]
+pub struct TravelCursorSystem;
+
+impl TravelCursorSystem {
+    /// Set off for the tile, false when there's no going there
+    fn travel(&self, em: &mut EntityManager, entity: Entity, destination: (i32, i32)) -> bool {
+        let position = match get_component!(em, entity, components::Position) {
+            Some(position) => (position.x, position.y),
+            None => return false
+        };
+
+        if position == destination {
+            return false;
+        }
+
+        let known = em.get_entity_by_name("Map")
+            .and_then(|map_entity| get_component!(em, map_entity, Map))
+            .map_or(false, |map| map.is_revealed(destination.0, destination.1) && !map.is_blocked(destination.0, destination.1));
+
+        if !known {
+            message_log::push(em, entity, "You don't know the way there.".to_string());
+            return false;
+        }
+
+        em.add_component(entity, components::Travel { x: destination.0, y: destination.1 });
+
+        true
+    }
+}
+
+impl System for TravelCursorSystem {
+    fn process(&self, em: &mut EntityManager) {
+        let input_entities = em.get_entities_with_components(components::Input::get_component_type());
+
+        for entity in input_entities {
+            let input = get_component!(em, entity, components::Input).unwrap().input;
+
+            match get_component!(em, entity, components::Targeting).map(|cursor| cursor.mode) {
+                Some(TargetMode::Travel) => {}
+                Some(_) => continue,
+                None => {
+                    if input == KEY_TRAVEL && !menu::in_menu(em, entity) {
+                        targeting::start(em, entity, TargetMode::Travel, 0, false);
+                        menu::consume(em, entity);
+                    }
+
+                    continue;
+                }
+            }
+
+            // Setting off takes the first step this turn
+            let traveling = match targeting::steer(em, entity, input, KEY_TRAVEL) {
+                Steer::Confirm(x, y) => self.travel(em, entity, (x, y)),
+                Steer::Aiming | Steer::Cancel => false
+            };
+
+            if !traveling {
+                menu::consume(em, entity);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+
+    fn press(em: &mut EntityManager, entity: Entity, key: i32) -> bool {
+        get_component!(mut, em, entity, components::Input).unwrap().input = key;
+        TravelCursorSystem.process(em);
+        menu::world_waits(em)
+    }
+
+    #[test]
+    fn it_should_travel_to_a_picked_tile_it_has_seen() {
+        let mut em = EntityManager::new();
+
+        let mut map = MapBuilder::new(20, 20).create_room(&Rect::new(0, 0, 19, 19)).build();
+        map.reveal(4, 3);
+
+        let map_entity = em.create_entity();
+        em.set_entity_name(map_entity, "Map");
+        em.add_component(map_entity, map);
+
+        let player = em.create_entity();
+        em.add_component(player, components::Input::new());
+        em.add_component(player, components::Log::new());
+        em.add_component(player, components::Position { x: 2, y: 2 });
+
+        assert!(press(&mut em, player, KEY_TRAVEL));
+        assert!(press(&mut em, player, 115));
+        assert!(press(&mut em, player, 100));
+
+        // Never seen, the cursor is dropped and nothing happens
+        assert!(press(&mut em, player, targeting::KEY_ENTER));
+        assert!(get_component!(em, player, components::Travel).is_none());
+        assert_eq!(get_component!(em, player, components::Log).unwrap().history.last().unwrap(), "You don't know the way there.");
+
+        press(&mut em, player, KEY_TRAVEL);
+        press(&mut em, player, 115);
+        press(&mut em, player, 100);
+        press(&mut em, player, 100);
+
+        assert!(!press(&mut em, player, KEY_TRAVEL));
+        assert_eq!(get_component!(em, player, components::Travel), Some(&components::Travel { x: 4, y: 3 }));
+        assert!(get_component!(em, player, components::Targeting).is_none());
+    }
+}
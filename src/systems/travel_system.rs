@@ -0,0 +1,133 @@
+use super::{System};
+use crate::entities::{Entity, EntityManager};
+use crate::components::{Component, self};
+use crate::map::{Map, PathOptions};
+use crate::map::pathfinding::{find_path, collidable_positions};
+use crate::interrupt;
+use crate::targeting::{self, KEY_ESCAPE};
+
+/**
+ * Steers entities with a Travel destination along an A* path
+ * Runs before the walk system, a movement key or escape gives the player back control
+ * and a hostile in view stops them
+ */
+#[derive(Debug)]
+pub struct TravelSystem;
+
+impl TravelSystem {
+    fn handle_input(&self, em: &mut EntityManager) {
+        for entity in em.get_entities_with_components(components::Travel::get_component_type()) {
+            let input = get_component!(em, entity, components::Input).map_or(0, |input| input.input);
+
+            if input == KEY_ESCAPE || targeting::nudge(input).is_some() {
+                em.remove_component(entity, components::Travel::get_component_type());
+            } else if interrupt::hostile_in_view(em, entity) {
+                interrupt::stop(em, entity, "You stop traveling, there's something hostile in view.");
+            }
+        }
+    }
+}
+
+impl System for TravelSystem {
+    fn process(&self, em: &mut EntityManager) {
+        self.handle_input(em);
+
+        let travel_entities = em.get_entities_with_components(components::Travel::get_component_type());
+
+        if travel_entities.is_empty() {
+            return;
+        }
+
+        let map_entity = match em.get_entity_by_name("Map") {
+            Some(entity) => entity,
+            None => return
+        };
+
+        let options = PathOptions::default();
+
+        // Plan every step first, the map borrows the entity manager
+        let steps: Vec<(Entity, Option<(i32, i32)>)> = {
+            let map = get_component!(em, map_entity, Map).unwrap();
+            let occupied = collidable_positions(em);
+
+            travel_entities.iter()
+                .filter_map(|entity| {
+                    let travel = get_component!(em, *entity, components::Travel)?;
+                    let position = get_component!(em, *entity, components::Position)?;
+                    let start = (position.x, position.y);
+
+                    let step = find_path(map, start, (travel.x, travel.y), &options, &occupied)
+                        .and_then(|path| path.next_direction(start));
+
+                    Some((*entity, step))
+                })
+                .collect()
+        };
+
+        for (entity, step) in steps {
+            match step {
+                Some((dx, dy)) => {
+                    if let Some(walk) = get_component!(mut, em, entity, components::Walk) {
+                        walk.dx = dx;
+                        walk.dy = dy;
+                    }
+                }
+                None => {
+                    // Arrived or no path left
+                    debug!("Entity {} finished traveling", entity);
+                    em.remove_component(entity, components::Travel::get_component_type());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+
+    fn setup() -> (EntityManager, Entity) {
+        let mut em = EntityManager::new();
+
+        let map_entity = em.create_entity();
+        em.set_entity_name(map_entity, "Map");
+        em.add_component(map_entity, MapBuilder::new(30, 10).create_room(&Rect::new(0, 0, 29, 9)).build());
+
+        let player = em.create_entity();
+        em.add_component(player, components::Input::new());
+        em.add_component(player, components::Log::new());
+        em.add_component(player, components::Position { x: 2, y: 2 });
+        em.add_component(player, components::Walk::new());
+        em.add_component(player, components::Travel { x: 6, y: 2 });
+
+        (em, player)
+    }
+
+    #[test]
+    fn it_should_travel_until_a_movement_key() {
+        let (mut em, player) = setup();
+
+        TravelSystem.process(&mut em);
+        assert_eq!(get_component!(em, player, components::Walk).map(|walk| walk.dx), Some(1));
+
+        get_component!(mut, em, player, components::Input).unwrap().input = 115;   // s
+        TravelSystem.process(&mut em);
+        assert!(get_component!(em, player, components::Travel).is_none());
+    }
+
+    #[test]
+    fn it_should_stop_when_a_hostile_comes_into_view() {
+        let (mut em, player) = setup();
+
+        let zombie = em.create_entity();
+        em.add_component(zombie, components::Health { health: 8, max_health: 8 });
+        em.add_component(zombie, components::Position { x: 9, y: 2 });
+
+        TravelSystem.process(&mut em);
+
+        assert!(get_component!(em, player, components::Travel).is_none());
+        assert_eq!(get_component!(em, player, components::Log).unwrap().history.last().unwrap(), "You stop traveling, there's something hostile in view.");
+    }
+}
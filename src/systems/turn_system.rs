@@ -1,98 +1,112 @@
 use super::System;
 use crate::entities::{Entity, EntityManager};
 use crate::components::{Component, self};
-
-use std::cell::RefCell;
-use std::collections::VecDeque;
-
+use crate::status;
+
+/// Energy one action costs, speed 10 acts once a turn
+pub const ACTION_COST: i32 = 10;
+
+/**
+ * Hands out actions from speed
+ * Every turn an entity gains its speed, after haste and slow, in energy
+ * and spends it on as many actions as it covers. The leftover carries over.
+ * Runs once walks are set; an entity without an action this turn stands still
+ * and one with more than one walks further, see the collision system
+ */
 #[derive(Debug)]
-pub struct TurnSystem {
-    entities: RefCell<VecDeque<Entity>>
-}
+pub struct TurnSystem;
 
 impl TurnSystem {
     pub fn new() -> Self {
-        Self {
-            entities: RefCell::new(VecDeque::new())
-        }
+        TurnSystem
     }
 
-    fn process_turn(&self, em: &mut EntityManager) {
-        // Check if current turn's entity's still has energy
-        let entities = self.entities.borrow();
-        let current_turn_entity = entities.front();
-
-        if current_turn_entity.is_none() {
-            return;
-        }
+    fn actions(&self, em: &mut EntityManager, entity: Entity) -> i32 {
+        let speed = get_component!(em, entity, components::Speed)
+            .map_or(ACTION_COST, |speed| status::effective_speed(em, entity, speed.amount));
 
-        let current_turn_entity = *current_turn_entity.unwrap();
+        let energy = get_component!(mut, em, entity, components::Energy).unwrap();
 
-        let energy = get_component!(em, current_turn_entity, components::Energy).unwrap();
+        energy.amount += speed.max(0);
 
-        if energy.amount <= 0 {
-            debug!("Current entity {} has no more energy", current_turn_entity);
-            self.entities.borrow_mut().rotate_right(1);
-            let new_turn_entity = *self.entities.borrow().front().unwrap();
+        let actions = energy.amount / ACTION_COST;
+        energy.amount -= actions * ACTION_COST;
 
-            // Remove turn component from current entity
-            // Give to entity
-            em.remove_component(current_turn_entity, components::Turn::get_component_type());
-            em.add_component(new_turn_entity, components::Turn);
+        actions
+    }
+}
 
-            // Give energy to new entity
-            let new_speed = {
-                let speed = get_component!(em, new_turn_entity, components::Speed).unwrap();
-                speed.amount
-            };
+/// Whether an entity has an action this turn, anything the TurnSystem doesn't track always does
+pub fn can_act(em: &EntityManager, entity: Entity) -> bool {
+    get_component!(em, entity, components::Turn).map_or(true, |turn| turn.actions > 0)
+}
 
-            {
-                let new_energy = get_component!(mut, em, new_turn_entity, components::Energy).unwrap();
+impl System for TurnSystem {
+    fn process(&self, em: &mut EntityManager) {
+        for entity in em.get_entities_with_components(components::Energy::get_component_type()) {
+            let actions = self.actions(em, entity);
 
-                new_energy.amount += new_speed;
+            if actions == 0 {
+                if let Some(walk) = get_component!(mut, em, entity, components::Walk) {
+                    walk.dx = 0;
+                    walk.dy = 0;
+                }
             }
 
-            debug!("Added turn to new entity {:?}", new_turn_entity);
+            em.add_component(entity, components::Turn { actions: actions });
         }
     }
 }
 
-impl System for TurnSystem {
-    fn mount(&mut self, em: &mut EntityManager) {
-        // Add all entities with speed and energy
-        let living_entities = em.get_entities_with_components(components::Energy::get_component_type());
-
-        for entity in living_entities {
-            self.entities.borrow_mut().push_back(entity);
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{StatusEffect, StatusKind};
+
+    fn walker(em: &mut EntityManager, speed: i32) -> Entity {
+        let entity = em.create_entity();
+        em.add_component(entity, components::Energy { amount: 0 });
+        em.add_component(entity, components::Speed { amount: speed });
+        em.add_component(entity, components::Walk { dx: 1, dy: 0 });
+        entity
     }
 
-    fn process(&self, em: &mut EntityManager) {
-        const turn_length: i32 = 24;
+    #[test]
+    fn it_should_hand_out_actions_by_speed() {
+        let mut em = EntityManager::new();
 
-        // Get all entities with energy
-        let entities_with_energy = em.get_entities_with_components(components::Energy::get_component_type());
+        let normal = walker(&mut em, 10);
+        let quick = walker(&mut em, 12);
+        let hasted = walker(&mut em, 10);
+        let slowed = walker(&mut em, 10);
 
-        for entity in entities_with_energy {
-            // 1. Subtract each entity's speed from it's energy
-            {
-                let speed = { get_component!(em, entity, components::Speed).cloned() };
-                let energy = get_component!(mut, em, entity, components::Energy).unwrap();
+        status::apply_status(&mut em, hasted, StatusEffect { kind: StatusKind::Hasted, turns: 10, magnitude: 0, source: None });
+        status::apply_status(&mut em, slowed, StatusEffect { kind: StatusKind::Slowed, turns: 10, magnitude: 0, source: None });
 
-                if let Some(speed) = speed {
-                    energy.amount -= speed.amount;
-                }
+        let entities = [normal, quick, hasted, slowed];
+        let mut actions = vec![Vec::new(); entities.len()];
+
+        for _ in 0..5 {
+            for entity in entities.iter() {
+                get_component!(mut, em, *entity, components::Walk).unwrap().dx = 1;
             }
 
-            // 2. If energy is less than 0, give the entity a move
-            {
-                let energy = get_component!(mut, em, entity, components::Energy).unwrap();
+            TurnSystem.process(&mut em);
 
-                if energy.amount < 0 {
-                    // Move entity to back of line
-                    energy.amount += turn_length;
-                }
+            for (index, entity) in entities.iter().enumerate() {
+                actions[index].push(get_component!(em, *entity, components::Turn).unwrap().actions);
             }
         }
+
+        assert_eq!(actions[0], vec![1, 1, 1, 1, 1]);
+        assert_eq!(actions[1], vec![1, 1, 1, 1, 2]);
+        assert_eq!(actions[2], vec![2, 2, 2, 2, 2]);
+        assert_eq!(actions[3], vec![0, 1, 0, 1, 0]);
+
+        // Too slow to act this turn, the walk is dropped
+        assert_eq!(get_component!(em, slowed, components::Walk).unwrap().dx, 0);
+        assert_eq!(get_component!(em, normal, components::Walk).unwrap().dx, 1);
+        assert!(!can_act(&em, slowed));
+        assert!(can_act(&em, normal));
     }
-}
\ No newline at end of file
+}
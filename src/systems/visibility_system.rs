@@ -0,0 +1,35 @@
+use super::{System};
+use crate::entities::{EntityManager};
+use crate::components::{Component, self};
+use crate::map::Map;
+use crate::interrupt;
+
+const SIGHT_RADIUS: i32 = interrupt::SIGHT_RADIUS as i32;
+
+/**
+ * Reveals the tiles in the player's field of view
+ */
+#[derive(Debug)]
+pub struct VisibilitySystem;
+
+impl System for VisibilitySystem {
+    fn process(&self, em: &mut EntityManager) {
+        let map_entity = match em.get_entity_by_name("Map") {
+            Some(entity) => entity,
+            None => return
+        };
+
+        let viewers: Vec<components::Position> = em.get_entities_with_components(components::Player::get_component_type())
+            .iter()
+            .filter_map(|entity| get_component!(em, *entity, components::Position).cloned())
+            .collect();
+
+        let map = get_component!(mut, em, map_entity, Map).unwrap();
+
+        for viewer in viewers {
+            for (x, y) in map.field_of_view((viewer.x, viewer.y), SIGHT_RADIUS) {
+                map.reveal(x, y);
+            }
+        }
+    }
+}
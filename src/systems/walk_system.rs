@@ -1,6 +1,9 @@
 use super::{System};
 use crate::entities::*;
 use crate::components::{Component, self};
+use crate::menu;
+use crate::interrupt;
+use crate::targeting;
 
 /**
  * Reads Input components and check if they have any input commands
@@ -17,14 +20,18 @@ impl System for WalkSystem {
 
         // Get their position components
         for entity in input_entities {
+            // Aiming or picking a spell, the keys work the menu
+            if menu::in_menu(em, entity) {
+                continue;
+            }
+
             let input_component = get_component!(em, entity, components::Input).unwrap();
             
-            let (dx, dy) = match input_component.input {
-                119 => (0, -1),             // w
-                100 => (1, 0),              // d
-                115 => (0, 1),                    // s
-                97 => (-1, 0),         // a
-                _ => (0, 0),
+            let (dx, dy) = match targeting::nudge(input_component.input) {
+                Some(step) => step,
+                // Exploring or traveling, the step already picked stands
+                None if interrupt::busy(em, entity) => continue,
+                None => (0, 0)
             };
 
             // Check if there are any walk commands
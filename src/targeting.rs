@@ -0,0 +1,162 @@
+// Aiming cursors, shared by everything that picks a tile
+
+use super::{Entity, EntityManager, Component, components};
+use components::{Targeting, TargetMode};
+use crate::factions;
+use crate::map::Map;
+
+pub const KEY_TAB: i32 = 9;
+pub const KEY_ENTER: i32 = 10;
+pub const KEY_ESCAPE: i32 = 27;
+
+/// What a key did to a cursor
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Steer {
+    /// Still aiming, the cursor may have moved
+    Aiming,
+    /// Let go at the tile, the cursor is gone
+    Confirm(i32, i32),
+    /// Gave up, the cursor is gone
+    Cancel
+}
+
+// Steps a bresenham line takes between two tiles
+pub fn distance(a: (i32, i32), b: (i32, i32)) -> i32 {
+    (a.0 - b.0).abs().max((a.1 - b.1).abs())
+}
+
+/// Direction of the movement keys
+pub fn nudge(input: i32) -> Option<(i32, i32)> {
+    match input {
+        119 => Some((0, -1)),   // w
+        100 => Some((1, 0)),    // d
+        115 => Some((0, 1)),    // s
+        97 => Some((-1, 0)),    // a
+        _ => None
+    }
+}
+
+/// Hostiles in range and in sight, closest first
+pub fn hostile_targets(em: &EntityManager, entity: Entity, range: usize) -> Vec<(i32, i32)> {
+    let map = match em.get_entity_by_name("Map").and_then(|map_entity| get_component!(em, map_entity, Map)) {
+        Some(map) => map,
+        None => return Vec::new()
+    };
+
+    let origin = match get_component!(em, entity, components::Position) {
+        Some(position) => (position.x, position.y),
+        None => return Vec::new()
+    };
+
+    let mut targets: Vec<(i32, i32)> = em.get_entities_with_components(components::Health::get_component_type())
+        .into_iter()
+        .filter(|other| *other != entity && factions::is_hostile(em, entity, *other))
+        .filter_map(|other| get_component!(em, other, components::Position).map(|position| (position.x, position.y)))
+        .filter(|target| distance(origin, *target) <= range as i32 && map.has_line_of_sight(origin, *target))
+        .collect();
+
+    targets.sort_by_key(|target| distance(origin, *target));
+
+    targets
+}
+
+/// Put a cursor on the closest hostile in range, or on the entity itself when there's none
+pub fn start(em: &mut EntityManager, entity: Entity, mode: TargetMode, range: usize, at_hostile: bool) {
+    let position = match get_component!(em, entity, components::Position) {
+        Some(position) => (position.x, position.y),
+        None => return
+    };
+
+    let (x, y) = match at_hostile {
+        true => hostile_targets(em, entity, range).first().cloned(),
+        false => None
+    }.unwrap_or(position);
+
+    em.add_component(entity, Targeting { x: x, y: y, mode: mode, range: range });
+}
+
+/**
+ * Work the cursor with the keys every kind of aiming shares
+ * The movement keys nudge it, tab cycles hostiles in range, escape gives up
+ * and enter or the caller's own key lets go
+ */
+pub fn steer(em: &mut EntityManager, entity: Entity, input: i32, confirm: i32) -> Steer {
+    let cursor = match get_component!(em, entity, Targeting) {
+        Some(cursor) => *cursor,
+        None => return Steer::Cancel
+    };
+
+    if input == KEY_ENTER || input == confirm {
+        em.remove_component(entity, Targeting::get_component_type());
+        return Steer::Confirm(cursor.x, cursor.y);
+    }
+
+    let next = match input {
+        KEY_ESCAPE => {
+            em.remove_component(entity, Targeting::get_component_type());
+            return Steer::Cancel;
+        }
+        KEY_TAB => {
+            let targets = hostile_targets(em, entity, cursor.range);
+
+            match targets.iter().position(|target| *target == (cursor.x, cursor.y)) {
+                Some(index) => Some(targets[(index + 1) % targets.len()]),
+                None => targets.first().cloned()
+            }
+        }
+        _ => nudge(input).map(|(dx, dy)| (cursor.x + dx, cursor.y + dy))
+    };
+
+    if let Some((x, y)) = next {
+        em.add_component(entity, Targeting { x: x, y: y, ..cursor });
+    }
+
+    Steer::Aiming
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapBuilder;
+    use crate::types::Rect;
+
+    #[test]
+    fn it_should_steer_cycle_and_confirm() {
+        let mut em = EntityManager::new();
+
+        let map_entity = em.create_entity();
+        em.set_entity_name(map_entity, "Map");
+        em.add_component(map_entity, MapBuilder::new(20, 20).create_room(&Rect::new(0, 0, 19, 19)).build());
+
+        let player = em.create_entity();
+        em.add_component(player, components::Faction { name: "player".to_string() });
+        em.add_component(player, components::Position { x: 2, y: 2 });
+
+        for x in &[4, 6] {
+            let goblin = em.create_entity();
+            em.add_component(goblin, components::Faction { name: "goblins".to_string() });
+            em.add_component(goblin, components::Health { health: 8, max_health: 8 });
+            em.add_component(goblin, components::Position { x: *x, y: 2 });
+        }
+
+        // Allies are never picked
+        let ally = em.create_entity();
+        em.add_component(ally, components::Faction { name: "player".to_string() });
+        em.add_component(ally, components::Health { health: 8, max_health: 8 });
+        em.add_component(ally, components::Position { x: 3, y: 2 });
+
+        start(&mut em, player, TargetMode::Travel, 8, true);
+        assert_eq!(get_component!(em, player, Targeting).map(|cursor| (cursor.x, cursor.y)), Some((4, 2)));
+
+        assert_eq!(steer(&mut em, player, KEY_TAB, 't' as i32), Steer::Aiming);
+        assert_eq!(steer(&mut em, player, 115, 't' as i32), Steer::Aiming);
+        assert_eq!(get_component!(em, player, Targeting).map(|cursor| (cursor.x, cursor.y)), Some((6, 3)));
+
+        assert_eq!(steer(&mut em, player, 't' as i32, 't' as i32), Steer::Confirm(6, 3));
+        assert!(get_component!(em, player, Targeting).is_none());
+
+        start(&mut em, player, TargetMode::Travel, 8, false);
+        assert_eq!(steer(&mut em, player, KEY_ESCAPE, 't' as i32), Steer::Cancel);
+        assert!(get_component!(em, player, Targeting).is_none());
+    }
+}
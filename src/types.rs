@@ -24,6 +24,15 @@ impl Color {
     }
 }
 
+/// Which screen the game loop is driving
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GameState {
+    MainMenu,
+    Running,
+    /// The player died, systems stop and the death screen shows
+    GameOver
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct Rect {
     pub x1: i32,